@@ -0,0 +1,211 @@
+use crate::{GroqError, SpeechToTextRequest};
+use std::path::Path;
+
+/// The default maximum size of an audio file sent for transcription, 25 MiB, the limit of the
+/// Groq free tier. Change it with `with_max_audio_size` on paid tiers or other servers.
+pub const MAX_AUDIO_FILE_BYTES: usize = 25 * 1024 * 1024;
+
+/// Represents the audio containers accepted by speech-to-text models.
+///
+/// - `Wav`: A RIFF WAVE file.
+/// - `Flac`: A FLAC file.
+/// - `Mp3`: An MP3 file, with or without an ID3 tag.
+/// - `Mp4`: An MP4 or M4A file.
+/// - `Ogg`: An Ogg file, such as Opus or Vorbis audio.
+/// - `Webm`: A WebM file, as recorded by browsers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AudioFormat {
+    Wav,
+    Flac,
+    Mp3,
+    Mp4,
+    Ogg,
+    Webm,
+}
+
+impl AudioFormat {
+    /// Detects the container of an audio file from its leading bytes, or `None` if it is not supported.
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WAVE" {
+            Some(AudioFormat::Wav)
+        } else if bytes.starts_with(b"fLaC") {
+            Some(AudioFormat::Flac)
+        } else if bytes.starts_with(b"ID3") || is_mpeg_audio_frame(bytes) {
+            Some(AudioFormat::Mp3)
+        } else if bytes.len() >= 8 && &bytes[4..8] == b"ftyp" {
+            Some(AudioFormat::Mp4)
+        } else if bytes.starts_with(b"OggS") {
+            Some(AudioFormat::Ogg)
+        } else if bytes.starts_with(&[0x1A, 0x45, 0xDF, 0xA3]) {
+            Some(AudioFormat::Webm)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the format of a file named `file_name` from its extension, or `None` if it is not supported.
+    pub fn from_file_name(file_name: &str) -> Option<Self> {
+        let extension = Path::new(file_name).extension()?.to_str()?;
+        match extension.to_ascii_lowercase().as_str() {
+            "wav" => Some(AudioFormat::Wav),
+            "flac" => Some(AudioFormat::Flac),
+            "mp3" | "mpeg" | "mpga" => Some(AudioFormat::Mp3),
+            "mp4" | "m4a" => Some(AudioFormat::Mp4),
+            "ogg" | "opus" => Some(AudioFormat::Ogg),
+            "webm" => Some(AudioFormat::Webm),
+            _ => None,
+        }
+    }
+
+    /// Returns the usual file extension of the format.
+    pub fn extension(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "wav",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Mp4 => "m4a",
+            AudioFormat::Ogg => "ogg",
+            AudioFormat::Webm => "webm",
+        }
+    }
+
+    /// Returns the MIME type of the format.
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Flac => "audio/flac",
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Mp4 => "audio/mp4",
+            AudioFormat::Ogg => "audio/ogg",
+            AudioFormat::Webm => "audio/webm",
+        }
+    }
+}
+
+/// Returns whether `bytes` start with the header of an MPEG layer I-III frame, as MP3 files
+/// without an ID3 tag do. AAC streams, whose layer bits are zero, do not match.
+fn is_mpeg_audio_frame(bytes: &[u8]) -> bool {
+    bytes.len() >= 2 && bytes[0] == 0xFF && bytes[1] & 0xE0 == 0xE0 && bytes[1] & 0x06 != 0
+}
+
+impl SpeechToTextRequest {
+    /// Returns the format of the audio file, sniffed from its first bytes, or else taken from the
+    /// extension of `file_name`.
+    pub fn audio_format(&self) -> Option<AudioFormat> {
+        AudioFormat::sniff(self.file_head()).or_else(|| {
+            self.file_name
+                .as_deref()
+                .and_then(AudioFormat::from_file_name)
+        })
+    }
+
+    /// Returns the file name the audio is uploaded with: `file_name` if set, or else `audio`
+    /// with the extension of its format.
+    pub(crate) fn upload_file_name(&self) -> String {
+        match (&self.file_name, self.audio_format()) {
+            (Some(file_name), _) => file_name.clone(),
+            (None, Some(format)) => format!("audio.{}", format.extension()),
+            (None, None) => "audio".to_string(),
+        }
+    }
+}
+
+/// Checks that the audio file of `request` can be uploaded, before sending it.
+///
+/// # Errors
+///
+/// Returns `GroqError::InvalidInput` if the file is empty, larger than `max_size`, or in a format
+/// that is not supported, or if the request has both a file and a `url`. Requests for a `url` are
+/// not checked further, since the API downloads the file itself.
+pub(crate) fn check_upload(
+    request: &SpeechToTextRequest,
+    max_size: usize,
+) -> Result<(), GroqError> {
+    let has_file = request.source.is_some() || !request.file.is_empty();
+    match (&request.url, has_file) {
+        (Some(_), true) => {
+            return Err(GroqError::InvalidInput(
+                "a request can send either an audio file or a url, not both".to_string(),
+            ))
+        }
+        (Some(_), false) => return Ok(()),
+        (None, _) => {}
+    }
+    let len = request.file_len();
+    if len == Some(0) {
+        return Err(GroqError::InvalidInput("audio file is empty".to_string()));
+    }
+    if let Some(len) = len.filter(|&len| len > max_size as u64) {
+        return Err(GroqError::InvalidInput(format!(
+            "audio file is {} bytes, above the limit of {} bytes",
+            len, max_size
+        )));
+    }
+    if request.audio_format().is_none() {
+        return Err(GroqError::InvalidInput(
+            "unsupported audio format, expected WAV, FLAC, MP3, MP4/M4A, Ogg or WebM".to_string(),
+        ));
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sniff() {
+        let cases: [(&[u8], AudioFormat); 7] = [
+            (b"RIFF\x24\0\0\0WAVEfmt ", AudioFormat::Wav),
+            (b"fLaC\0\0\0\x22", AudioFormat::Flac),
+            (b"ID3\x04\0\0\0\0", AudioFormat::Mp3),
+            (&[0xFF, 0xFB, 0x90, 0x64], AudioFormat::Mp3),
+            (b"\0\0\0\x20ftypM4A ", AudioFormat::Mp4),
+            (b"OggS\0\x02\0\0", AudioFormat::Ogg),
+            (&[0x1A, 0x45, 0xDF, 0xA3, 0x9F], AudioFormat::Webm),
+        ];
+        for (bytes, format) in cases {
+            assert_eq!(AudioFormat::sniff(bytes), Some(format));
+        }
+        // An AAC stream, a text file and a truncated header.
+        assert_eq!(AudioFormat::sniff(&[0xFF, 0xF1, 0x50, 0x80]), None);
+        assert_eq!(AudioFormat::sniff(b"hello"), None);
+        assert_eq!(AudioFormat::sniff(b"RIFF"), None);
+        assert_eq!(
+            AudioFormat::from_file_name("memo.M4A"),
+            Some(AudioFormat::Mp4)
+        );
+        assert_eq!(AudioFormat::from_file_name("memo.txt"), None);
+    }
+
+    #[test]
+    fn test_check_upload() {
+        let ogg = SpeechToTextRequest::new(b"OggS\0\x02\0\0".to_vec());
+        assert_eq!(ogg.upload_file_name(), "audio.ogg");
+        assert!(check_upload(&ogg, MAX_AUDIO_FILE_BYTES).is_ok());
+        assert!(matches!(
+            check_upload(&ogg, 4),
+            Err(GroqError::InvalidInput(_))
+        ));
+
+        let unknown = SpeechToTextRequest::new(b"\0\0\0\0".to_vec());
+        assert_eq!(unknown.upload_file_name(), "audio");
+        assert!(check_upload(&unknown, MAX_AUDIO_FILE_BYTES).is_err());
+        assert!(check_upload(&SpeechToTextRequest::new(Vec::new()), MAX_AUDIO_FILE_BYTES).is_err());
+
+        // A file name vouches for formats that cannot be sniffed, and is kept as is.
+        let named = unknown.file_name("memo.mpga");
+        assert_eq!(named.audio_format(), Some(AudioFormat::Mp3));
+        assert_eq!(named.upload_file_name(), "memo.mpga");
+        assert!(check_upload(&named, MAX_AUDIO_FILE_BYTES).is_ok());
+
+        // The API downloads URLs itself, but a request cannot send a file as well.
+        let remote = SpeechToTextRequest::from_url("https://example.com/memo.m4a");
+        assert!(check_upload(&remote, 4).is_ok());
+        let both = SpeechToTextRequest {
+            url: remote.url,
+            ..ogg
+        };
+        assert!(check_upload(&both, MAX_AUDIO_FILE_BYTES).is_err());
+    }
+}
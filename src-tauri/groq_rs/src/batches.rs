@@ -0,0 +1,561 @@
+//! Batches of requests processed asynchronously by the Groq API at a lower cost.
+//!
+//! A batch reads its requests from a JSONL file uploaded with the `batch` purpose, and writes
+//! the responses to an output file, and the requests that failed to an error file. The clients'
+//! `chat_completion_batch` method does all of this for a list of chat completion requests.
+
+use crate::codec;
+use crate::{
+    ApiErrorDetails, ChatCompletionRequest, ChatCompletionResponse, GroqError, RequestOptions,
+};
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+/// The endpoint of batches of chat completion requests.
+pub const CHAT_COMPLETIONS_ENDPOINT: &str = "/v1/chat/completions";
+
+/// The default time within which a batch must be processed.
+pub const DEFAULT_COMPLETION_WINDOW: &str = "24h";
+
+/// The number of polls in a row a batch may report a status this crate does not know, before
+/// `chat_completion_batch` stops waiting for it.
+const MAX_UNKNOWN_POLLS: u32 = 10;
+
+#[derive(Debug, Clone, PartialEq, Serialize)]
+/// Represents a request to create a batch.
+///
+/// - `input_file_id`: The id of the uploaded JSONL file holding the requests.
+/// - `endpoint`: The endpoint every request is sent to, such as `/v1/chat/completions`.
+/// - `completion_window`: The time within which the batch must be processed, from `24h` to `7d`.
+/// - `metadata`: Key-value pairs attached to the batch.
+pub struct CreateBatchRequest {
+    pub input_file_id: String,
+    pub endpoint: String,
+    pub completion_window: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+impl CreateBatchRequest {
+    /// Creates a request for a batch of chat completions read from the given file.
+    ///
+    /// # Arguments
+    ///
+    /// * `input_file_id` - The id of the uploaded JSONL file holding the requests.
+    pub fn new(input_file_id: &str) -> Self {
+        Self {
+            input_file_id: input_file_id.to_string(),
+            endpoint: CHAT_COMPLETIONS_ENDPOINT.to_string(),
+            completion_window: DEFAULT_COMPLETION_WINDOW.to_string(),
+            metadata: None,
+        }
+    }
+
+    /// Sets the time within which the batch must be processed, such as `24h` or `7d`.
+    pub fn completion_window(mut self, completion_window: &str) -> Self {
+        self.completion_window = completion_window.to_string();
+        self
+    }
+
+    /// Sets the key-value pairs attached to the batch.
+    pub fn metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Represents the stage a batch is in.
+///
+/// - `Validating`: The input file is being validated.
+/// - `Failed`: The input file was rejected.
+/// - `InProgress`: The requests are being processed.
+/// - `Finalizing`: The output and error files are being written.
+/// - `Completed`: Every request was processed.
+/// - `Expired`: The completion window ended before every request was processed.
+/// - `Cancelling`: The batch is being cancelled.
+/// - `Cancelled`: The batch was cancelled.
+/// - `Unknown`: A status this crate does not know about yet.
+pub enum BatchStatus {
+    Validating,
+    Failed,
+    InProgress,
+    Finalizing,
+    Completed,
+    Expired,
+    Cancelling,
+    Cancelled,
+    #[serde(other)]
+    Unknown,
+}
+
+impl BatchStatus {
+    /// Returns whether the batch will not change anymore.
+    pub fn is_terminal(&self) -> bool {
+        matches!(
+            self,
+            BatchStatus::Failed
+                | BatchStatus::Completed
+                | BatchStatus::Expired
+                | BatchStatus::Cancelled
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Serialize, Deserialize)]
+/// Represents the progress of a batch.
+///
+/// - `total`: The number of requests in the batch.
+/// - `completed`: The number of requests processed successfully.
+/// - `failed`: The number of requests that failed.
+pub struct BatchRequestCounts {
+    #[serde(default)]
+    pub total: u64,
+    #[serde(default)]
+    pub completed: u64,
+    #[serde(default)]
+    pub failed: u64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents an error that made a batch fail, such as an invalid line of the input file.
+///
+/// - `code`: A machine-readable code for the error.
+/// - `message`: A human-readable description of the error.
+/// - `param`: The parameter that caused the error, if any.
+/// - `line`: The line of the input file that caused the error, if any.
+pub struct BatchError {
+    #[serde(default)]
+    pub code: Option<String>,
+    #[serde(default)]
+    pub message: Option<String>,
+    #[serde(default)]
+    pub param: Option<String>,
+    #[serde(default)]
+    pub line: Option<u64>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents the errors of a failed batch.
+///
+/// - `object`: The object type, always `list`.
+/// - `data`: The errors.
+pub struct BatchErrors {
+    #[serde(default)]
+    pub object: String,
+    #[serde(default)]
+    pub data: Vec<BatchError>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents a batch, as returned by the `/batches` endpoint.
+///
+/// - `id`: The id of the batch.
+/// - `object`: The object type, always `batch`.
+/// - `endpoint`: The endpoint every request is sent to.
+/// - `errors`: The errors that made the batch fail, if any.
+/// - `input_file_id`: The id of the file holding the requests.
+/// - `completion_window`: The time within which the batch must be processed.
+/// - `status`: The stage the batch is in.
+/// - `output_file_id`: The id of the file holding the successful responses, once available.
+/// - `error_file_id`: The id of the file holding the failed requests, once available.
+/// - `created_at`: The Unix timestamp of when the batch was created.
+/// - `in_progress_at`: The Unix timestamp of when processing started.
+/// - `expires_at`: The Unix timestamp of when the completion window ends.
+/// - `finalizing_at`: The Unix timestamp of when finalizing started.
+/// - `completed_at`: The Unix timestamp of when the batch completed.
+/// - `failed_at`: The Unix timestamp of when the batch failed.
+/// - `expired_at`: The Unix timestamp of when the batch expired.
+/// - `cancelling_at`: The Unix timestamp of when cancellation started.
+/// - `cancelled_at`: The Unix timestamp of when the batch was cancelled.
+/// - `request_counts`: The progress of the batch.
+/// - `metadata`: The key-value pairs attached to the batch.
+pub struct Batch {
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    #[serde(default)]
+    pub endpoint: String,
+    #[serde(default)]
+    pub errors: Option<BatchErrors>,
+    #[serde(default)]
+    pub input_file_id: String,
+    #[serde(default)]
+    pub completion_window: String,
+    pub status: BatchStatus,
+    #[serde(default)]
+    pub output_file_id: Option<String>,
+    #[serde(default)]
+    pub error_file_id: Option<String>,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub in_progress_at: Option<u64>,
+    #[serde(default)]
+    pub expires_at: Option<u64>,
+    #[serde(default)]
+    pub finalizing_at: Option<u64>,
+    #[serde(default)]
+    pub completed_at: Option<u64>,
+    #[serde(default)]
+    pub failed_at: Option<u64>,
+    #[serde(default)]
+    pub expired_at: Option<u64>,
+    #[serde(default)]
+    pub cancelling_at: Option<u64>,
+    #[serde(default)]
+    pub cancelled_at: Option<u64>,
+    #[serde(default)]
+    pub request_counts: Option<BatchRequestCounts>,
+    #[serde(default)]
+    pub metadata: Option<HashMap<String, String>>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the response of the `/batches` endpoint.
+///
+/// - `object`: The object type, always `list`.
+/// - `data`: The batches of the account.
+pub struct BatchList {
+    pub object: String,
+    pub data: Vec<Batch>,
+}
+
+/// Options of `chat_completion_batch`.
+///
+/// - `completion_window`: The time within which the batch must be processed. Defaults to `24h`.
+/// - `poll_interval`: How long to wait between two checks of the status of the batch. Defaults to 30 seconds.
+/// - `max_wait`: How long to wait for the batch to end before failing, leaving it running. Defaults
+///   to no limit, the API expiring the batch at the end of its completion window.
+/// - `metadata`: Key-value pairs attached to the batch.
+/// - `request_options`: The cancellation token and deadline of the whole call, polls included.
+#[derive(Debug, Clone)]
+pub struct BatchOptions {
+    pub completion_window: String,
+    pub poll_interval: Duration,
+    pub max_wait: Option<Duration>,
+    pub metadata: Option<HashMap<String, String>>,
+    pub request_options: RequestOptions,
+}
+
+impl Default for BatchOptions {
+    fn default() -> Self {
+        Self {
+            completion_window: DEFAULT_COMPLETION_WINDOW.to_string(),
+            poll_interval: Duration::from_secs(30),
+            max_wait: None,
+            metadata: None,
+            request_options: RequestOptions::default(),
+        }
+    }
+}
+
+impl BatchOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the time within which the batch must be processed, such as `24h` or `7d`.
+    pub fn completion_window(mut self, completion_window: &str) -> Self {
+        self.completion_window = completion_window.to_string();
+        self
+    }
+
+    /// Sets how long to wait between two checks of the status of the batch.
+    pub fn poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// Sets how long to wait for the batch to end before failing.
+    pub fn max_wait(mut self, max_wait: Duration) -> Self {
+        self.max_wait = Some(max_wait);
+        self
+    }
+
+    /// Sets the key-value pairs attached to the batch.
+    pub fn metadata(mut self, metadata: HashMap<String, String>) -> Self {
+        self.metadata = Some(metadata);
+        self
+    }
+
+    /// Sets the cancellation token and deadline of the call.
+    pub fn request_options(mut self, request_options: RequestOptions) -> Self {
+        self.request_options = request_options;
+        self
+    }
+
+    /// Builds the request creating a batch from the given input file.
+    pub(crate) fn create_request(&self, input_file_id: &str) -> CreateBatchRequest {
+        CreateBatchRequest {
+            metadata: self.metadata.clone(),
+            ..CreateBatchRequest::new(input_file_id).completion_window(&self.completion_window)
+        }
+    }
+}
+
+/// Tracks the polls of a batch, to tell `chat_completion_batch` when to stop.
+pub(crate) struct BatchPoll {
+    started: Instant,
+    max_wait: Option<Duration>,
+    unknown_polls: u32,
+}
+
+impl BatchPoll {
+    pub fn new(options: &BatchOptions) -> Self {
+        Self {
+            started: Instant::now(),
+            max_wait: options.max_wait,
+            unknown_polls: 0,
+        }
+    }
+
+    /// Returns whether the batch has ended, or an error if it should not be waited for anymore.
+    pub fn is_done(&mut self, batch: &Batch) -> Result<bool, GroqError> {
+        if batch.status.is_terminal() {
+            return Ok(true);
+        }
+        if batch.status == BatchStatus::Unknown {
+            self.unknown_polls += 1;
+            if self.unknown_polls >= MAX_UNKNOWN_POLLS {
+                return Err(batch_unfinished(batch, "its status is unknown"));
+            }
+        } else {
+            self.unknown_polls = 0;
+        }
+        match self.max_wait {
+            Some(max_wait) if self.started.elapsed() >= max_wait => Err(batch_unfinished(
+                batch,
+                &format!("it did not end within {:?}", max_wait),
+            )),
+            _ => Ok(false),
+        }
+    }
+}
+
+/// Returns the `custom_id` of the request at the given index of a batch.
+fn custom_id(index: usize) -> String {
+    format!("request-{}", index)
+}
+
+/// Encodes chat completion requests as the JSONL input file of a batch.
+pub(crate) fn encode_chat_completions(requests: &[ChatCompletionRequest]) -> Vec<u8> {
+    let mut input = Vec::new();
+    for (index, request) in requests.iter().enumerate() {
+        let line = json!({
+            "custom_id": custom_id(index),
+            "method": "POST",
+            "url": CHAT_COMPLETIONS_ENDPOINT,
+            "body": codec::chat_completion_body(request, false),
+        });
+        serde_json::to_writer(&mut input, &line).expect("JSON values always serialize");
+        input.push(b'\n');
+    }
+    input
+}
+
+/// The results of a batch of chat completions, in the order of their requests.
+pub(crate) struct BatchResults {
+    results: Vec<Option<Result<ChatCompletionResponse, GroqError>>>,
+}
+
+impl BatchResults {
+    pub fn new(count: usize) -> Self {
+        Self {
+            results: (0..count).map(|_| None).collect(),
+        }
+    }
+
+    /// Reads the lines of an output or error file, matching them to their requests by `custom_id`.
+    pub fn read(&mut self, content: &[u8]) {
+        for line in content.split(|&b| b == b'\n') {
+            if line.iter().all(u8::is_ascii_whitespace) {
+                continue;
+            }
+            let line: Value = match serde_json::from_slice(line) {
+                Ok(line) => line,
+                Err(e) => {
+                    log::warn!("skipping a batch result that is not valid JSON: {}", e);
+                    continue;
+                }
+            };
+            let index = line["custom_id"]
+                .as_str()
+                .and_then(|id| id.strip_prefix("request-"))
+                .and_then(|index| index.parse::<usize>().ok());
+            match index.and_then(|index| self.results.get_mut(index)) {
+                Some(slot) => *slot = Some(decode_result(&line)),
+                None => log::warn!(
+                    "skipping a batch result with unknown custom_id {}",
+                    line["custom_id"]
+                ),
+            }
+        }
+    }
+
+    /// Returns the results, with an error for every request the batch did not process.
+    pub fn finish(self, batch: &Batch) -> Vec<Result<ChatCompletionResponse, GroqError>> {
+        self.results
+            .into_iter()
+            .map(|result| {
+                result.unwrap_or_else(|| {
+                    Err(GroqError::ApiError(Box::new(ApiErrorDetails {
+                        message: format!(
+                            "Batch {} ended with status {:?} before processing this request",
+                            batch.id, batch.status
+                        ),
+                        code: Some("batch_request_not_processed".to_string()),
+                        ..ApiErrorDetails::default()
+                    })))
+                })
+            })
+            .collect()
+    }
+}
+
+/// Decodes a line of an output or error file.
+fn decode_result(line: &Value) -> Result<ChatCompletionResponse, GroqError> {
+    let error = &line["error"];
+    if !error.is_null() {
+        return Err(GroqError::ApiError(Box::new(ApiErrorDetails {
+            message: error["message"]
+                .as_str()
+                .unwrap_or("Unknown error")
+                .to_string(),
+            code: error["code"].as_str().map(str::to_string),
+            ..ApiErrorDetails::default()
+        })));
+    }
+    let response = &line["response"];
+    let status = response["status_code"]
+        .as_u64()
+        .and_then(|status| StatusCode::from_u16(status as u16).ok())
+        .unwrap_or(StatusCode::OK);
+    if !status.is_success() {
+        let mut headers = HeaderMap::new();
+        if let Some(request_id) = response["request_id"]
+            .as_str()
+            .and_then(|id| HeaderValue::from_str(id).ok())
+        {
+            headers.insert("x-request-id", request_id);
+        }
+        return Err(codec::api_error(
+            Some(status),
+            &headers,
+            Some(response["body"].clone()),
+        ));
+    }
+    Ok(serde_json::from_value(response["body"].clone())?)
+}
+
+/// Builds the error for a batch that failed as a whole.
+pub(crate) fn batch_failed(batch: &Batch) -> GroqError {
+    let errors: Vec<String> = batch
+        .errors
+        .iter()
+        .flat_map(|errors| &errors.data)
+        .map(|error| match (error.line, &error.message) {
+            (Some(line), Some(message)) => format!("line {}: {}", line, message),
+            (None, Some(message)) => message.clone(),
+            _ => error.code.clone().unwrap_or_default(),
+        })
+        .collect();
+    GroqError::ApiError(Box::new(ApiErrorDetails {
+        message: format!("Batch {} failed: {}", batch.id, errors.join("; ")),
+        code: Some("batch_failed".to_string()),
+        ..ApiErrorDetails::default()
+    }))
+}
+
+/// Builds the error for a batch that is no longer waited for, although it may still be running.
+fn batch_unfinished(batch: &Batch, reason: &str) -> GroqError {
+    GroqError::ApiError(Box::new(ApiErrorDetails {
+        message: format!("Stopped waiting for batch {}: {}", batch.id, reason),
+        code: Some("batch_unfinished".to_string()),
+        ..ApiErrorDetails::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatCompletionMessage, ChatCompletionRoles};
+
+    #[test]
+    fn test_results_are_matched_to_requests() {
+        let requests: Vec<_> = ["a", "b", "c"]
+            .iter()
+            .map(|content| {
+                ChatCompletionRequest::new(
+                    "llama-3.1-8b-instant",
+                    vec![ChatCompletionMessage::new(
+                        ChatCompletionRoles::User,
+                        content,
+                    )],
+                )
+            })
+            .collect();
+        let input = String::from_utf8(encode_chat_completions(&requests)).unwrap();
+        let lines: Vec<Value> = input
+            .lines()
+            .map(|line| serde_json::from_str(line).unwrap())
+            .collect();
+        assert_eq!(lines.len(), 3);
+        assert_eq!(lines[1]["custom_id"], "request-1");
+        assert_eq!(lines[1]["url"], CHAT_COMPLETIONS_ENDPOINT);
+        assert_eq!(lines[1]["body"]["messages"][0]["content"], "b");
+
+        let output = json!({
+            "id": "batch_req_1",
+            "custom_id": "request-2",
+            "response": {
+                "status_code": 200,
+                "request_id": "req_2",
+                "body": {
+                    "id": "chatcmpl-2",
+                    "model": "llama-3.1-8b-instant",
+                    "choices": [{ "index": 0, "message": { "role": "assistant", "content": "C" }, "finish_reason": "stop" }]
+                }
+            },
+            "error": null
+        });
+        let errors = json!({
+            "id": "batch_req_0",
+            "custom_id": "request-0",
+            "response": {
+                "status_code": 400,
+                "request_id": "req_0",
+                "body": { "error": { "message": "Bad request", "type": "invalid_request_error" } }
+            },
+            "error": null
+        });
+        let batch: Batch = serde_json::from_value(json!({
+            "id": "batch_1",
+            "status": "expired",
+            "request_counts": { "total": 3, "completed": 1, "failed": 1 }
+        }))
+        .unwrap();
+
+        let mut results = BatchResults::new(3);
+        results.read(format!("{}\n", output).as_bytes());
+        results.read(format!("{}\n", errors).as_bytes());
+        let results = results.finish(&batch);
+        let error = results[0].as_ref().unwrap_err();
+        assert!(matches!(error, GroqError::InvalidRequest(_)));
+        assert_eq!(error.request_id(), Some("req_0"));
+        assert!(matches!(&results[1], Err(GroqError::ApiError(_))));
+        assert_eq!(
+            results[2].as_ref().unwrap().choices[0]
+                .message
+                .content
+                .as_deref(),
+            Some("C")
+        );
+    }
+}
@@ -0,0 +1,428 @@
+use crate::codec::DEFAULT_ENDPOINT;
+use crate::MAX_AUDIO_FILE_BYTES;
+use crate::{
+    AsyncGroqClient, AuthScheme, GroqClient, GroqError, Hook, ModelRegistry, RateLimiter,
+    ResponseCache, RetryPolicy,
+};
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, USER_AGENT};
+use reqwest::Proxy;
+use std::collections::HashMap;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// The environment variable holding the API key used when none is set on the builder.
+pub const API_KEY_ENV: &str = "GROQ_API_KEY";
+
+/// The environment variable holding the endpoint used when none is set on the builder.
+pub const BASE_URL_ENV: &str = "GROQ_BASE_URL";
+
+/// A builder for `GroqClient` and `AsyncGroqClient`.
+///
+/// Values that are not set explicitly fall back to the `GROQ_API_KEY` and `GROQ_BASE_URL`
+/// environment variables, then to the defaults of the clients.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::GroqClientBuilder;
+/// use std::time::Duration;
+///
+/// let client = GroqClientBuilder::new()
+///     .api_key("my_api_key")
+///     .timeout(Duration::from_secs(30))
+///     .connect_timeout(Duration::from_secs(5))
+///     .user_agent("dashi/0.1")
+///     .build_async()
+///     .unwrap();
+///```
+#[derive(Debug, Default)]
+pub struct GroqClientBuilder {
+    api_key: Option<String>,
+    auth: Option<AuthScheme>,
+    endpoint: Option<String>,
+    timeout: Option<Duration>,
+    connect_timeout: Option<Duration>,
+    proxy: Option<Proxy>,
+    user_agent: Option<String>,
+    default_headers: Vec<(String, String)>,
+    http_client: Option<reqwest::Client>,
+    blocking_http_client: Option<reqwest::blocking::Client>,
+    retry_policy: Option<RetryPolicy>,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    model_registry: Option<Arc<ModelRegistry>>,
+    strict_responses: bool,
+    hooks: Vec<Arc<dyn Hook>>,
+    response_cache: Option<Arc<ResponseCache>>,
+    model_aliases: HashMap<String, String>,
+    max_audio_size: Option<usize>,
+}
+
+/// The settings shared by both clients once a builder has been validated.
+pub(crate) struct ClientConfig {
+    pub auth: AuthScheme,
+    pub endpoint: String,
+    pub default_headers: HeaderMap,
+    pub timeout: Option<Duration>,
+    pub retry_policy: RetryPolicy,
+    pub model_registry: Option<Arc<ModelRegistry>>,
+    pub strict_responses: bool,
+    pub hooks: Vec<Arc<dyn Hook>>,
+    pub response_cache: Option<Arc<ResponseCache>>,
+    pub model_aliases: HashMap<String, String>,
+    pub max_audio_size: usize,
+}
+
+impl GroqClientBuilder {
+    /// Creates a builder with no settings.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the API key used to authenticate with the Groq API.
+    ///
+    /// # Arguments
+    ///
+    /// * `api_key` - The API key. Defaults to the `GROQ_API_KEY` environment variable.
+    pub fn api_key(mut self, api_key: &str) -> Self {
+        self.api_key = Some(api_key.to_string());
+        self
+    }
+
+    /// Sets how requests are authenticated, for OpenAI-compatible servers other than Groq.
+    ///
+    /// Takes precedence over `api_key`, and no API key is required when set.
+    ///
+    /// # Arguments
+    ///
+    /// * `auth` - The `AuthScheme` to use. Defaults to `AuthScheme::Bearer` with the API key.
+    pub fn auth(mut self, auth: AuthScheme) -> Self {
+        self.auth = Some(auth);
+        self
+    }
+
+    /// Sets the base URL of the Groq API.
+    ///
+    /// # Arguments
+    ///
+    /// * `endpoint` - The base URL. Defaults to the `GROQ_BASE_URL` environment variable, then to <https://api.groq.com/openai/v1>.
+    pub fn endpoint(mut self, endpoint: &str) -> Self {
+        self.endpoint = Some(endpoint.to_string());
+        self
+    }
+
+    /// Sets the timeout for each request, from sending it until its response body has been read.
+    ///
+    /// # Arguments
+    ///
+    /// * `timeout` - The request timeout.
+    pub fn timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = Some(timeout);
+        self
+    }
+
+    /// Sets the timeout for establishing a connection.
+    ///
+    /// Ignored when an HTTP client is injected with `http_client` or `blocking_http_client`.
+    ///
+    /// # Arguments
+    ///
+    /// * `connect_timeout` - The connect timeout.
+    pub fn connect_timeout(mut self, connect_timeout: Duration) -> Self {
+        self.connect_timeout = Some(connect_timeout);
+        self
+    }
+
+    /// Routes every request through a proxy.
+    ///
+    /// Ignored when an HTTP client is injected with `http_client` or `blocking_http_client`.
+    ///
+    /// # Arguments
+    ///
+    /// * `proxy` - The proxy to use.
+    pub fn proxy(mut self, proxy: Proxy) -> Self {
+        self.proxy = Some(proxy);
+        self
+    }
+
+    /// Sets the `User-Agent` header sent with every request.
+    ///
+    /// # Arguments
+    ///
+    /// * `user_agent` - The user agent.
+    pub fn user_agent(mut self, user_agent: &str) -> Self {
+        self.user_agent = Some(user_agent.to_string());
+        self
+    }
+
+    /// Adds a header sent with every request.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the header.
+    /// * `value` - The value of the header.
+    pub fn default_header(mut self, name: &str, value: &str) -> Self {
+        self.default_headers
+            .push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Sets the HTTP client used by `AsyncGroqClient`, instead of building one.
+    ///
+    /// # Arguments
+    ///
+    /// * `http_client` - The `reqwest::Client` to use.
+    pub fn http_client(mut self, http_client: reqwest::Client) -> Self {
+        self.http_client = Some(http_client);
+        self
+    }
+
+    /// Sets the HTTP client used by `GroqClient`, instead of building one.
+    ///
+    /// # Arguments
+    ///
+    /// * `http_client` - The `reqwest::blocking::Client` to use.
+    pub fn blocking_http_client(mut self, http_client: reqwest::blocking::Client) -> Self {
+        self.blocking_http_client = Some(http_client);
+        self
+    }
+
+    /// Sets the policy used to retry failed requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `retry_policy` - The `RetryPolicy` to use.
+    pub fn retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = Some(retry_policy);
+        self
+    }
+
+    /// Sets the client-side rate limiter. Only used by `AsyncGroqClient`.
+    ///
+    /// # Arguments
+    ///
+    /// * `rate_limiter` - The `RateLimiter` to use.
+    pub fn rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Sets the registry used to check requests against the capabilities of their model before sending them.
+    ///
+    /// # Arguments
+    ///
+    /// * `model_registry` - The `ModelRegistry` to use.
+    pub fn model_registry(mut self, model_registry: Arc<ModelRegistry>) -> Self {
+        self.model_registry = Some(model_registry);
+        self
+    }
+
+    /// Sets whether chat completion responses with unknown or missing fields are rejected.
+    ///
+    /// # Arguments
+    ///
+    /// * `strict_responses` - Whether to reject responses that do not match the known schema exactly.
+    pub fn strict_responses(mut self, strict_responses: bool) -> Self {
+        self.strict_responses = strict_responses;
+        self
+    }
+
+    /// Adds a hook that observes or changes every request and response, after any added before.
+    ///
+    /// # Arguments
+    ///
+    /// * `hook` - The `Hook` to add.
+    pub fn hook(mut self, hook: Arc<dyn Hook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Sets the on-disk cache serving the reproducible responses the client has already received.
+    ///
+    /// # Arguments
+    ///
+    /// * `response_cache` - The `ResponseCache` to use.
+    pub fn response_cache(mut self, response_cache: Arc<ResponseCache>) -> Self {
+        self.response_cache = Some(response_cache);
+        self
+    }
+
+    /// Sends requests for the model `alias` to `model` instead.
+    ///
+    /// # Arguments
+    ///
+    /// * `alias` - The model requests are made for, such as `models::WHISPER_LARGE_V3`.
+    /// * `model` - The model the server knows, such as `ggml-large-v3`.
+    pub fn model_alias(mut self, alias: &str, model: &str) -> Self {
+        self.model_aliases
+            .insert(alias.to_string(), model.to_string());
+        self
+    }
+
+    /// Sets the maximum size of the audio files sent for transcription.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_audio_size` - The maximum size in bytes. Defaults to `MAX_AUDIO_FILE_BYTES`.
+    pub fn max_audio_size(mut self, max_audio_size: usize) -> Self {
+        self.max_audio_size = Some(max_audio_size);
+        self
+    }
+
+    /// Builds an `AsyncGroqClient`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidConfig` if no API key is set or a header is invalid, and
+    /// `GroqError::RequestFailed` if the HTTP client could not be built.
+    pub fn build_async(mut self) -> Result<AsyncGroqClient, GroqError> {
+        let http_client = self.http_client.take();
+        let connect_timeout = self.connect_timeout;
+        let proxy = self.proxy.take();
+        let rate_limiter = self.rate_limiter.take();
+        let config = self.config()?;
+
+        let client = match http_client {
+            Some(client) => client,
+            None => {
+                let mut builder = reqwest::Client::builder();
+                if let Some(connect_timeout) = connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                if let Some(proxy) = proxy {
+                    builder = builder.proxy(proxy);
+                }
+                builder.build()?
+            }
+        };
+        Ok(AsyncGroqClient::from_config(config, client, rate_limiter))
+    }
+
+    /// Builds a `GroqClient`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidConfig` if no API key is set or a header is invalid, and
+    /// `GroqError::RequestFailed` if the HTTP client could not be built.
+    pub fn build(mut self) -> Result<GroqClient, GroqError> {
+        let http_client = self.blocking_http_client.take();
+        let connect_timeout = self.connect_timeout;
+        let proxy = self.proxy.take();
+        let config = self.config()?;
+
+        let client = match http_client {
+            Some(client) => client,
+            None => {
+                let mut builder = reqwest::blocking::Client::builder();
+                if let Some(connect_timeout) = connect_timeout {
+                    builder = builder.connect_timeout(connect_timeout);
+                }
+                if let Some(proxy) = proxy {
+                    builder = builder.proxy(proxy);
+                }
+                builder.build()?
+            }
+        };
+        Ok(GroqClient::from_config(config, client))
+    }
+
+    /// Resolves the settings shared by both clients, falling back to the environment.
+    fn config(self) -> Result<ClientConfig, GroqError> {
+        let auth = match self.auth {
+            Some(auth) => auth,
+            None => self
+                .api_key
+                .or_else(|| std::env::var(API_KEY_ENV).ok())
+                .filter(|key| !key.is_empty())
+                .map(AuthScheme::Bearer)
+                .ok_or_else(|| {
+                    GroqError::InvalidConfig(format!(
+                        "no API key set and {} is not defined",
+                        API_KEY_ENV
+                    ))
+                })?,
+        };
+        if !auth.is_valid() {
+            return Err(GroqError::InvalidConfig(
+                "the API key is not a valid header value".to_string(),
+            ));
+        }
+        let endpoint = self
+            .endpoint
+            .or_else(|| std::env::var(BASE_URL_ENV).ok())
+            .filter(|endpoint| !endpoint.is_empty())
+            .unwrap_or_else(|| DEFAULT_ENDPOINT.to_string());
+
+        let mut default_headers = HeaderMap::new();
+        if let Some(user_agent) = self.user_agent {
+            default_headers.insert(USER_AGENT, header_value(&user_agent)?);
+        }
+        for (name, value) in self.default_headers {
+            let name = HeaderName::from_bytes(name.as_bytes())
+                .map_err(|_| GroqError::InvalidConfig(format!("invalid header name: {}", name)))?;
+            default_headers.append(name, header_value(&value)?);
+        }
+
+        Ok(ClientConfig {
+            auth,
+            endpoint: endpoint.trim_end_matches('/').to_string(),
+            default_headers,
+            timeout: self.timeout,
+            retry_policy: self.retry_policy.unwrap_or_default(),
+            model_registry: self.model_registry,
+            strict_responses: self.strict_responses,
+            hooks: self.hooks,
+            response_cache: self.response_cache,
+            model_aliases: self.model_aliases,
+            max_audio_size: self.max_audio_size.unwrap_or(MAX_AUDIO_FILE_BYTES),
+        })
+    }
+}
+
+fn header_value(value: &str) -> Result<HeaderValue, GroqError> {
+    HeaderValue::from_str(value)
+        .map_err(|_| GroqError::InvalidConfig(format!("invalid header value: {}", value)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_config() {
+        let config = GroqClientBuilder::new()
+            .api_key("test_key")
+            .endpoint("http://localhost:8080/v1/")
+            .user_agent("dashi/0.1")
+            .default_header("x-dashi-session", "42")
+            .timeout(Duration::from_secs(5))
+            .config()
+            .unwrap();
+        assert_eq!(config.auth, AuthScheme::Bearer("test_key".to_string()));
+        assert_eq!(config.endpoint, "http://localhost:8080/v1");
+        assert_eq!(config.default_headers[USER_AGENT], "dashi/0.1");
+        assert_eq!(config.default_headers["x-dashi-session"], "42");
+        assert_eq!(config.timeout, Some(Duration::from_secs(5)));
+    }
+
+    #[test]
+    fn test_invalid_config() {
+        let result = GroqClientBuilder::new()
+            .api_key("test_key")
+            .default_header("bad header", "1")
+            .config();
+        assert!(matches!(result, Err(GroqError::InvalidConfig(_))));
+        let result = GroqClientBuilder::new().api_key("").config();
+        assert!(matches!(result, Err(GroqError::InvalidConfig(_))));
+        let result = GroqClientBuilder::new()
+            .auth(AuthScheme::ApiKey("bad\nkey".to_string()))
+            .config();
+        assert!(matches!(result, Err(GroqError::InvalidConfig(_))));
+        let config = GroqClientBuilder::new()
+            .auth(AuthScheme::None)
+            .model_alias("whisper-large-v3", "ggml-large-v3")
+            .config()
+            .unwrap();
+        assert_eq!(config.auth, AuthScheme::None);
+        assert_eq!(config.model_aliases["whisper-large-v3"], "ggml-large-v3");
+    }
+}
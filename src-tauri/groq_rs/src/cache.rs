@@ -0,0 +1,434 @@
+use crate::{ApiRequest, ApiResponse, AuthScheme};
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderValue, CONTENT_TYPE};
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use std::fs::{self, File};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{Duration, SystemTime};
+
+/// The default maximum size of a `ResponseCache`, 256 MiB.
+pub const DEFAULT_CACHE_SIZE: u64 = 256 * 1024 * 1024;
+
+/// The default time to live of the entries of a `ResponseCache`, 7 days.
+pub const DEFAULT_CACHE_TTL: Duration = Duration::from_secs(7 * 24 * 60 * 60);
+
+/// The extension of the files holding cache entries.
+const ENTRY_EXTENSION: &str = "groq-cache";
+
+/// Hashes `parts` with 128-bit FNV-1a, which unlike `DefaultHasher` gives the same result on every
+/// platform and Rust version. Parts are length-prefixed, so `["ab", "c"]` and `["a", "bc"]` differ.
+pub(crate) fn stable_hash<'a>(parts: impl IntoIterator<Item = &'a [u8]>) -> u128 {
+    const OFFSET: u128 = 0x6c62272e07bb014262b821756295c58d;
+    const PRIME: u128 = 0x0000000001000000000000000000013b;
+    let mut hash = OFFSET;
+    let mut write = |bytes: &[u8]| {
+        for &byte in bytes {
+            hash ^= byte as u128;
+            hash = hash.wrapping_mul(PRIME);
+        }
+    };
+    for part in parts {
+        write(&(part.len() as u64).to_le_bytes());
+        write(part);
+    }
+    hash
+}
+
+/// The metadata stored on the first line of a cache entry, before the response body.
+///
+/// - `stored_at`: When the entry was stored, in milliseconds since the Unix epoch. Entries
+///   written before it was recorded read as stored at the epoch, so they are expired.
+#[derive(Debug, Serialize, Deserialize)]
+struct EntryHeader {
+    status: u16,
+    content_type: Option<String>,
+    #[serde(default)]
+    stored_at: u64,
+}
+
+impl EntryHeader {
+    fn stored_at(&self) -> SystemTime {
+        SystemTime::UNIX_EPOCH + Duration::from_millis(self.stored_at)
+    }
+}
+
+/// An on-disk cache of successful responses, shared by clients through `with_response_cache`.
+///
+/// Entries are keyed by a stable hash of the whole encoded request: for chat completions, the
+/// model, messages, seed, temperature and every other parameter; for speech-to-text and
+/// text-to-speech, the audio or text and every parameter. The endpoint and the credentials of the
+/// client are part of the key too, so clients of different servers or accounts can share a cache
+/// without being served each other's responses. Credentials are only hashed, never stored.
+///
+/// Only requests with a reproducible response are cached: chat completions that set a `seed` or
+/// a `temperature` of 0, speech-to-text requests with no `temperature` or one of 0, which Whisper
+/// decodes greedily, and every text-to-speech request. Streams and error responses are never
+/// cached.
+///
+/// Entries stored longer ago than the time to live are ignored, however often they are read, and
+/// the least recently used entries are evicted once the cache grows past its maximum size. Rate
+/// limit headers are not stored, so cached responses carry no `rate_limit`. Hooks still see every
+/// call served from the cache, with `RequestInfo::attempts` set to 0. Use
+/// `RequestOptions::bypass_cache` to skip the cache for a single call.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::{AsyncGroqClient, ResponseCache};
+/// use std::sync::Arc;
+/// use std::time::Duration;
+///
+/// let cache = ResponseCache::new(std::env::temp_dir().join("groq-cache"))
+///     .max_size(64 * 1024 * 1024)
+///     .ttl(Duration::from_secs(24 * 60 * 60));
+/// let client = AsyncGroqClient::new("my_api_key".to_string(), None)
+///     .with_response_cache(Arc::new(cache));
+///```
+#[derive(Debug)]
+pub struct ResponseCache {
+    dir: PathBuf,
+    max_size: u64,
+    ttl: Duration,
+    // Serializes writes and evictions within the process.
+    lock: Mutex<()>,
+}
+
+impl ResponseCache {
+    /// Creates a cache storing its entries in `dir`, created when the first entry is stored.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            max_size: DEFAULT_CACHE_SIZE,
+            ttl: DEFAULT_CACHE_TTL,
+            lock: Mutex::new(()),
+        }
+    }
+
+    /// Sets the maximum total size of the entries in bytes. Defaults to `DEFAULT_CACHE_SIZE`.
+    pub fn max_size(mut self, max_size: u64) -> Self {
+        self.max_size = max_size;
+        self
+    }
+
+    /// Sets how long entries are served after being stored. Defaults to `DEFAULT_CACHE_TTL`.
+    pub fn ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = ttl;
+        self
+    }
+
+    /// Returns the directory holding the entries.
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Removes every entry.
+    ///
+    /// # Errors
+    ///
+    /// Returns the I/O error of the first entry that could not be removed.
+    pub fn clear(&self) -> std::io::Result<()> {
+        let _guard = self.lock.lock().unwrap();
+        for (path, _, _) in self.entries() {
+            fs::remove_file(path)?;
+        }
+        Ok(())
+    }
+
+    /// Returns the path of the entry for `request`, sent to `endpoint` with `auth`, or `None` if
+    /// its response is not cacheable.
+    ///
+    /// The path is taken before hooks run, so changes they make do not affect the key.
+    pub(crate) fn entry(
+        &self,
+        request: &ApiRequest,
+        endpoint: &str,
+        auth: &AuthScheme,
+    ) -> Option<PathBuf> {
+        if !request.cacheable {
+            return None;
+        }
+        let credential = auth.header();
+        let (auth_name, auth_value) = credential
+            .as_ref()
+            .map_or((&b""[..], &b""[..]), |(name, value)| {
+                (name.as_str().as_bytes(), value.as_bytes())
+            });
+        let key = stable_hash([
+            endpoint.as_bytes(),
+            auth_name,
+            auth_value,
+            request.method.as_str().as_bytes(),
+            request.path.as_bytes(),
+            &request.body,
+        ]);
+        Some(self.dir.join(format!("{:032x}.{}", key, ENTRY_EXTENSION)))
+    }
+
+    /// Returns the response cached in `entry`, if any and still fresh.
+    pub(crate) fn get(&self, entry: &Path) -> Option<ApiResponse> {
+        let file = File::open(entry).ok()?;
+        let Some((header, response)) = read_entry(&file) else {
+            log::warn!("ignoring the unreadable cache entry {}", entry.display());
+            return None;
+        };
+        if self.is_expired(&header) {
+            let _ = fs::remove_file(entry);
+            return None;
+        }
+        // Touching the entry keeps it from being evicted as least recently used. The time it
+        // was stored is kept in its header, so this does not extend its lifetime. Entries of a
+        // read-only cache cannot be touched, but are still served.
+        let _ = File::options()
+            .write(true)
+            .open(entry)
+            .and_then(|file| file.set_modified(SystemTime::now()));
+        Some(response)
+    }
+
+    /// Returns whether the entry with `header` was stored longer ago than the time to live.
+    fn is_expired(&self, header: &EntryHeader) -> bool {
+        header.stored_at().elapsed().unwrap_or_default() >= self.ttl
+    }
+
+    /// Stores a successful response in `entry`, evicting old entries if the cache is full.
+    pub(crate) fn put(&self, entry: &Path, response: &ApiResponse) {
+        if !response.status.is_success() {
+            return;
+        }
+        let _guard = self.lock.lock().unwrap();
+        if let Err(e) = self.write_entry(entry, response) {
+            log::warn!("failed to store a response in the cache: {}", e);
+        }
+        self.evict();
+    }
+
+    /// Writes an entry to a temporary file, then moves it in place so readers never see it half written.
+    fn write_entry(&self, path: &Path, response: &ApiResponse) -> std::io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let header = EntryHeader {
+            status: response.status.as_u16(),
+            content_type: response
+                .headers
+                .get(CONTENT_TYPE)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string),
+            stored_at: SystemTime::now()
+                .duration_since(SystemTime::UNIX_EPOCH)
+                .unwrap_or_default()
+                .as_millis() as u64,
+        };
+        let partial = path.with_extension("partial");
+        let mut file = File::create(&partial)?;
+        serde_json::to_writer(&mut file, &header)?;
+        file.write_all(b"\n")?;
+        file.write_all(&response.body)?;
+        drop(file);
+        fs::rename(partial, path)
+    }
+
+    /// Lists the entries with their size and last use, oldest first.
+    fn entries(&self) -> Vec<(PathBuf, u64, SystemTime)> {
+        let Ok(dir) = fs::read_dir(&self.dir) else {
+            return Vec::new();
+        };
+        let mut entries: Vec<_> = dir
+            .flatten()
+            .filter(|entry| {
+                entry.path().extension().and_then(|e| e.to_str()) == Some(ENTRY_EXTENSION)
+            })
+            .filter_map(|entry| {
+                let metadata = entry.metadata().ok()?;
+                Some((entry.path(), metadata.len(), metadata.modified().ok()?))
+            })
+            .collect();
+        entries.sort_by_key(|(_, _, modified)| *modified);
+        entries
+    }
+
+    /// Removes expired entries, then the least recently used ones until the cache fits in its size.
+    fn evict(&self) {
+        let entries = self.entries();
+        let mut size: u64 = entries.iter().map(|(_, len, _)| len).sum();
+        for (path, len, _) in entries {
+            // Unreadable entries are removed along with the expired ones.
+            let fresh = File::open(&path)
+                .ok()
+                .and_then(|file| read_header(&mut BufReader::new(file)))
+                .is_some_and(|header| !self.is_expired(&header));
+            if fresh && size <= self.max_size {
+                continue;
+            }
+            if fs::remove_file(&path).is_ok() {
+                size -= len;
+            }
+        }
+    }
+}
+
+/// Reads the header line of an entry.
+fn read_header(reader: &mut impl BufRead) -> Option<EntryHeader> {
+    let mut line = String::new();
+    reader.read_line(&mut line).ok()?;
+    serde_json::from_str(&line).ok()
+}
+
+/// Reads an entry written by `ResponseCache::write_entry`.
+fn read_entry(file: &File) -> Option<(EntryHeader, ApiResponse)> {
+    let mut reader = BufReader::new(file);
+    let header = read_header(&mut reader)?;
+    let mut body = Vec::new();
+    reader.read_to_end(&mut body).ok()?;
+    let mut headers = HeaderMap::new();
+    if let Some(content_type) = header
+        .content_type
+        .as_deref()
+        .and_then(|value| HeaderValue::from_str(value).ok())
+    {
+        headers.insert(CONTENT_TYPE, content_type);
+    }
+    let response = ApiResponse {
+        status: StatusCode::from_u16(header.status).ok()?,
+        headers,
+        body: Bytes::from(body),
+    };
+    Some((header, response))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::Method;
+
+    fn request(body: &str) -> ApiRequest {
+        ApiRequest {
+            method: Method::POST,
+            path: "/audio/speech".to_string(),
+            headers: HeaderMap::new(),
+            body: body.as_bytes().to_vec(),
+            model: None,
+            cacheable: true,
+            upload: None,
+            rate_limit: None,
+        }
+    }
+
+    fn entry(cache: &ResponseCache, request: &ApiRequest) -> Option<PathBuf> {
+        cache.entry(request, "https://api.groq.com/openai/v1", &AuthScheme::None)
+    }
+
+    fn response(body: &str) -> ApiResponse {
+        ApiResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::from_iter([(CONTENT_TYPE, HeaderValue::from_static("audio/wav"))]),
+            body: Bytes::from(body.to_string()),
+        }
+    }
+
+    #[test]
+    fn test_stable_hash() {
+        assert_eq!(stable_hash([b"".as_slice()]), stable_hash([b"".as_slice()]));
+        assert_ne!(
+            stable_hash([b"ab".as_slice(), b"c"]),
+            stable_hash([b"a".as_slice(), b"bc"])
+        );
+    }
+
+    #[test]
+    fn test_entry_key() {
+        let cache = ResponseCache::new("cache");
+        let hello = request("Hello!");
+        let groq = entry(&cache, &hello).unwrap();
+        let local = cache
+            .entry(&hello, "http://localhost:8080/v1", &AuthScheme::None)
+            .unwrap();
+        assert_ne!(groq, local);
+
+        // Clients of the same server with different credentials do not share entries either.
+        let bearer = |key: &str| {
+            cache
+                .entry(
+                    &hello,
+                    "http://localhost:8080/v1",
+                    &AuthScheme::Bearer(key.to_string()),
+                )
+                .unwrap()
+        };
+        assert_eq!(bearer("key_a"), bearer("key_a"));
+        assert_ne!(bearer("key_a"), bearer("key_b"));
+        assert_ne!(bearer("key_a"), local);
+        let api_key = cache
+            .entry(
+                &hello,
+                "http://localhost:8080/v1",
+                &AuthScheme::ApiKey("key_a".to_string()),
+            )
+            .unwrap();
+        assert_ne!(bearer("key_a"), api_key);
+    }
+
+    #[test]
+    fn test_response_cache() {
+        let dir = std::env::temp_dir().join(format!("groq-rs-cache-{}", std::process::id()));
+        let cache = ResponseCache::new(&dir).max_size(100);
+        let sure = entry(&cache, &request("Sure!")).unwrap();
+        let hello = entry(&cache, &request("Hello!")).unwrap();
+        assert_ne!(sure, hello);
+        assert!(cache.get(&sure).is_none());
+
+        cache.put(&sure, &response("12345"));
+        let cached = cache.get(&sure).unwrap();
+        assert_eq!(cached.status, StatusCode::OK);
+        assert_eq!(cached.headers[CONTENT_TYPE], "audio/wav");
+        assert_eq!(cached.body, "12345");
+        assert!(cache.get(&hello).is_none());
+
+        // Read-only entries are served too.
+        let mut permissions = fs::metadata(&sure).unwrap().permissions();
+        permissions.set_readonly(true);
+        fs::set_permissions(&sure, permissions.clone()).unwrap();
+        assert_eq!(cache.get(&sure).unwrap().body, "12345");
+        #[allow(clippy::permissions_set_readonly_false)]
+        permissions.set_readonly(false);
+        fs::set_permissions(&sure, permissions).unwrap();
+
+        // Neither requests marked as not cacheable nor error responses are stored.
+        let mut uncacheable = request("Hello!");
+        uncacheable.cacheable = false;
+        assert!(entry(&cache, &uncacheable).is_none());
+        let error = ApiResponse {
+            status: StatusCode::INTERNAL_SERVER_ERROR,
+            ..response("")
+        };
+        cache.put(&hello, &error);
+        assert!(cache.get(&hello).is_none());
+
+        // With their header line, entries take 74 bytes: the least recently used is evicted.
+        cache.put(&hello, &response("67890"));
+        assert!(cache.get(&sure).is_none());
+        assert_eq!(cache.get(&hello).unwrap().body, "67890");
+
+        let expired = ResponseCache::new(&dir).ttl(Duration::ZERO);
+        assert!(expired.get(&hello).is_none());
+        cache.clear().unwrap();
+        fs::remove_dir(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_ttl() {
+        let dir = std::env::temp_dir().join(format!("groq-rs-ttl-{}", std::process::id()));
+        let cache = ResponseCache::new(&dir).ttl(Duration::from_millis(300));
+        let hello = entry(&cache, &request("Hello!")).unwrap();
+        cache.put(&hello, &response("12345"));
+
+        // Reading an entry keeps it from being evicted, but does not extend its lifetime.
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(cache.get(&hello).is_some());
+        std::thread::sleep(Duration::from_millis(200));
+        assert!(cache.get(&hello).is_none());
+        assert!(!hello.exists());
+        fs::remove_dir(&dir).unwrap();
+    }
+}
@@ -0,0 +1,203 @@
+use crate::GroqError;
+use futures_util::future::{self, Either};
+use std::future::Future;
+use std::pin::pin;
+use std::sync::{Arc, Condvar, Mutex};
+use std::time::{Duration, Instant};
+use tokio::sync::Notify;
+
+/// A handle to abandon in-flight requests, shared by every clone.
+///
+/// Pass it to a call through `RequestOptions::cancellation`, then call `cancel` from anywhere,
+/// for instance when the user starts over. Requests of the asynchronous client stop at once with
+/// `GroqError::Cancelled`. The blocking client checks the token before each attempt, between
+/// retries and between the chunks of a stream, since a blocking request cannot be interrupted.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::{CancellationToken, RequestOptions};
+///
+/// let token = CancellationToken::new();
+/// let options = RequestOptions::new().cancellation(token.clone());
+/// token.cancel();
+/// assert!(token.is_cancelled());
+///```
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    inner: Arc<TokenState>,
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    cancelled: Mutex<bool>,
+    condvar: Condvar,
+    notify: Notify,
+}
+
+impl CancellationToken {
+    /// Creates a token that is not cancelled.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cancels every request using this token, now and in the future.
+    pub fn cancel(&self) {
+        *self.inner.cancelled.lock().unwrap() = true;
+        self.inner.condvar.notify_all();
+        self.inner.notify.notify_waiters();
+    }
+
+    /// Returns whether `cancel` has been called.
+    pub fn is_cancelled(&self) -> bool {
+        *self.inner.cancelled.lock().unwrap()
+    }
+
+    /// Waits until the token is cancelled.
+    pub async fn cancelled(&self) {
+        // Registered before the check, so a `cancel` in between is not missed.
+        let notified = self.inner.notify.notified();
+        if self.is_cancelled() {
+            return;
+        }
+        notified.await;
+    }
+
+    /// Blocks the current thread until the token is cancelled or `timeout` has passed.
+    fn wait_timeout(&self, timeout: Duration) {
+        let cancelled = self.inner.cancelled.lock().unwrap();
+        let _ = self
+            .inner
+            .condvar
+            .wait_timeout_while(cancelled, timeout, |cancelled| !*cancelled)
+            .unwrap();
+    }
+}
+
+/// Options of a single call, such as `chat_completion_with`.
+///
+/// - `cancellation`: The token abandoning the call with `GroqError::Cancelled` once cancelled.
+/// - `deadline`: The instant after which the call fails with `GroqError::DeadlineExceeded`,
+///   including the time spent waiting for the rate limiter and between retries.
+/// - `bypass_cache`: Whether the client's `ResponseCache`, if any, is neither read nor written.
+#[derive(Debug, Clone, Default)]
+pub struct RequestOptions {
+    pub cancellation: Option<CancellationToken>,
+    pub deadline: Option<Instant>,
+    pub bypass_cache: bool,
+}
+
+impl RequestOptions {
+    /// Creates options without a cancellation token or a deadline.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the token abandoning the call once cancelled.
+    pub fn cancellation(mut self, cancellation: CancellationToken) -> Self {
+        self.cancellation = Some(cancellation);
+        self
+    }
+
+    /// Sets the instant after which the call fails.
+    pub fn deadline(mut self, deadline: Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+
+    /// Sets the deadline to `timeout` from now.
+    pub fn timeout(self, timeout: Duration) -> Self {
+        self.deadline(Instant::now() + timeout)
+    }
+
+    /// Sets whether the call skips the client's response cache, to force a fresh response.
+    pub fn bypass_cache(mut self, bypass_cache: bool) -> Self {
+        self.bypass_cache = bypass_cache;
+        self
+    }
+
+    /// Returns an error if the call was cancelled or its deadline has passed.
+    pub(crate) fn check(&self) -> Result<(), GroqError> {
+        if self
+            .cancellation
+            .as_ref()
+            .is_some_and(CancellationToken::is_cancelled)
+        {
+            return Err(GroqError::Cancelled);
+        }
+        if self
+            .deadline
+            .is_some_and(|deadline| deadline <= Instant::now())
+        {
+            return Err(GroqError::DeadlineExceeded);
+        }
+        Ok(())
+    }
+
+    /// Returns the shorter of `timeout` and the time left until the deadline.
+    pub(crate) fn limit(&self, timeout: Option<Duration>) -> Option<Duration> {
+        let remaining = self
+            .deadline
+            .map(|deadline| deadline.saturating_duration_since(Instant::now()));
+        match (timeout, remaining) {
+            (Some(timeout), Some(remaining)) => Some(timeout.min(remaining)),
+            (timeout, remaining) => timeout.or(remaining),
+        }
+    }
+
+    /// Blocks the current thread for `delay`, returning early with an error if the call is
+    /// cancelled or its deadline passes in the meantime.
+    pub(crate) fn sleep(&self, delay: Duration) -> Result<(), GroqError> {
+        let delay = self.limit(Some(delay)).unwrap_or(delay);
+        match &self.cancellation {
+            Some(cancellation) => cancellation.wait_timeout(delay),
+            None => std::thread::sleep(delay),
+        }
+        self.check()
+    }
+
+    /// Returns a future that resolves with the matching error once the call is cancelled or its
+    /// deadline passes, and never otherwise.
+    pub(crate) fn interrupted(&self) -> impl Future<Output = GroqError> + Send + 'static {
+        let cancellation = self.cancellation.clone();
+        let deadline = self.deadline;
+        async move {
+            let cancelled = async {
+                match &cancellation {
+                    Some(cancellation) => cancellation.cancelled().await,
+                    None => future::pending().await,
+                }
+            };
+            let expired = async {
+                match deadline {
+                    Some(deadline) => tokio::time::sleep_until(deadline.into()).await,
+                    None => future::pending().await,
+                }
+            };
+            match future::select(pin!(cancelled), pin!(expired)).await {
+                Either::Left(_) => GroqError::Cancelled,
+                Either::Right(_) => GroqError::DeadlineExceeded,
+            }
+        }
+    }
+
+    /// Returns whether the call can be interrupted at all.
+    pub(crate) fn is_interruptible(&self) -> bool {
+        self.cancellation.is_some() || self.deadline.is_some()
+    }
+
+    /// Runs `future` until it completes, the call is cancelled or its deadline passes.
+    pub(crate) async fn run<T>(
+        &self,
+        future: impl Future<Output = Result<T, GroqError>>,
+    ) -> Result<T, GroqError> {
+        self.check()?;
+        if !self.is_interruptible() {
+            return future.await;
+        }
+        match future::select(pin!(future), pin!(self.interrupted())).await {
+            Either::Left((result, _)) => result,
+            Either::Right((error, _)) => Err(error),
+        }
+    }
+}
@@ -0,0 +1,654 @@
+use crate::{
+    AudioFormat, GroqError, RequestOptions, SpeechToTextProvider, SpeechToTextRequest,
+    SpeechToTextResponse, TimestampGranularity, Transcript, TranscriptFormat, TranscriptionSegment,
+    TranscriptionWord, MAX_AUDIO_FILE_BYTES,
+};
+use futures_util::stream::{self, StreamExt, TryStreamExt};
+use std::io::Cursor;
+use std::time::Duration;
+
+/// The size of the canonical header of the WAV files chunks are sent as.
+const WAV_HEADER_BYTES: usize = 44;
+
+/// The length of the windows whose loudness is compared when looking for silence.
+const ANALYSIS_WINDOW: Duration = Duration::from_millis(20);
+
+/// Options of `SpeechToTextProvider::transcribe_chunked`.
+///
+/// - `max_chunk_duration`: The longest a chunk may last. Defaults to 10 minutes.
+/// - `max_chunk_bytes`: The largest a chunk may be, header included. Defaults to `MAX_AUDIO_FILE_BYTES`.
+/// - `overlap`: How long consecutive chunks overlap, so no word is cut in half. Defaults to 2 seconds.
+/// - `silence_window`: How far before its limit a chunk may end early, at the quietest point. Defaults to 30 seconds.
+/// - `concurrency`: The number of chunks transcribed at once. Defaults to 4.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ChunkingOptions {
+    pub max_chunk_duration: Duration,
+    pub max_chunk_bytes: usize,
+    pub overlap: Duration,
+    pub silence_window: Duration,
+    pub concurrency: usize,
+}
+
+impl Default for ChunkingOptions {
+    fn default() -> Self {
+        Self {
+            max_chunk_duration: Duration::from_secs(10 * 60),
+            max_chunk_bytes: MAX_AUDIO_FILE_BYTES,
+            overlap: Duration::from_secs(2),
+            silence_window: Duration::from_secs(30),
+            concurrency: 4,
+        }
+    }
+}
+
+impl ChunkingOptions {
+    /// Creates the default options.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the longest a chunk may last.
+    pub fn max_chunk_duration(mut self, max_chunk_duration: Duration) -> Self {
+        self.max_chunk_duration = max_chunk_duration;
+        self
+    }
+
+    /// Sets the largest a chunk may be, such as the `max_audio_size` of the client.
+    pub fn max_chunk_bytes(mut self, max_chunk_bytes: usize) -> Self {
+        self.max_chunk_bytes = max_chunk_bytes;
+        self
+    }
+
+    /// Sets how long consecutive chunks overlap.
+    pub fn overlap(mut self, overlap: Duration) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    /// Sets how far before its limit a chunk may end early, at the quietest point.
+    pub fn silence_window(mut self, silence_window: Duration) -> Self {
+        self.silence_window = silence_window;
+        self
+    }
+
+    /// Sets the number of chunks transcribed at once.
+    pub fn concurrency(mut self, concurrency: usize) -> Self {
+        self.concurrency = concurrency;
+        self
+    }
+
+    /// Returns whether the audio of a request created with `from_path` or `from_reader` may have
+    /// to be split, so it must be read into memory first. Only WAV files can be split, and those
+    /// within the limits, as told by their size and header, are streamed as they are.
+    fn needs_loading(&self, request: &SpeechToTextRequest) -> bool {
+        let Some(source) = &request.source else {
+            return false;
+        };
+        if AudioFormat::sniff(&source.head) != Some(AudioFormat::Wav) {
+            return false;
+        }
+        let fits_bytes = source
+            .len
+            .is_some_and(|len| len <= self.max_chunk_bytes as u64);
+        let fits_duration = hound::WavReader::new(Cursor::new(&source.head)).is_ok_and(|reader| {
+            reader.duration() as f64 / reader.spec().sample_rate.max(1) as f64
+                <= self.max_chunk_duration.as_secs_f64()
+        });
+        !(fits_bytes && fits_duration)
+    }
+
+    /// Splits `file` into chunks within the limits, or returns `None` if it already fits in one.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidInput` if the file is too large but is not a PCM WAV file, the
+    /// only format that can be split without decoding it, or if the limits leave no room for
+    /// anything but the overlap.
+    pub(crate) fn split(&self, file: &[u8]) -> Result<Option<Vec<AudioChunk>>, GroqError> {
+        let wav = match AudioFormat::sniff(file) {
+            Some(AudioFormat::Wav) => Wav::parse(file)?,
+            _ if file.len() <= self.max_chunk_bytes => return Ok(None),
+            _ => {
+                return Err(GroqError::InvalidInput(format!(
+                    "audio file is {} bytes, above the limit of {} bytes, and only WAV files can be split",
+                    file.len(),
+                    self.max_chunk_bytes
+                )))
+            }
+        };
+        let max_frames = (self.max_chunk_bytes.saturating_sub(WAV_HEADER_BYTES) / wav.block_align)
+            .min(wav.frames_in(self.max_chunk_duration));
+        if file.len() <= self.max_chunk_bytes && wav.frames() <= max_frames {
+            return Ok(None);
+        }
+        let overlap = wav.frames_in(self.overlap);
+        if max_frames <= 2 * overlap {
+            return Err(GroqError::InvalidInput(
+                "chunks must be longer than twice their overlap".to_string(),
+            ));
+        }
+        // Ending early must still leave each chunk longer than the overlap, so the split progresses.
+        let search = wav
+            .frames_in(self.silence_window)
+            .min((max_frames - overlap) / 2);
+
+        let mut chunks = Vec::new();
+        let mut start = 0;
+        loop {
+            if wav.frames() - start <= max_frames {
+                chunks.push(wav.chunk(start, wav.frames()));
+                break;
+            }
+            let limit = start + max_frames;
+            let end = wav.quietest(limit - search, limit);
+            chunks.push(wav.chunk(start, end));
+            start = end - overlap;
+        }
+        Ok(Some(chunks))
+    }
+}
+
+/// A piece of a longer audio file, as a WAV file of its own.
+///
+/// - `start`: The time the chunk starts in the whole file, in seconds.
+/// - `end`: The time the chunk ends in the whole file, in seconds.
+/// - `file`: The WAV file holding the chunk.
+#[derive(Debug, Clone)]
+pub(crate) struct AudioChunk {
+    pub start: f64,
+    pub end: f64,
+    pub file: Vec<u8>,
+}
+
+/// The PCM samples of a WAV file, kept as bytes.
+struct Wav<'a> {
+    spec: hound::WavSpec,
+    block_align: usize,
+    data: &'a [u8],
+}
+
+impl<'a> Wav<'a> {
+    /// Locates the samples of a WAV file.
+    fn parse(file: &'a [u8]) -> Result<Self, GroqError> {
+        let invalid = |e: hound::Error| GroqError::InvalidInput(format!("invalid WAV file: {}", e));
+        let reader = hound::WavReader::new(Cursor::new(file)).map_err(invalid)?;
+        let spec = reader.spec();
+        let block_align = spec.channels as usize * spec.bits_per_sample.div_ceil(8) as usize;
+        let samples = reader.len() as usize;
+        // The reader stops right at the start of the samples.
+        let start = reader.into_inner().position() as usize;
+        let len = (samples * spec.bits_per_sample.div_ceil(8) as usize).min(file.len() - start);
+        Ok(Self {
+            spec,
+            block_align,
+            data: &file[start..start + len - len % block_align],
+        })
+    }
+
+    fn frames(&self) -> usize {
+        self.data.len() / self.block_align
+    }
+
+    fn frames_in(&self, duration: Duration) -> usize {
+        (duration.as_secs_f64() * self.spec.sample_rate as f64) as usize
+    }
+
+    fn seconds(&self, frames: usize) -> f64 {
+        frames as f64 / self.spec.sample_rate as f64
+    }
+
+    /// Returns the amplitude of the first channel of a frame, between 0 and 1.
+    fn amplitude(&self, frame: usize) -> f64 {
+        let bytes = &self.data[frame * self.block_align..];
+        let value = match (self.spec.sample_format, self.spec.bits_per_sample) {
+            (hound::SampleFormat::Float, _) => {
+                f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+            }
+            (_, 8) => (bytes[0] as f64 - 128.0) / 128.0,
+            (_, bits) => {
+                let width = bits.div_ceil(8) as usize;
+                let mut padded = [0u8; 4];
+                padded[4 - width..].copy_from_slice(&bytes[..width]);
+                i32::from_le_bytes(padded) as f64 / i32::MAX as f64
+            }
+        };
+        value.abs()
+    }
+
+    /// Returns the frame between `from` and `to` to end a chunk at: the middle of the latest
+    /// stretch of quietest analysis windows, or `to` if the audio is equally loud throughout.
+    fn quietest(&self, from: usize, to: usize) -> usize {
+        let window = self.frames_in(ANALYSIS_WINDOW).max(1);
+        // The windows end at `to`, latest first.
+        let levels: Vec<f64> = (1..=(to - from) / window)
+            .map(|i| {
+                let start = to - i * window;
+                (start..start + window)
+                    .map(|frame| self.amplitude(frame))
+                    .sum()
+            })
+            .collect();
+        let min = levels.iter().copied().fold(f64::INFINITY, f64::min);
+        let max = levels.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+        if levels.is_empty() || max <= min {
+            return to;
+        }
+        let latest = levels.iter().position(|level| *level == min).unwrap();
+        let len = levels[latest..]
+            .iter()
+            .take_while(|level| **level == min)
+            .count();
+        // The stretch spans from the start of window `latest + len - 1` to the end of window `latest`.
+        let end = to - latest * window;
+        end - len * window / 2
+    }
+
+    /// Copies the frames between `start` and `end` into a WAV file of their own.
+    fn chunk(&self, start: usize, end: usize) -> AudioChunk {
+        let data = &self.data[start * self.block_align..end * self.block_align];
+        let format: u16 = match self.spec.sample_format {
+            hound::SampleFormat::Float => 3,
+            hound::SampleFormat::Int => 1,
+        };
+        let mut file = Vec::with_capacity(WAV_HEADER_BYTES + data.len());
+        file.extend_from_slice(b"RIFF");
+        file.extend_from_slice(&(36 + data.len() as u32).to_le_bytes());
+        file.extend_from_slice(b"WAVEfmt ");
+        file.extend_from_slice(&16u32.to_le_bytes());
+        file.extend_from_slice(&format.to_le_bytes());
+        file.extend_from_slice(&self.spec.channels.to_le_bytes());
+        file.extend_from_slice(&self.spec.sample_rate.to_le_bytes());
+        file.extend_from_slice(&(self.spec.sample_rate * self.block_align as u32).to_le_bytes());
+        file.extend_from_slice(&(self.block_align as u16).to_le_bytes());
+        file.extend_from_slice(&self.spec.bits_per_sample.to_le_bytes());
+        file.extend_from_slice(b"data");
+        file.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        file.extend_from_slice(data);
+        AudioChunk {
+            start: self.seconds(start),
+            end: self.seconds(end),
+            file,
+        }
+    }
+}
+
+/// Transcribes `request` with `provider`, in chunks if it is too long for a single request.
+pub(crate) async fn transcribe_chunked<P: SpeechToTextProvider + ?Sized>(
+    provider: &P,
+    mut request: SpeechToTextRequest,
+    chunking: &ChunkingOptions,
+    options: &RequestOptions,
+) -> Result<SpeechToTextResponse, GroqError> {
+    // Chunks are stitched by their timestamps, then converted to the format asked for.
+    let format = request.response_format.unwrap_or_default();
+    let granularities = request.timestamp_granularities.take();
+    request.response_format = Some(TranscriptFormat::VerboseJson);
+    let mut request = request
+        .timestamp_granularities(&[TimestampGranularity::Segment, TimestampGranularity::Word]);
+    if chunking.needs_loading(&request) {
+        request = request.into_memory().await?;
+    }
+    let response = match chunking.split(&request.file)? {
+        Some(chunks) => transcribe_chunks(provider, request, chunks, chunking, options).await?,
+        None => provider.transcribe(request, options).await?,
+    };
+    Ok(convert(response, format, granularities.as_deref()))
+}
+
+/// Transcribes the `chunks` of `request` concurrently and stitches their transcriptions.
+async fn transcribe_chunks<P: SpeechToTextProvider + ?Sized>(
+    provider: &P,
+    request: SpeechToTextRequest,
+    chunks: Vec<AudioChunk>,
+    chunking: &ChunkingOptions,
+    options: &RequestOptions,
+) -> Result<SpeechToTextResponse, GroqError> {
+    log::debug!("transcribing the audio in {} chunks", chunks.len());
+
+    let template = SpeechToTextRequest {
+        file: Vec::new(),
+        file_name: None,
+        ..request
+    };
+    let bounds: Vec<(f64, f64)> = chunks
+        .iter()
+        .map(|chunk| (chunk.start, chunk.end))
+        .collect();
+    let responses: Vec<SpeechToTextResponse> = stream::iter(chunks)
+        .map(|chunk| {
+            let request = SpeechToTextRequest {
+                file: chunk.file,
+                ..template.clone()
+            };
+            provider.transcribe(request, options)
+        })
+        .buffered(chunking.concurrency.max(1))
+        .try_collect()
+        .await?;
+    Ok(stitch(&bounds, responses))
+}
+
+/// Converts a `VerboseJson` transcription with segment and word timestamps to `format`.
+///
+/// As with `Transcript::into_response`, the `text` of `Text` holds the text alone and that of
+/// `Srt` and `Vtt` the subtitles, built from the segments. `Json` keeps only the text, and
+/// `VerboseJson` only the timestamps in `granularities`, segments by default as with the API.
+fn convert(
+    response: SpeechToTextResponse,
+    format: TranscriptFormat,
+    granularities: Option<&[TimestampGranularity]>,
+) -> SpeechToTextResponse {
+    let rate_limit = response.rate_limit.clone();
+    let transcript = match format {
+        TranscriptFormat::Json | TranscriptFormat::Text => Transcript::Text(response.text),
+        TranscriptFormat::Srt | TranscriptFormat::Vtt => Transcript::Subtitles {
+            format,
+            content: response.subtitles(format).unwrap_or_default(),
+        },
+        TranscriptFormat::VerboseJson => {
+            let granularities = granularities.unwrap_or(&[TimestampGranularity::Segment]);
+            return SpeechToTextResponse {
+                segments: response
+                    .segments
+                    .filter(|_| granularities.contains(&TimestampGranularity::Segment)),
+                words: response
+                    .words
+                    .filter(|_| granularities.contains(&TimestampGranularity::Word)),
+                ..response
+            };
+        }
+    };
+    SpeechToTextResponse {
+        rate_limit,
+        ..transcript.into_response()
+    }
+}
+
+/// Joins the transcriptions of overlapping chunks, starting and ending at `bounds` in seconds.
+///
+/// Timestamps are offset by the start of their chunk. Each overlap is cut in its middle: the
+/// segments and words before the cut are taken from the earlier chunk, the others from the later.
+pub(crate) fn stitch(
+    bounds: &[(f64, f64)],
+    responses: Vec<SpeechToTextResponse>,
+) -> SpeechToTextResponse {
+    let cuts: Vec<f64> = bounds
+        .windows(2)
+        .map(|pair| (pair[1].0 + pair[0].1) / 2.0)
+        .collect();
+    let has_segments = responses.iter().all(|r| r.segments.is_some());
+    let has_words = responses.iter().any(|r| r.words.is_some());
+    let mut texts = Vec::new();
+    let mut segments: Vec<TranscriptionSegment> = Vec::new();
+    let mut words: Vec<TranscriptionWord> = Vec::new();
+    let mut language = None;
+    let mut rate_limit = None;
+
+    for (index, response) in responses.into_iter().enumerate() {
+        let offset = bounds[index].0;
+        let from = index.checked_sub(1).map_or(f64::NEG_INFINITY, |i| cuts[i]);
+        let to = cuts.get(index).copied().unwrap_or(f64::INFINITY);
+        let kept = |time: f64| from <= time && time < to;
+
+        for mut segment in response.segments.into_iter().flatten() {
+            segment.start += offset;
+            segment.end += offset;
+            if kept((segment.start + segment.end) / 2.0) {
+                segment.id = segments.len() as u32;
+                segments.push(segment);
+            }
+        }
+        let first = words.len();
+        for mut word in response.words.into_iter().flatten() {
+            word.start += offset;
+            word.end += offset;
+            if kept(word.start) {
+                words.push(word);
+            }
+        }
+        // A word spoken across the cut can be heard by both chunks, at overlapping times.
+        if first > 0 && first < words.len() {
+            let (before, after) = (&words[first - 1], &words[first]);
+            if normalize(&before.word) == normalize(&after.word) && after.start < before.end {
+                words.remove(first);
+            }
+        }
+        if !has_segments {
+            texts.push(response.text.trim().to_string());
+        }
+        language = language.or(response.language);
+        rate_limit = response.rate_limit.or(rate_limit);
+    }
+
+    if has_segments {
+        texts = segments
+            .iter()
+            .map(|segment| segment.text.trim().to_string())
+            .collect();
+    }
+    texts.retain(|text| !text.is_empty());
+    SpeechToTextResponse {
+        text: texts.join(" "),
+        language,
+        duration: bounds.last().map(|(_, end)| *end),
+        segments: has_segments.then_some(segments),
+        words: has_words.then_some(words),
+        rate_limit,
+    }
+}
+
+/// Lowercases a word and strips its punctuation, to compare the same word heard twice.
+fn normalize(word: &str) -> String {
+    word.chars()
+        .filter(|c| c.is_alphanumeric())
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockGroqServer, MockResponse};
+    use crate::AsyncGroqClient;
+
+    /// Writes a 16-bit mono WAV file at 1 kHz, loud where `pattern` is `#` and silent where it is
+    /// `.`, each character lasting one second.
+    fn wav(pattern: &str) -> Vec<u8> {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 1000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut wav = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut wav, spec).unwrap();
+        for c in pattern.chars() {
+            for i in 0..1000 {
+                let sample = if c == '#' { [8000i16, -8000][i % 2] } else { 0 };
+                writer.write_sample(sample).unwrap();
+            }
+        }
+        writer.finalize().unwrap();
+        wav.into_inner()
+    }
+
+    fn word(word: &str, start: f64, end: f64) -> TranscriptionWord {
+        TranscriptionWord {
+            word: word.to_string(),
+            start,
+            end,
+        }
+    }
+
+    fn segment(start: f64, end: f64, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            id: 0,
+            seek: 0,
+            start,
+            end,
+            text: text.to_string(),
+            tokens: Vec::new(),
+            temperature: 0.0,
+            avg_logprob: 0.0,
+            compression_ratio: 0.0,
+            no_speech_prob: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_split() {
+        let options = ChunkingOptions::new()
+            .max_chunk_duration(Duration::from_secs(10))
+            .overlap(Duration::from_secs(1))
+            .silence_window(Duration::from_secs(4));
+        assert!(options.split(&wav("##########")).unwrap().is_none());
+
+        // The first chunk ends in the silence at 7 seconds rather than at its 10 second limit.
+        let file = wav("#######.#####################");
+        let chunks = options.split(&file).unwrap().unwrap();
+        let bounds: Vec<(f64, f64)> = chunks.iter().map(|c| (c.start, c.end)).collect();
+        assert_eq!(bounds[0], (0.0, 7.5));
+        assert_eq!(bounds[1].0, 6.5);
+        assert_eq!(bounds.last().unwrap().1, 29.0);
+        for chunk in &chunks {
+            let reader = hound::WavReader::new(Cursor::new(&chunk.file)).unwrap();
+            let duration = reader.duration() as f64 / 1000.0;
+            assert!((duration - (chunk.end - chunk.start)).abs() < 1e-9);
+            assert!(duration <= 10.0);
+        }
+
+        // Without silence, chunks end at their limit.
+        let chunks = options
+            .split(&wav("####################"))
+            .unwrap()
+            .unwrap();
+        assert_eq!((chunks[0].start, chunks[0].end), (0.0, 10.0));
+
+        // Other formats can only be sent whole.
+        let ogg = b"OggS\0\x02\0\0".to_vec();
+        assert!(options.split(&ogg).unwrap().is_none());
+        assert!(matches!(
+            options.max_chunk_bytes(4).split(&ogg),
+            Err(GroqError::InvalidInput(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_transcribe_chunked() {
+        let server = MockGroqServer::start();
+        for text in ["one two", "three four", "five six", "seven eight"] {
+            server.enqueue(
+                "/audio/transcriptions",
+                MockResponse::verbose_transcription(text),
+            );
+        }
+        let client = AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        // One chunk at a time, so the responses are matched to the chunks in order.
+        let chunking = ChunkingOptions::new()
+            .max_chunk_duration(Duration::from_secs(10))
+            .overlap(Duration::from_secs(1))
+            .silence_window(Duration::from_secs(4))
+            .concurrency(1);
+        let request = SpeechToTextRequest::new(wav("#######.#####################"))
+            .timestamp_granularities(&[TimestampGranularity::Word]);
+        let response = client
+            .transcribe_chunked(request, &chunking, &RequestOptions::default())
+            .await
+            .unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        assert_eq!(
+            requests[0].form_text("response_format").as_deref(),
+            Some("verbose_json")
+        );
+        let (file_name, file) = requests[1].form_file("file").unwrap();
+        assert_eq!(file_name, "audio.wav");
+        assert_eq!(
+            hound::WavReader::new(Cursor::new(file)).unwrap().duration(),
+            10000
+        );
+
+        // The chunks start at 0, 6.5, 15.5 and 24.5 seconds, and the words at 0 and 0.5 seconds
+        // into each: only the first word of the first chunk comes before a cut.
+        assert_eq!(response.text, "one two three four five six seven eight");
+        assert_eq!(response.duration, Some(29.0));
+        let words = response.words.unwrap();
+        let starts: Vec<f64> = words.iter().map(|word| word.start).collect();
+        assert_eq!(starts, [0.0, 0.5, 7.0, 16.0, 25.0]);
+        // Only the word timestamps were asked for.
+        assert!(response.segments.is_none());
+
+        // Other formats are converted from the verbose transcription.
+        for _ in 0..2 {
+            server.enqueue(
+                "/audio/transcriptions",
+                MockResponse::verbose_transcription("one two"),
+            );
+        }
+        let short = || SpeechToTextRequest::new(wav("#"));
+        let text = client
+            .transcribe_chunked(
+                short().response_format(TranscriptFormat::Text),
+                &chunking,
+                &RequestOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert_eq!(text.text, "one two");
+        assert!(text.segments.is_none() && text.words.is_none());
+        let srt = client
+            .transcribe_chunked(
+                short().response_format(TranscriptFormat::Srt),
+                &chunking,
+                &RequestOptions::default(),
+            )
+            .await
+            .unwrap();
+        assert!(srt.text.starts_with("1\n00:00:00,000 --> "));
+        assert_eq!(
+            server.requests()[5].form_text("response_format").as_deref(),
+            Some("verbose_json")
+        );
+    }
+
+    #[test]
+    fn test_stitch() {
+        let first = SpeechToTextResponse {
+            text: "Hello there, general".to_string(),
+            language: Some("English".to_string()),
+            duration: Some(10.0),
+            segments: Some(vec![
+                segment(0.0, 4.0, " Hello there,"),
+                segment(8.5, 10.0, " general"),
+            ]),
+            words: Some(vec![
+                word("Hello", 0.0, 1.0),
+                word("there,", 1.0, 4.0),
+                word("general", 8.8, 9.6),
+            ]),
+            rate_limit: None,
+        };
+        let second = SpeechToTextResponse {
+            text: "General Kenobi.".to_string(),
+            language: None,
+            duration: Some(10.0),
+            segments: Some(vec![segment(0.5, 3.0, " General Kenobi.")]),
+            words: Some(vec![word("General", 0.7, 1.5), word("Kenobi.", 1.5, 3.0)]),
+            rate_limit: None,
+        };
+
+        // The chunks overlap between 8 and 10 seconds, and are cut at 9.
+        let stitched = stitch(&[(0.0, 10.0), (8.0, 18.0)], vec![first, second]);
+        assert_eq!(stitched.text, "Hello there, General Kenobi.");
+        assert_eq!(stitched.language.as_deref(), Some("English"));
+        assert_eq!(stitched.duration, Some(18.0));
+        let segments = stitched.segments.unwrap();
+        assert_eq!(segments.len(), 2);
+        assert_eq!((segments[1].id, segments[1].start), (1, 8.5));
+        let words: Vec<_> = stitched.words.unwrap();
+        let texts: Vec<_> = words.iter().map(|w| w.word.as_str()).collect();
+        assert_eq!(texts, ["Hello", "there,", "general", "Kenobi."]);
+        assert_eq!(words[3].start, 9.5);
+    }
+}
@@ -0,0 +1,1106 @@
+use crate::batches::{Batch, BatchList, CreateBatchRequest};
+use crate::cache::stable_hash;
+use crate::files::{DeletedFile, FileList, FileObject};
+use crate::limiter::{RequestCost, DEFAULT_SPEECH_TO_TEXT_MODEL};
+use crate::models::{Model, ModelList};
+use crate::{
+    ApiErrorDetails, AudioFormat, ChatCompletionRequest, ChatCompletionResponse, FileUpload,
+    GroqError, RateLimitInfo, SpeechToTextRequest, SpeechToTextResponse, TextToSpeechRequest,
+    TextToSpeechResponse, Transcript, TranscriptFormat,
+};
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderValue, ACCEPT, CONTENT_LENGTH, CONTENT_TYPE};
+use reqwest::{Method, StatusCode};
+use serde::de::DeserializeOwned;
+use serde_json::{json, Value};
+
+/// The default base URL of the Groq API.
+pub(crate) const DEFAULT_ENDPOINT: &str = "https://api.groq.com/openai/v1";
+
+/// A fully encoded request to the Groq API, relative to the client's endpoint.
+///
+/// Both `GroqClient` and `AsyncGroqClient` encode every request through `ApiRequest` and decode
+/// every response through the functions of this module, so each endpoint behaves the same in both.
+/// Hooks see, and may change, every request in this form before it is sent.
+///
+/// - `method`: The HTTP method of the request.
+/// - `path`: The path of the API resource, such as `/chat/completions`.
+/// - `headers`: The headers specific to this request, such as `Content-Type`. The default headers
+///   of the client and `Authorization` are added when it is sent.
+/// - `body`: The encoded request body, or the part of it before the file of an `upload`.
+/// - `model`: The model the request is for, if any.
+/// - `cacheable`: Whether the response is reproducible, so a `ResponseCache` may store and serve it.
+/// - `upload`: The audio file streamed after `body`, for speech-to-text requests created with
+///   `SpeechToTextRequest::from_path` or `from_reader`.
+#[derive(Debug, Clone)]
+pub struct ApiRequest {
+    pub method: Method,
+    pub path: String,
+    pub headers: HeaderMap,
+    pub body: Vec<u8>,
+    pub model: Option<String>,
+    pub cacheable: bool,
+    pub upload: Option<FileUpload>,
+    /// The model and estimated cost charged to the client's `RateLimiter` on every attempt, if
+    /// the request counts against its limits.
+    pub(crate) rate_limit: Option<(String, RequestCost)>,
+}
+
+impl ApiRequest {
+    /// Creates a request without a body.
+    fn empty(method: Method, path: &str) -> Self {
+        Self {
+            method,
+            path: path.to_string(),
+            headers: HeaderMap::new(),
+            body: Vec::new(),
+            model: None,
+            cacheable: false,
+            upload: None,
+            rate_limit: None,
+        }
+    }
+
+    /// Creates a request with a JSON body.
+    fn json(path: &str, body: &Value) -> Self {
+        Self {
+            method: Method::POST,
+            path: path.to_string(),
+            headers: HeaderMap::from_iter([(
+                CONTENT_TYPE,
+                HeaderValue::from_static("application/json"),
+            )]),
+            body: serde_json::to_vec(body).expect("JSON values always serialize"),
+            model: None,
+            cacheable: false,
+            upload: None,
+            rate_limit: None,
+        }
+    }
+
+    /// Creates a request with a `multipart/form-data` body.
+    fn multipart(path: &str, form: MultipartForm) -> Self {
+        let (content_type, body) = form.encode();
+        Self {
+            method: Method::POST,
+            path: path.to_string(),
+            headers: HeaderMap::from_iter([(
+                CONTENT_TYPE,
+                HeaderValue::from_str(&content_type).expect("boundaries are valid header values"),
+            )]),
+            body,
+            model: None,
+            cacheable: false,
+            upload: None,
+            rate_limit: None,
+        }
+    }
+
+    /// Encodes a chat completion request.
+    ///
+    /// `stream` overrides `request.stream`, since each client method only handles one kind of response.
+    pub(crate) fn chat_completion(request: &ChatCompletionRequest, stream: bool) -> Self {
+        let mut api_request =
+            Self::json("/chat/completions", &chat_completion_body(request, stream));
+        if stream {
+            api_request
+                .headers
+                .insert(ACCEPT, HeaderValue::from_static("text/event-stream"));
+        }
+        api_request.model = Some(request.model.clone());
+        // Sampled completions differ from one call to the next, so only reproducible ones are cached.
+        api_request.cacheable =
+            !stream && (request.seed.is_some() || request.temperature == Some(0.0));
+        api_request.rate_limit =
+            Some((request.model.clone(), RequestCost::chat_completion(request)));
+        api_request
+    }
+
+    /// Encodes a speech-to-text request, using the translation endpoint if `english_text` is set.
+    pub(crate) fn speech_to_text(request: SpeechToTextRequest) -> Self {
+        let path = if request.english_text {
+            "/audio/translations"
+        } else {
+            "/audio/transcriptions"
+        };
+
+        let model = request.model.clone();
+        let rate_limit = Some((
+            model
+                .clone()
+                .unwrap_or_else(|| DEFAULT_SPEECH_TO_TEXT_MODEL.to_string()),
+            RequestCost::speech_to_text(&request),
+        ));
+        // Whisper decodes greedily unless given a temperature, so only sampled requests are not cached.
+        let cacheable = request.temperature.map_or(true, |t| t == 0.0);
+        let file_name = request.upload_file_name();
+        let content_type = request
+            .audio_format()
+            .map_or("application/octet-stream", AudioFormat::mime_type);
+        let mut form = MultipartForm::default();
+        if let Some(url) = request.url {
+            form = form.text("url", url);
+        } else if request.source.is_some() {
+            form = form.stream("file", &file_name, content_type);
+        } else {
+            form = form.file("file", &file_name, content_type, request.file);
+        }
+        if let Some(model) = request.model {
+            form = form.text("model", model);
+        }
+        if let Some(temperature) = request.temperature {
+            form = form.text("temperature", temperature.to_string());
+        }
+        if let Some(language) = request.language {
+            form = form.text("language", language);
+        }
+        if let Some(prompt) = request.prompt {
+            form = form.text("prompt", prompt);
+        }
+        if let Some(response_format) = request.response_format {
+            form = form.text("response_format", response_format.as_str().to_string());
+        }
+        for granularity in request.timestamp_granularities.iter().flatten() {
+            form = form.text(
+                "timestamp_granularities[]",
+                granularity.as_str().to_string(),
+            );
+        }
+        let Some(source) = request.source else {
+            return Self {
+                model,
+                cacheable,
+                rate_limit,
+                ..Self::multipart(path, form)
+            };
+        };
+        // Streamed files are not read until they are sent, so their responses are not cached.
+        let (content_type, body, suffix) = form.encode_streamed();
+        let mut headers = HeaderMap::from_iter([(
+            CONTENT_TYPE,
+            HeaderValue::from_str(&content_type).expect("boundaries are valid header values"),
+        )]);
+        if let Some(len) = source.len {
+            let total = body.len() as u64 + len + suffix.len() as u64;
+            headers.insert(CONTENT_LENGTH, HeaderValue::from(total));
+        }
+        Self {
+            method: Method::POST,
+            path: path.to_string(),
+            headers,
+            body,
+            model,
+            cacheable: false,
+            upload: Some(FileUpload::new(source, suffix, request.progress)),
+            rate_limit,
+        }
+    }
+
+    /// Encodes a request listing the available models.
+    pub(crate) fn list_models() -> Self {
+        Self::empty(Method::GET, "/models")
+    }
+
+    /// Encodes a request retrieving a single model. The id is percent-encoded, since ids such
+    /// as `org/model` are common on OpenAI-compatible servers.
+    pub(crate) fn retrieve_model(id: &str) -> Self {
+        Self::empty(Method::GET, &format!("/models/{}", path_segment(id)))
+    }
+
+    /// Encodes a file upload.
+    pub(crate) fn upload_file(bytes: Vec<u8>, filename: &str, purpose: &str) -> Self {
+        let form = MultipartForm::default()
+            .text("purpose", purpose.to_string())
+            .file("file", filename, "application/octet-stream", bytes);
+        Self::multipart("/files", form)
+    }
+
+    /// Encodes a request listing the files of the account.
+    pub(crate) fn list_files() -> Self {
+        Self::empty(Method::GET, "/files")
+    }
+
+    /// Encodes a request retrieving a single file. Ids are percent-encoded like model ids.
+    pub(crate) fn retrieve_file(id: &str) -> Self {
+        Self::empty(Method::GET, &format!("/files/{}", path_segment(id)))
+    }
+
+    /// Encodes a request deleting a file.
+    pub(crate) fn delete_file(id: &str) -> Self {
+        Self::empty(Method::DELETE, &format!("/files/{}", path_segment(id)))
+    }
+
+    /// Encodes a request downloading the content of a file.
+    pub(crate) fn file_content(id: &str) -> Self {
+        Self::empty(Method::GET, &format!("/files/{}/content", path_segment(id)))
+    }
+
+    /// Encodes a request creating a batch.
+    pub(crate) fn create_batch(request: &CreateBatchRequest) -> Self {
+        Self::json(
+            "/batches",
+            &serde_json::to_value(request).expect("batch requests always serialize"),
+        )
+    }
+
+    /// Encodes a request retrieving a single batch.
+    pub(crate) fn retrieve_batch(id: &str) -> Self {
+        Self::empty(Method::GET, &format!("/batches/{}", path_segment(id)))
+    }
+
+    /// Encodes a request listing the batches of the account.
+    pub(crate) fn list_batches() -> Self {
+        Self::empty(Method::GET, "/batches")
+    }
+
+    /// Encodes a request cancelling a batch.
+    pub(crate) fn cancel_batch(id: &str) -> Self {
+        Self::empty(
+            Method::POST,
+            &format!("/batches/{}/cancel", path_segment(id)),
+        )
+    }
+
+    /// Encodes a text-to-speech request.
+    pub(crate) fn text_to_speech(request: &TextToSpeechRequest) -> Self {
+        let mut body = json!({
+            "model": request.model,
+            "input": request.input,
+            "voice": request.voice,
+        });
+        if let Some(speed) = request.speed {
+            body["speed"] = json!(speed);
+        }
+        Self {
+            model: Some(request.model.clone()),
+            cacheable: true,
+            rate_limit: Some((request.model.clone(), RequestCost::default())),
+            ..Self::json("/audio/speech", &body)
+        }
+    }
+}
+
+/// A fully buffered response from the Groq API.
+///
+/// Hooks see, and may change, every response in this form before it is decoded.
+///
+/// - `status`: The HTTP status of the response.
+/// - `headers`: The response headers.
+/// - `body`: The raw response body.
+#[derive(Debug, Clone)]
+pub struct ApiResponse {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    pub body: Bytes,
+}
+
+/// Decodes a JSON response body into `T`, or the API error it describes.
+fn decode_json<T: DeserializeOwned>(response: &ApiResponse) -> Result<T, GroqError> {
+    if !response.status.is_success() {
+        return Err(decode_error(
+            response.status,
+            &response.headers,
+            &response.body,
+        ));
+    }
+    Ok(serde_json::from_slice(&response.body)?)
+}
+
+/// Decodes the response to a chat completion request.
+///
+/// When `strict` is set, responses with fields the models do not know about, or without fields
+/// the Groq API always sends, are rejected with `GroqError::JsonParseError`.
+pub(crate) fn decode_chat_completion(
+    response: ApiResponse,
+    strict: bool,
+) -> Result<ChatCompletionResponse, GroqError> {
+    let mut chat_completion: ChatCompletionResponse = decode_json(&response)?;
+    if strict {
+        let unknown = chat_completion.unknown_fields();
+        let missing = chat_completion.missing_fields();
+        if !unknown.is_empty() || !missing.is_empty() {
+            return Err(GroqError::JsonParseError(serde::de::Error::custom(format!(
+                "response does not match the chat completion schema (unknown fields: [{}], missing fields: [{}])",
+                unknown.join(", "),
+                missing.join(", ")
+            ))));
+        }
+    }
+    chat_completion.rate_limit = RateLimitInfo::from_headers(&response.headers);
+    Ok(chat_completion)
+}
+
+/// Decodes the response to a speech-to-text request made in `format`.
+pub(crate) fn decode_speech_to_text(
+    response: ApiResponse,
+    format: TranscriptFormat,
+) -> Result<SpeechToTextResponse, GroqError> {
+    let rate_limit = RateLimitInfo::from_headers(&response.headers);
+    let mut speech_to_text = decode_transcript(response, format)?.into_response();
+    speech_to_text.rate_limit = rate_limit;
+    Ok(speech_to_text)
+}
+
+/// Decodes the response to a speech-to-text request made in `format`, parsing it only for JSON formats.
+pub(crate) fn decode_transcript(
+    response: ApiResponse,
+    format: TranscriptFormat,
+) -> Result<Transcript, GroqError> {
+    if format.is_json() {
+        let mut speech_to_text: SpeechToTextResponse = decode_json(&response)?;
+        speech_to_text.rate_limit = RateLimitInfo::from_headers(&response.headers);
+        return Ok(Transcript::Json(speech_to_text));
+    }
+    if !response.status.is_success() {
+        return Err(decode_error(
+            response.status,
+            &response.headers,
+            &response.body,
+        ));
+    }
+    let content = String::from_utf8_lossy(&response.body).into_owned();
+    Ok(match format {
+        TranscriptFormat::Text => Transcript::Text(content),
+        format => Transcript::Subtitles { format, content },
+    })
+}
+
+/// Decodes the response to a model listing request.
+pub(crate) fn decode_models(response: ApiResponse) -> Result<Vec<Model>, GroqError> {
+    let list: ModelList = decode_json(&response)?;
+    Ok(list.data)
+}
+
+/// Decodes the response to a model retrieval request.
+pub(crate) fn decode_model(response: ApiResponse) -> Result<Model, GroqError> {
+    decode_json(&response)
+}
+
+/// Decodes the response to a file upload or retrieval request.
+pub(crate) fn decode_file(response: ApiResponse) -> Result<FileObject, GroqError> {
+    decode_json(&response)
+}
+
+/// Decodes the response to a file listing request.
+pub(crate) fn decode_files(response: ApiResponse) -> Result<Vec<FileObject>, GroqError> {
+    let list: FileList = decode_json(&response)?;
+    Ok(list.data)
+}
+
+/// Decodes the response to a file deletion request.
+pub(crate) fn decode_deleted_file(response: ApiResponse) -> Result<DeletedFile, GroqError> {
+    decode_json(&response)
+}
+
+/// Decodes the content of a file.
+pub(crate) fn decode_file_content(response: ApiResponse) -> Result<Vec<u8>, GroqError> {
+    if !response.status.is_success() {
+        return Err(decode_error(
+            response.status,
+            &response.headers,
+            &response.body,
+        ));
+    }
+    Ok(response.body.to_vec())
+}
+
+/// Decodes the response to a batch creation, retrieval or cancellation request.
+pub(crate) fn decode_batch(response: ApiResponse) -> Result<Batch, GroqError> {
+    decode_json(&response)
+}
+
+/// Decodes the response to a batch listing request.
+pub(crate) fn decode_batches(response: ApiResponse) -> Result<Vec<Batch>, GroqError> {
+    let list: BatchList = decode_json(&response)?;
+    Ok(list.data)
+}
+
+/// Decodes the audio returned by a text-to-speech request.
+pub(crate) fn decode_text_to_speech(
+    response: ApiResponse,
+) -> Result<TextToSpeechResponse, GroqError> {
+    if !response.status.is_success() {
+        return Err(decode_error(
+            response.status,
+            &response.headers,
+            &response.body,
+        ));
+    }
+    Ok(TextToSpeechResponse {
+        audio_data: response.body.to_vec(),
+        rate_limit: RateLimitInfo::from_headers(&response.headers),
+    })
+}
+
+/// Builds a `GroqError` from an unsuccessful response and its raw body.
+pub(crate) fn decode_error(status: StatusCode, headers: &HeaderMap, body: &[u8]) -> GroqError {
+    api_error(Some(status), headers, serde_json::from_slice(body).ok())
+}
+
+/// Builds a `GroqError` from an unsuccessful response and its JSON body, if any.
+///
+/// `status` is `None` for error objects sent inside a stream after a successful response.
+pub(crate) fn api_error(
+    status: Option<StatusCode>,
+    headers: &HeaderMap,
+    body: Option<Value>,
+) -> GroqError {
+    let string = |value: &Value| value.as_str().map(str::to_string);
+    let mut details = ApiErrorDetails {
+        status: status.map(|s| s.as_u16()),
+        request_id: headers
+            .get("x-request-id")
+            .and_then(|v| v.to_str().ok())
+            .map(str::to_string),
+        rate_limit: RateLimitInfo::from_headers(headers),
+        ..ApiErrorDetails::default()
+    };
+    if let Some(error) = body.as_ref().and_then(|b| b.get("error")) {
+        // JSON mode rejects generations that are not valid JSON or do not match the schema.
+        if error["code"].as_str() == Some("json_validate_failed") {
+            return GroqError::StructuredOutputError {
+                message: error["message"]
+                    .as_str()
+                    .unwrap_or("Failed to validate JSON")
+                    .to_string(),
+                content: error["failed_generation"].as_str().map(str::to_string),
+            };
+        }
+        details.message = error["message"]
+            .as_str()
+            .unwrap_or("Unknown error")
+            .to_string();
+        details.type_ = string(&error["type"]);
+        details.code = string(&error["code"]);
+        details.param = string(&error["param"]);
+    } else if let Some(status) = status {
+        details.message = format!("Request failed with status code: {}", status);
+    } else {
+        details.message = "Unknown error".to_string();
+    }
+    classify_error(details)
+}
+
+/// Picks the `GroqError` variant for an API error, from its code and type first, then its status.
+fn classify_error(details: ApiErrorDetails) -> GroqError {
+    let code = details.code.as_deref().unwrap_or_default();
+    let type_ = details.type_.as_deref().unwrap_or_default();
+    let message = details.message.to_lowercase();
+    let status = details.status.unwrap_or_default();
+
+    let variant: fn(Box<ApiErrorDetails>) -> GroqError =
+        if status == 429 || code == "rate_limit_exceeded" {
+            GroqError::RateLimited
+        } else if code == "context_length_exceeded"
+            || message.contains("context length")
+            || message.contains("context_length")
+        {
+            GroqError::ContextLengthExceeded
+        } else if matches!(status, 401 | 403)
+            || code == "invalid_api_key"
+            || type_ == "authentication_error"
+        {
+            GroqError::Unauthorized
+        } else if status >= 500 || matches!(type_, "server_error" | "internal_server_error") {
+            GroqError::ServerError
+        } else if matches!(status, 400 | 404 | 413 | 422) || type_ == "invalid_request_error" {
+            GroqError::InvalidRequest
+        } else {
+            GroqError::ApiError
+        };
+    variant(Box::new(details))
+}
+
+/// Parses the content of the first choice of a JSON mode response into `T`.
+pub(crate) fn structured_output<T: DeserializeOwned>(
+    response: &ChatCompletionResponse,
+) -> Result<T, GroqError> {
+    let content = response
+        .choices
+        .first()
+        .and_then(|choice| choice.message.content.as_deref())
+        .ok_or_else(|| GroqError::StructuredOutputError {
+            message: "Response has no message content".to_string(),
+            content: None,
+        })?;
+    serde_json::from_str(content).map_err(|e| GroqError::StructuredOutputError {
+        message: e.to_string(),
+        content: Some(content.to_string()),
+    })
+}
+
+/// Builds the JSON body of a chat completion request.
+pub(crate) fn chat_completion_body(request: &ChatCompletionRequest, stream: bool) -> Value {
+    let messages = request
+        .messages
+        .iter()
+        .map(|m| {
+            let mut msg_json = json!({
+                "role": m.role,
+                "content": m.content,
+            });
+            if let Some(name) = &m.name {
+                msg_json["name"] = json!(name);
+            }
+            if let Some(tool_calls) = &m.tool_calls {
+                msg_json["tool_calls"] = json!(tool_calls);
+            }
+            if let Some(tool_call_id) = &m.tool_call_id {
+                msg_json["tool_call_id"] = json!(tool_call_id);
+            }
+            msg_json
+        })
+        .collect::<Vec<Value>>();
+
+    let mut body = json!({
+        "model": request.model,
+        "messages": messages,
+        "stream": stream,
+    });
+
+    // Unset parameters are left out, so the API applies its own defaults.
+    let optional = [
+        ("temperature", json!(request.temperature)),
+        ("max_tokens", json!(request.max_tokens)),
+        ("top_p", json!(request.top_p)),
+        ("stop", json!(request.stop)),
+        ("seed", json!(request.seed)),
+        ("tools", json!(request.tools)),
+        ("tool_choice", json!(request.tool_choice)),
+        ("response_format", json!(request.response_format)),
+        ("n", json!(request.n)),
+        ("presence_penalty", json!(request.presence_penalty)),
+        ("frequency_penalty", json!(request.frequency_penalty)),
+        ("logit_bias", json!(request.logit_bias)),
+        ("logprobs", json!(request.logprobs)),
+        ("top_logprobs", json!(request.top_logprobs)),
+        ("user", json!(request.user)),
+        ("parallel_tool_calls", json!(request.parallel_tool_calls)),
+        ("service_tier", json!(request.service_tier)),
+    ];
+    for (name, value) in optional {
+        if !value.is_null() {
+            body[name] = value;
+        }
+    }
+    body
+}
+
+/// Percent-encodes `segment` for use as a single path segment, leaving only unreserved
+/// characters as they are.
+fn path_segment(segment: &str) -> String {
+    let mut encoded = String::with_capacity(segment.len());
+    for byte in segment.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// A single part of a `multipart/form-data` body.
+#[derive(Debug, Clone)]
+enum FormPart {
+    Text {
+        name: &'static str,
+        value: String,
+    },
+    File {
+        name: &'static str,
+        file_name: String,
+        content_type: &'static str,
+        bytes: Vec<u8>,
+    },
+    /// A file sent after the encoded form, in place of this part.
+    Stream {
+        name: &'static str,
+        file_name: String,
+        content_type: &'static str,
+    },
+}
+
+/// A `multipart/form-data` body, encoded by hand so that both clients send identical bytes.
+#[derive(Debug, Clone, Default)]
+struct MultipartForm {
+    parts: Vec<FormPart>,
+}
+
+impl MultipartForm {
+    fn text(mut self, name: &'static str, value: String) -> Self {
+        self.parts.push(FormPart::Text { name, value });
+        self
+    }
+
+    fn file(
+        mut self,
+        name: &'static str,
+        file_name: &str,
+        content_type: &'static str,
+        bytes: Vec<u8>,
+    ) -> Self {
+        self.parts.push(FormPart::File {
+            name,
+            file_name: file_name.to_string(),
+            content_type,
+            bytes,
+        });
+        self
+    }
+
+    /// Adds a file part whose content is streamed when the form is sent.
+    fn stream(mut self, name: &'static str, file_name: &str, content_type: &'static str) -> Self {
+        self.parts.push(FormPart::Stream {
+            name,
+            file_name: file_name.to_string(),
+            content_type,
+        });
+        self
+    }
+
+    /// Derives the boundary from the form contents, so encoding the same form twice is
+    /// deterministic, with any Rust version, and gives the same `ResponseCache` key.
+    fn boundary(&self) -> String {
+        let hash = stable_hash(self.parts.iter().flat_map(|part| match part {
+            FormPart::Text { name, value } => [name.as_bytes(), value.as_bytes(), &[]],
+            FormPart::File {
+                name,
+                file_name,
+                bytes,
+                ..
+            } => [name.as_bytes(), file_name.as_bytes(), bytes.as_slice()],
+            FormPart::Stream {
+                name, file_name, ..
+            } => [name.as_bytes(), file_name.as_bytes(), &[]],
+        }));
+        format!("groq-rs-{:016x}", hash as u64)
+    }
+
+    /// Returns the `Content-Type` header value and the encoded body.
+    fn encode(self) -> (String, Vec<u8>) {
+        let (content_type, body, _) = self.encode_parts();
+        (content_type, body)
+    }
+
+    /// Returns the `Content-Type` header value and the body before and after the streamed file.
+    fn encode_streamed(self) -> (String, Vec<u8>, Vec<u8>) {
+        let (content_type, mut body, split) = self.encode_parts();
+        let suffix = body.split_off(split.unwrap_or(body.len()));
+        (content_type, body, suffix)
+    }
+
+    /// Returns the `Content-Type` header value, the encoded body and the offset of the streamed
+    /// file in it, if any.
+    fn encode_parts(self) -> (String, Vec<u8>, Option<usize>) {
+        let boundary = self.boundary();
+        let mut body = Vec::new();
+        let mut split = None;
+        let file_header = |name: &str, file_name: &str, content_type: &str| {
+            format!(
+                "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\n\
+                 Content-Type: {}\r\n\r\n",
+                name,
+                file_name.replace(['"', '\r', '\n'], "_"),
+                content_type
+            )
+        };
+        for part in self.parts {
+            body.extend_from_slice(format!("--{}\r\n", boundary).as_bytes());
+            match part {
+                FormPart::Text { name, value } => {
+                    body.extend_from_slice(
+                        format!("Content-Disposition: form-data; name=\"{}\"\r\n\r\n", name)
+                            .as_bytes(),
+                    );
+                    body.extend_from_slice(value.as_bytes());
+                }
+                FormPart::File {
+                    name,
+                    file_name,
+                    content_type,
+                    bytes,
+                } => {
+                    body.extend_from_slice(file_header(name, &file_name, content_type).as_bytes());
+                    body.extend_from_slice(&bytes);
+                }
+                FormPart::Stream {
+                    name,
+                    file_name,
+                    content_type,
+                } => {
+                    body.extend_from_slice(file_header(name, &file_name, content_type).as_bytes());
+                    split = Some(body.len());
+                }
+            }
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", boundary).as_bytes());
+        (
+            format!("multipart/form-data; boundary={}", boundary),
+            body,
+            split,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        ChatCompletionMessage, ChatCompletionRoles, Choice, FinishReason, LogProbs, Message,
+        ResponseFormat, ServiceTier, Tool, ToolChoice,
+    };
+    use std::collections::HashMap;
+
+    fn body(request: &ApiRequest) -> Value {
+        serde_json::from_slice(&request.body).unwrap()
+    }
+
+    #[test]
+    fn test_chat_completion_body_tools() {
+        let messages = vec![
+            ChatCompletionMessage::new(ChatCompletionRoles::User, "Weather in Paris?"),
+            ChatCompletionMessage::tool("call_1", "{\"temp\":21}"),
+        ];
+        let request = ChatCompletionRequest::new("llama3-70b-8192", messages)
+            .tools(vec![Tool::function(
+                "get_weather",
+                "Get the current weather for a city",
+                json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+            )])
+            .tool_choice(ToolChoice::Function("get_weather".to_string()));
+        let body = body(&ApiRequest::chat_completion(&request, false));
+        assert_eq!(body["messages"][1]["role"], "tool");
+        assert_eq!(body["messages"][1]["tool_call_id"], "call_1");
+        assert!(body["messages"][0].get("tool_call_id").is_none());
+        assert_eq!(body["tools"][0]["type"], "function");
+        assert_eq!(body["tools"][0]["function"]["name"], "get_weather");
+        assert_eq!(
+            body["tool_choice"],
+            json!({"type": "function", "function": {"name": "get_weather"}})
+        );
+    }
+
+    #[test]
+    fn test_chat_completion_body_sampling() {
+        let messages = vec![ChatCompletionMessage::new(ChatCompletionRoles::User, "Hi")];
+        let request = ChatCompletionRequest::new("llama3-70b-8192", messages);
+        let sent = body(&ApiRequest::chat_completion(&request, false));
+        // Nothing the caller left unset is sent.
+        assert_eq!(
+            sent.as_object().unwrap().keys().collect::<Vec<_>>(),
+            vec!["messages", "model", "stream"]
+        );
+
+        let request = request
+            .n(2)
+            .presence_penalty(0.5)
+            .frequency_penalty(-0.5)
+            .logit_bias(HashMap::from([(1734, -100.0)]))
+            .top_logprobs(3)
+            .user("user_1")
+            .parallel_tool_calls(false)
+            .service_tier(ServiceTier::Flex);
+        let sent = body(&ApiRequest::chat_completion(&request, false));
+        assert_eq!(sent["n"], 2);
+        assert_eq!(sent["presence_penalty"], 0.5);
+        assert_eq!(sent["frequency_penalty"], -0.5);
+        assert_eq!(sent["logit_bias"], json!({"1734": -100.0}));
+        assert_eq!(sent["logprobs"], true);
+        assert_eq!(sent["top_logprobs"], 3);
+        assert_eq!(sent["user"], "user_1");
+        assert_eq!(sent["parallel_tool_calls"], false);
+        assert_eq!(sent["service_tier"], "flex");
+        assert!(sent.get("temperature").is_none());
+    }
+
+    #[test]
+    fn test_logprobs_response() {
+        let choice: Choice = serde_json::from_value(json!({
+            "index": 0,
+            "finish_reason": "stop",
+            "message": {"role": "assistant", "content": "Yes."},
+            "logprobs": {"content": [
+                {"token": "Yes", "logprob": -0.1, "bytes": [89, 101, 115], "top_logprobs": [
+                    {"token": "Yes", "logprob": -0.1, "bytes": [89, 101, 115]},
+                    {"token": "No", "logprob": -2.4, "bytes": [78, 111]}
+                ]},
+                {"token": ".", "logprob": -0.3, "bytes": [46], "top_logprobs": []}
+            ]}
+        }))
+        .unwrap();
+        let logprobs = choice.logprobs.unwrap();
+        assert_eq!(logprobs.tokens()[0].top_logprobs[1].token, "No");
+        assert!((logprobs.mean_logprob().unwrap() + 0.2).abs() < 1e-9);
+        assert!((logprobs.confidence().unwrap() - (-0.2f64).exp()).abs() < 1e-9);
+        assert!(LogProbs::default().confidence().is_none());
+    }
+
+    #[test]
+    fn test_tool_call_response() {
+        let message: Message = serde_json::from_value(json!({
+            "role": "assistant",
+            "tool_calls": [{
+                "id": "call_1",
+                "type": "function",
+                "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+            }]
+        }))
+        .unwrap();
+        assert!(message.content.is_none());
+        let call = &message.tool_calls.as_ref().unwrap()[0];
+        let args: Value = call.function.parse_arguments().unwrap();
+        assert_eq!(args["city"], "Paris");
+
+        let request = ChatCompletionRequest::new("llama3-70b-8192", vec![message.into()]);
+        let echoed = body(&ApiRequest::chat_completion(&request, false));
+        assert_eq!(echoed["messages"][0]["tool_calls"][0]["id"], "call_1");
+    }
+
+    #[test]
+    fn test_structured_output() {
+        #[derive(serde::Deserialize)]
+        struct Intent {
+            action: String,
+        }
+
+        let request = ChatCompletionRequest::new("llama3-70b-8192", vec![]).response_format(
+            ResponseFormat::json_schema("intent", json!({"type": "object"})),
+        );
+        let body = body(&ApiRequest::chat_completion(&request, false));
+        assert_eq!(body["response_format"]["type"], "json_schema");
+        assert_eq!(body["response_format"]["json_schema"]["name"], "intent");
+
+        let response = |content: &str| -> ChatCompletionResponse {
+            serde_json::from_value(json!({
+                "choices": [{
+                    "finish_reason": "stop",
+                    "index": 0,
+                    "logprobs": null,
+                    "message": {"role": "assistant", "content": content}
+                }],
+                "created": 0,
+                "id": "chatcmpl-1",
+                "model": "llama3-70b-8192",
+                "object": "chat.completion",
+                "system_fingerprint": "fp_1",
+                "usage": {
+                    "completion_time": 0.0, "completion_tokens": 1, "prompt_time": 0.0,
+                    "prompt_tokens": 1, "total_time": 0.0, "total_tokens": 2
+                },
+                "x_groq": {"id": "req_1"}
+            }))
+            .unwrap()
+        };
+        let intent: Intent = structured_output(&response("{\"action\":\"remind\"}")).unwrap();
+        assert_eq!(intent.action, "remind");
+        assert!(matches!(
+            structured_output::<Intent>(&response("not json")),
+            Err(GroqError::StructuredOutputError {
+                content: Some(_),
+                ..
+            })
+        ));
+
+        let error = decode_error(
+            StatusCode::BAD_REQUEST,
+            &HeaderMap::new(),
+            br#"{"error": {
+                "message": "Failed to generate JSON",
+                "type": "invalid_request_error",
+                "code": "json_validate_failed",
+                "failed_generation": "{\"action\":"
+            }}"#,
+        );
+        assert!(matches!(error, GroqError::StructuredOutputError { .. }));
+    }
+
+    #[test]
+    fn test_resource_paths() {
+        assert_eq!(
+            ApiRequest::retrieve_model("llama-3.1-8b-instant").path,
+            "/models/llama-3.1-8b-instant"
+        );
+        assert_eq!(
+            ApiRequest::retrieve_model("meta-llama/Llama 3").path,
+            "/models/meta-llama%2FLlama%203"
+        );
+        assert_eq!(ApiRequest::retrieve_file("file_01").path, "/files/file_01");
+        assert_eq!(
+            ApiRequest::delete_file("../batches").path,
+            "/files/..%2Fbatches"
+        );
+        assert_eq!(ApiRequest::file_content("a/b").path, "/files/a%2Fb/content");
+        assert_eq!(
+            ApiRequest::retrieve_batch("batch?x=1").path,
+            "/batches/batch%3Fx%3D1"
+        );
+        assert_eq!(
+            ApiRequest::cancel_batch("batch#1").path,
+            "/batches/batch%231/cancel"
+        );
+    }
+
+    #[test]
+    fn test_text_to_speech_body() {
+        let request = TextToSpeechRequest::new(None, "Hello!", None, None);
+        let encoded = body(&ApiRequest::text_to_speech(&request));
+        assert_eq!(encoded["input"], "Hello!");
+        // An unset speed is left to the server rather than sent as 1.0.
+        assert!(encoded.get("speed").is_none());
+        let encoded = body(&ApiRequest::text_to_speech(&request.speed(1.5)));
+        assert_eq!(encoded["speed"], 1.5);
+    }
+
+    #[test]
+    fn test_speech_to_text_multipart() {
+        let request = SpeechToTextRequest::new(b"RIFF....WAVE".to_vec())
+            .model("whisper-large-v3")
+            .prompt("dashi")
+            .response_format(TranscriptFormat::Json);
+        let encoded = ApiRequest::speech_to_text(request.clone());
+        assert_eq!(encoded.path, "/audio/transcriptions");
+        let content_type = encoded.headers[CONTENT_TYPE].to_str().unwrap();
+        let boundary = content_type.split("boundary=").nth(1).unwrap();
+        let body = String::from_utf8(encoded.body.clone()).unwrap();
+        assert!(body.starts_with(&format!("--{}\r\n", boundary)));
+        assert!(body.ends_with(&format!("--{}--\r\n", boundary)));
+        assert!(
+            body.contains("name=\"file\"; filename=\"audio.wav\"\r\nContent-Type: audio/wav\r\n")
+        );
+        assert!(body.contains("name=\"prompt\"\r\n\r\ndashi\r\n"));
+        assert!(body.contains("name=\"response_format\"\r\n\r\njson\r\n"));
+
+        // Encoding is deterministic, including the boundary.
+        assert_eq!(
+            ApiRequest::speech_to_text(request.clone()).body,
+            encoded.body
+        );
+
+        // Sampled transcriptions are not cached, as with chat completions.
+        assert!(encoded.cacheable);
+        assert!(ApiRequest::speech_to_text(request.clone().temperature(0.0)).cacheable);
+        assert!(!ApiRequest::speech_to_text(request.temperature(0.7)).cacheable);
+
+        // Requests are charged to their model, or to the one the API transcribes with by default.
+        let (model, cost) = encoded.rate_limit.unwrap();
+        assert_eq!(model, "whisper-large-v3");
+        assert_eq!(cost.audio_seconds, 10.0);
+        let encoded = ApiRequest::speech_to_text(SpeechToTextRequest::new(b"RIFF".to_vec()));
+        assert_eq!(encoded.rate_limit.unwrap().0, DEFAULT_SPEECH_TO_TEXT_MODEL);
+    }
+
+    #[test]
+    fn test_decode_error_without_json_body() {
+        let error = decode_error(
+            StatusCode::BAD_GATEWAY,
+            &HeaderMap::new(),
+            b"<html>bad gateway</html>",
+        );
+        assert!(matches!(error, GroqError::ServerError(_)));
+        assert_eq!(error.status(), Some(502));
+        assert!(error.is_retryable());
+    }
+
+    #[test]
+    fn test_decode_error_classification() {
+        let mut headers = HeaderMap::new();
+        headers.insert("x-request-id", "req_01".parse().unwrap());
+        headers.insert("retry-after", "7".parse().unwrap());
+        let decode = |status: u16, body: &str| {
+            decode_error(
+                StatusCode::from_u16(status).unwrap(),
+                &headers,
+                body.as_bytes(),
+            )
+        };
+
+        let error = decode(
+            401,
+            r#"{"error":{"message":"Invalid API Key","type":"invalid_request_error","code":"invalid_api_key"}}"#,
+        );
+        assert!(matches!(error, GroqError::Unauthorized(_)));
+        assert_eq!(error.request_id(), Some("req_01"));
+        assert!(!error.is_retryable());
+
+        let error = decode(
+            429,
+            r#"{"error":{"message":"Rate limit reached","type":"tokens","code":"rate_limit_exceeded"}}"#,
+        );
+        assert!(matches!(error, GroqError::RateLimited(_)));
+        assert_eq!(error.retry_after(), Some(std::time::Duration::from_secs(7)));
+        assert!(error.is_retryable());
+
+        let error = decode(
+            400,
+            r#"{"error":{"message":"Please reduce the length of the messages or completion.","type":"invalid_request_error","param":"messages","code":"context_length_exceeded"}}"#,
+        );
+        assert!(matches!(error, GroqError::ContextLengthExceeded(_)));
+        assert_eq!(error.details().unwrap().param.as_deref(), Some("messages"));
+
+        let error = decode(
+            404,
+            r#"{"error":{"message":"The model `llama9` does not exist","type":"invalid_request_error","code":"model_not_found"}}"#,
+        );
+        assert!(matches!(error, GroqError::InvalidRequest(_)));
+        assert_eq!(
+            error.details().unwrap().code.as_deref(),
+            Some("model_not_found")
+        );
+        assert_eq!(
+            error.to_string(),
+            "Invalid request: The model `llama9` does not exist (status 404, request req_01)"
+        );
+
+        let error = decode(409, r#"{"error":{"message":"Conflict"}}"#);
+        assert!(matches!(error, GroqError::ApiError(_)));
+    }
+
+    #[test]
+    fn test_decode_chat_completion_forward_compatible() {
+        // An OpenAI-compatible response without the Groq-specific fields, with a new field and
+        // an unknown finish reason.
+        let response = || ApiResponse {
+            status: StatusCode::OK,
+            headers: HeaderMap::new(),
+            body: Bytes::from(
+                json!({
+                    "id": "chatcmpl-1",
+                    "model": "llama-3.3-70b-versatile",
+                    "choices": [{
+                        "index": 0,
+                        "message": { "role": "assistant", "content": "Hi", "reasoning": "..." },
+                        "finish_reason": "max_reasoning_tokens"
+                    }],
+                    "service_tier": "on_demand"
+                })
+                .to_string(),
+            ),
+        };
+
+        let chat_completion = decode_chat_completion(response(), false).unwrap();
+        assert_eq!(
+            chat_completion.choices[0].message.content,
+            Some("Hi".to_string())
+        );
+        assert_eq!(
+            chat_completion.choices[0].finish_reason,
+            Some(FinishReason::Unknown("max_reasoning_tokens".to_string()))
+        );
+        assert!(chat_completion.usage.is_none());
+        assert_eq!(
+            chat_completion.unknown_fields(),
+            vec!["choices[0].message.reasoning", "service_tier"]
+        );
+        assert_eq!(
+            chat_completion.missing_fields(),
+            vec!["system_fingerprint", "usage", "x_groq"]
+        );
+
+        let error = decode_chat_completion(response(), true).unwrap_err();
+        assert!(matches!(error, GroqError::JsonParseError(_)));
+        assert!(error.to_string().contains("service_tier"));
+    }
+}
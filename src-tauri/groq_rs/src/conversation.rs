@@ -0,0 +1,474 @@
+use crate::{
+    AsyncGroqClient, ChatCompletionMessage, ChatCompletionRequest, ChatCompletionResponse,
+    ChatCompletionRoles, GroqClient, GroqError, ModelRegistry, TokenEstimator,
+};
+
+/// The context window assumed for models missing from the default `ModelRegistry`.
+pub const DEFAULT_CONTEXT_WINDOW: u32 = 8_192;
+
+/// The number of tokens reserved for the completion when `max_tokens` is not set.
+pub const DEFAULT_MAX_TOKENS: u32 = 1_024;
+
+/// The instructions sent with the turns to summarize.
+const SUMMARY_PROMPT: &str = "You maintain the memory of a conversation between a user and an \
+assistant. Summarize the conversation below in a few short paragraphs, keeping every fact, \
+decision, name, number and open question the assistant needs to continue it. Reply with the \
+summary only.";
+
+/// Represents how a `Conversation` makes room when its history no longer fits the context window.
+///
+/// - `Trim`: Drops the oldest turns.
+/// - `Summarize`: Replaces every turn but the `keep_turns` most recent ones with a summary written
+///   by the model. Turns are still trimmed if the summary and recent turns do not fit.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HistoryPolicy {
+    Trim,
+    Summarize { keep_turns: usize },
+}
+
+/// A chat session that owns a system prompt and the message history, and builds the
+/// `ChatCompletionRequest` of each turn.
+///
+/// A turn starts with a `User` message and holds every message that follows it, including
+/// tool calls and their results, so history is always dropped or summarized a whole turn at a
+/// time. Before each turn, the prompt is kept below `threshold` of the context window of the
+/// model, minus the `max_tokens` reserved for the completion, according to the `HistoryPolicy`.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::{models, ChatCompletionMessage, ChatCompletionRoles, Conversation, HistoryPolicy};
+///
+/// let mut conversation = Conversation::new(models::LLAMA_3_3_70B_VERSATILE)
+///     .system_prompt("You are a concise voice assistant.")
+///     .policy(HistoryPolicy::Summarize { keep_turns: 4 });
+/// conversation.push(ChatCompletionMessage::new(ChatCompletionRoles::User, "Hello!"));
+///
+/// // `send` and `send_blocking` compact the history, send the request and record the reply.
+/// let request = conversation.request();
+/// assert_eq!(request.messages.len(), 2);
+/// assert!(!conversation.needs_compaction());
+///```
+#[derive(Debug, Clone)]
+pub struct Conversation {
+    template: ChatCompletionRequest,
+    system_prompt: Option<String>,
+    summary: Option<String>,
+    history: Vec<ChatCompletionMessage>,
+    context_window: Option<u32>,
+    threshold: f64,
+    policy: HistoryPolicy,
+    summary_model: Option<String>,
+}
+
+impl Conversation {
+    /// Creates an empty conversation with the given model, trimming old turns by default.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The language model to chat with.
+    pub fn new(model: &str) -> Self {
+        Self {
+            template: ChatCompletionRequest::new(model, Vec::new()),
+            system_prompt: None,
+            summary: None,
+            history: Vec::new(),
+            context_window: None,
+            threshold: 0.9,
+            policy: HistoryPolicy::Trim,
+            summary_model: None,
+        }
+    }
+
+    /// Sets the system prompt sent at the start of every request.
+    pub fn system_prompt(mut self, system_prompt: &str) -> Self {
+        self.system_prompt = Some(system_prompt.to_string());
+        self
+    }
+
+    /// Sets the request every turn is built from, for parameters such as `temperature` or `tools`.
+    ///
+    /// # Arguments
+    ///
+    /// * `template` - The request to copy. Its model is used, and its messages are ignored.
+    pub fn template(mut self, template: ChatCompletionRequest) -> Self {
+        self.template = template;
+        self.template.messages.clear();
+        self
+    }
+
+    /// Sets the number of prompt and completion tokens the model accepts.
+    ///
+    /// Defaults to the context window of the model in `ModelRegistry::default()`, then to
+    /// `DEFAULT_CONTEXT_WINDOW`.
+    pub fn context_window(mut self, context_window: u32) -> Self {
+        self.context_window = Some(context_window);
+        self
+    }
+
+    /// Sets the number of tokens reserved for, and requested from, each completion.
+    pub fn max_tokens(mut self, max_tokens: u32) -> Self {
+        self.template.max_tokens = Some(max_tokens);
+        self
+    }
+
+    /// Sets the share of the context window, between 0.0 and 1.0, the history may fill before it is compacted.
+    pub fn threshold(mut self, threshold: f64) -> Self {
+        self.threshold = threshold.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets how room is made when the history no longer fits.
+    pub fn policy(mut self, policy: HistoryPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+
+    /// Sets the model used to write summaries. Defaults to the model of the conversation.
+    pub fn summary_model(mut self, summary_model: &str) -> Self {
+        self.summary_model = Some(summary_model.to_string());
+        self
+    }
+
+    /// Returns the messages kept verbatim, oldest first.
+    pub fn history(&self) -> &[ChatCompletionMessage] {
+        &self.history
+    }
+
+    /// Returns the summary of the turns that were compacted, if any.
+    pub fn summary(&self) -> Option<&str> {
+        self.summary.as_deref()
+    }
+
+    /// Appends a message to the history.
+    pub fn push(&mut self, message: ChatCompletionMessage) {
+        self.history.push(message);
+    }
+
+    /// Appends the message of the first choice of a response to the history.
+    pub fn push_response(&mut self, response: &ChatCompletionResponse) {
+        if let Some(choice) = response.choices.first() {
+            self.history.push(choice.message.clone().into());
+        }
+    }
+
+    /// Forgets the history and its summary, keeping the system prompt and settings.
+    pub fn clear(&mut self) {
+        self.history.clear();
+        self.summary = None;
+    }
+
+    /// Returns the number of prompt tokens of the next request, estimated with the `TokenEstimator` of the model.
+    pub fn prompt_tokens(&self) -> u32 {
+        TokenEstimator::for_model(&self.template.model).count_request(&self.request())
+    }
+
+    /// Returns the number of prompt tokens the history may fill before it is compacted.
+    pub fn prompt_budget(&self) -> u32 {
+        let context_window = self.context_window.unwrap_or_else(|| {
+            ModelRegistry::default()
+                .get(&self.template.model)
+                .and_then(|capabilities| capabilities.context_window)
+                .unwrap_or(DEFAULT_CONTEXT_WINDOW)
+        });
+        ((context_window as f64 * self.threshold) as u32)
+            .saturating_sub(self.max_completion_tokens())
+    }
+
+    /// Returns whether the next request would exceed the prompt budget.
+    pub fn needs_compaction(&self) -> bool {
+        self.prompt_tokens() > self.prompt_budget()
+    }
+
+    /// Builds the request for the next turn from the template, system prompt, summary and history.
+    ///
+    /// The history is sent as is; call `compact` first, or use `send`, to keep it within the budget.
+    pub fn request(&self) -> ChatCompletionRequest {
+        let mut request = self.template.clone();
+        request.messages = self.messages();
+        request.max_tokens = Some(self.max_completion_tokens());
+        request
+    }
+
+    /// Makes room in the history according to the policy, if it exceeds the prompt budget.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the summary request with `HistoryPolicy::Summarize`. The history is
+    /// left untouched in that case.
+    pub async fn compact(&mut self, client: &AsyncGroqClient) -> Result<(), GroqError> {
+        if let Some((request, turns_end)) = self.summary_request() {
+            let response = client.chat_completion(request).await?;
+            self.apply_summary(&response, turns_end);
+        }
+        self.trim();
+        Ok(())
+    }
+
+    /// Makes room in the history with the blocking client. See `compact`.
+    pub fn compact_blocking(&mut self, client: &GroqClient) -> Result<(), GroqError> {
+        if let Some((request, turns_end)) = self.summary_request() {
+            let response = client.chat_completion(request)?;
+            self.apply_summary(&response, turns_end);
+        }
+        self.trim();
+        Ok(())
+    }
+
+    /// Sends a user message, compacting the history first if needed, and records the reply.
+    ///
+    /// # Arguments
+    ///
+    /// * `client` - The client to send the requests with.
+    /// * `content` - The content of the user message.
+    ///
+    /// # Errors
+    ///
+    /// Returns the error of the summary or chat completion request. The user message is not
+    /// kept in the history when the request fails, so it can be sent again.
+    pub async fn send(
+        &mut self,
+        client: &AsyncGroqClient,
+        content: &str,
+    ) -> Result<ChatCompletionResponse, GroqError> {
+        self.push(ChatCompletionMessage::new(
+            ChatCompletionRoles::User,
+            content,
+        ));
+        let result = match self.compact(client).await {
+            Ok(()) => client.chat_completion(self.request()).await,
+            Err(e) => Err(e),
+        };
+        self.record(result)
+    }
+
+    /// Sends a user message with the blocking client. See `send`.
+    pub fn send_blocking(
+        &mut self,
+        client: &GroqClient,
+        content: &str,
+    ) -> Result<ChatCompletionResponse, GroqError> {
+        self.push(ChatCompletionMessage::new(
+            ChatCompletionRoles::User,
+            content,
+        ));
+        let result = self
+            .compact_blocking(client)
+            .and_then(|()| client.chat_completion(self.request()));
+        self.record(result)
+    }
+
+    fn record(
+        &mut self,
+        result: Result<ChatCompletionResponse, GroqError>,
+    ) -> Result<ChatCompletionResponse, GroqError> {
+        match &result {
+            Ok(response) => self.push_response(response),
+            Err(_) => {
+                self.history.pop();
+            }
+        }
+        result
+    }
+
+    fn max_completion_tokens(&self) -> u32 {
+        self.template.max_tokens.unwrap_or(DEFAULT_MAX_TOKENS)
+    }
+
+    fn messages(&self) -> Vec<ChatCompletionMessage> {
+        let mut messages = Vec::with_capacity(self.history.len() + 2);
+        if let Some(system_prompt) = &self.system_prompt {
+            messages.push(ChatCompletionMessage::new(
+                ChatCompletionRoles::System,
+                system_prompt,
+            ));
+        }
+        if let Some(summary) = &self.summary {
+            messages.push(ChatCompletionMessage::new(
+                ChatCompletionRoles::System,
+                &format!("Summary of the earlier conversation:\n{}", summary),
+            ));
+        }
+        messages.extend(self.history.iter().cloned());
+        messages
+    }
+
+    /// Returns the index in the history of the first message of each turn.
+    fn turn_starts(&self) -> Vec<usize> {
+        let mut starts: Vec<usize> = self
+            .history
+            .iter()
+            .enumerate()
+            .filter(|(_, m)| matches!(m.role, ChatCompletionRoles::User))
+            .map(|(i, _)| i)
+            .collect();
+        // Messages before the first user message belong to a turn of their own.
+        if !self.history.is_empty() && starts.first() != Some(&0) {
+            starts.insert(0, 0);
+        }
+        starts
+    }
+
+    /// Drops the oldest turns until the prompt fits, always keeping the latest turn.
+    fn trim(&mut self) {
+        while self.needs_compaction() {
+            let starts = self.turn_starts();
+            if starts.len() < 2 {
+                break;
+            }
+            self.history.drain(..starts[1]);
+        }
+    }
+
+    /// Builds the request summarizing the turns to compact, and the end of those turns in the history.
+    fn summary_request(&self) -> Option<(ChatCompletionRequest, usize)> {
+        let HistoryPolicy::Summarize { keep_turns } = self.policy else {
+            return None;
+        };
+        if !self.needs_compaction() {
+            return None;
+        }
+        let starts = self.turn_starts();
+        // The latest turn holds the message being answered, so it is never summarized.
+        let keep_turns = keep_turns.max(1);
+        if starts.len() <= keep_turns {
+            return None;
+        }
+        let turns_end = starts[starts.len() - keep_turns];
+
+        let mut transcript = String::new();
+        if let Some(summary) = &self.summary {
+            transcript.push_str(&format!(
+                "Summary of the earlier conversation:\n{}\n\n",
+                summary
+            ));
+        }
+        for message in &self.history[..turns_end] {
+            let role = match message.role {
+                ChatCompletionRoles::System => "system",
+                ChatCompletionRoles::User => "user",
+                ChatCompletionRoles::Assistant => "assistant",
+                ChatCompletionRoles::Tool => "tool",
+            };
+            transcript.push_str(&format!("{}: {}\n", role, message.content.text()));
+        }
+
+        let model = self
+            .summary_model
+            .as_deref()
+            .unwrap_or(&self.template.model);
+        let request = ChatCompletionRequest::new(
+            model,
+            vec![
+                ChatCompletionMessage::new(ChatCompletionRoles::System, SUMMARY_PROMPT),
+                ChatCompletionMessage::new(ChatCompletionRoles::User, &transcript),
+            ],
+        )
+        .temperature(0.0)
+        .max_tokens(self.max_completion_tokens());
+        Some((request, turns_end))
+    }
+
+    /// Replaces the summarized turns with the summary in `response`, if it has one.
+    fn apply_summary(&mut self, response: &ChatCompletionResponse, turns_end: usize) {
+        let summary = response
+            .choices
+            .first()
+            .and_then(|choice| choice.message.content.as_deref())
+            .map(str::trim)
+            .filter(|summary| !summary.is_empty());
+        if let Some(summary) = summary {
+            self.summary = Some(summary.to_string());
+            self.history.drain(..turns_end);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{MockGroqServer, MockResponse};
+
+    /// A message taking 19 tokens: 14 for the digits and 5 for the chat template.
+    fn message(role: ChatCompletionRoles, n: usize) -> ChatCompletionMessage {
+        ChatCompletionMessage::new(role, &format!("{:0>40}", n))
+    }
+
+    fn conversation(policy: HistoryPolicy, turns: usize) -> Conversation {
+        let mut conversation = Conversation::new("llama-3.3-70b-versatile")
+            .system_prompt("Be brief.")
+            .context_window(140)
+            .threshold(1.0)
+            .max_tokens(20)
+            .policy(policy);
+        for n in 0..turns {
+            conversation.push(message(ChatCompletionRoles::User, n));
+            conversation.push(message(ChatCompletionRoles::Assistant, n));
+        }
+        conversation
+    }
+
+    #[test]
+    fn test_trim() {
+        let server = MockGroqServer::start();
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let mut conversation = conversation(HistoryPolicy::Trim, 3);
+        assert_eq!(conversation.prompt_budget(), 120);
+        // The reply header, the system prompt and three turns.
+        assert_eq!(conversation.prompt_tokens(), 5 + 8 + 6 * 19);
+
+        conversation.send_blocking(&client, "Hi").unwrap();
+        // The first turn was dropped to fit the third one.
+        let request = server.requests()[0].json().unwrap();
+        let messages = request["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 6);
+        assert_eq!(messages[1]["content"], format!("{:0>40}", 1));
+        assert_eq!(request["max_tokens"], 20);
+        assert_eq!(conversation.history().len(), 6);
+        assert!(conversation.summary().is_none());
+    }
+
+    #[test]
+    fn test_summarize() {
+        let server = MockGroqServer::start();
+        server.enqueue(
+            "/chat/completions",
+            MockResponse::chat_completion("They counted."),
+        );
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let mut conversation = conversation(HistoryPolicy::Summarize { keep_turns: 1 }, 3);
+
+        conversation.send_blocking(&client, "Hi").unwrap();
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let summarized = requests[0].json().unwrap();
+        assert!(summarized["messages"][1]["content"]
+            .as_str()
+            .unwrap()
+            .contains(&format!("assistant: {:0>40}", 2)));
+
+        let request = requests[1].json().unwrap();
+        let messages = request["messages"].as_array().unwrap();
+        assert_eq!(messages.len(), 3);
+        assert_eq!(
+            messages[1]["content"],
+            "Summary of the earlier conversation:\nThey counted."
+        );
+        assert_eq!(messages[2]["content"], "Hi");
+        assert_eq!(conversation.summary(), Some("They counted."));
+        assert_eq!(conversation.history().len(), 2);
+    }
+
+    #[test]
+    fn test_failed_turn_is_not_kept() {
+        let server = MockGroqServer::start();
+        server.enqueue("/chat/completions", MockResponse::unauthorized());
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let mut conversation = conversation(HistoryPolicy::Summarize { keep_turns: 1 }, 3);
+
+        let result = conversation.send_blocking(&client, "Hi");
+        assert!(matches!(result, Err(GroqError::Unauthorized(_))));
+        assert_eq!(conversation.history().len(), 6);
+        assert!(conversation.summary().is_none());
+    }
+}
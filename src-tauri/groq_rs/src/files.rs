@@ -0,0 +1,52 @@
+//! Files stored by the Groq API, such as the input and output of batches.
+
+use serde::{Deserialize, Serialize};
+
+/// The purpose of files holding the input of a batch.
+pub const BATCH_PURPOSE: &str = "batch";
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents a file stored by the Groq API.
+///
+/// - `id`: The id of the file, used to refer to it in other requests.
+/// - `object`: The object type, always `file`.
+/// - `bytes`: The size of the file in bytes.
+/// - `created_at`: The Unix timestamp of when the file was created.
+/// - `filename`: The name of the file.
+/// - `purpose`: What the file is for, such as `batch` or `batch_output`.
+pub struct FileObject {
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    #[serde(default)]
+    pub bytes: u64,
+    #[serde(default)]
+    pub created_at: u64,
+    #[serde(default)]
+    pub filename: String,
+    #[serde(default)]
+    pub purpose: String,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the response of the `/files` endpoint.
+///
+/// - `object`: The object type, always `list`.
+/// - `data`: The files of the account.
+pub struct FileList {
+    pub object: String,
+    pub data: Vec<FileObject>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents the response to a file deletion.
+///
+/// - `id`: The id of the deleted file.
+/// - `object`: The object type, always `file`.
+/// - `deleted`: Whether the file was deleted.
+pub struct DeletedFile {
+    pub id: String,
+    #[serde(default)]
+    pub object: String,
+    pub deleted: bool,
+}
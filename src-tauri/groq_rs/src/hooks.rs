@@ -0,0 +1,188 @@
+use crate::{ApiRequest, ApiResponse, GroqError};
+use std::fmt;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Represents how a request went, passed to hooks along with its outcome.
+///
+/// - `attempts`: The number of times the request was sent, including retries, or 0 if its response
+///   was served from the client's `ResponseCache`.
+/// - `elapsed`: The time from the first attempt until the response was read or the request failed.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RequestInfo {
+    pub attempts: u32,
+    pub elapsed: Duration,
+}
+
+/// Observes or changes the requests a client sends and the responses it receives.
+///
+/// Hooks are added with `GroqClientBuilder::hook` or the `with_hook` method of the clients, and
+/// run in the order they were added. Every method has a default implementation that does
+/// nothing, so a hook only implements what it needs.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::{ApiRequest, ApiResponse, GroqClient, Hook, RequestInfo};
+/// use std::sync::atomic::{AtomicU32, Ordering};
+/// use std::sync::Arc;
+///
+/// /// Counts the requests that needed a retry.
+/// #[derive(Default)]
+/// struct RetryCounter(AtomicU32);
+///
+/// impl Hook for RetryCounter {
+///     fn on_response(&self, _request: &ApiRequest, _response: &mut ApiResponse, info: &RequestInfo) {
+///         if info.attempts > 1 {
+///             self.0.fetch_add(1, Ordering::Relaxed);
+///         }
+///     }
+/// }
+///
+/// let client = GroqClient::new("my_api_key".to_string(), None)
+///     .with_hook(Arc::new(RetryCounter::default()));
+///```
+pub trait Hook: Send + Sync {
+    /// Called once before a request is sent. Changes to `request` are sent, including on retries.
+    fn on_request(&self, _request: &mut ApiRequest) {}
+
+    /// Called once with the final response to a request, whatever its status, before it is decoded.
+    /// Responses served from a `ResponseCache` are passed as well, as they were first received.
+    ///
+    /// For streamed chat completions, `body` is empty and changes to the response are ignored,
+    /// since the events are read as they arrive.
+    fn on_response(&self, _request: &ApiRequest, _response: &mut ApiResponse, _info: &RequestInfo) {
+    }
+
+    /// Called when a request could not be sent, or its response could not be read.
+    fn on_error(&self, _request: &ApiRequest, _error: &GroqError, _info: &RequestInfo) {}
+}
+
+impl fmt::Debug for dyn Hook {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Hook")
+    }
+}
+
+/// Tracks a single request from the first hook until its outcome, and records it in a tracing
+/// span when the `tracing` feature is enabled.
+pub(crate) struct Exchange<'a> {
+    hooks: &'a [Arc<dyn Hook>],
+    started: Instant,
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+impl<'a> Exchange<'a> {
+    /// Runs the `on_request` hooks and starts timing the request.
+    pub fn begin(hooks: &'a [Arc<dyn Hook>], request: &mut ApiRequest) -> Self {
+        for hook in hooks {
+            hook.on_request(request);
+        }
+        Self {
+            hooks,
+            started: Instant::now(),
+            #[cfg(feature = "tracing")]
+            span: tracing::info_span!(
+                "groq_request",
+                method = %request.method,
+                endpoint = %request.path,
+                model = request.model.as_deref().unwrap_or_default(),
+                status = tracing::field::Empty,
+                attempts = tracing::field::Empty,
+                latency_ms = tracing::field::Empty,
+                prompt_tokens = tracing::field::Empty,
+                completion_tokens = tracing::field::Empty,
+                total_tokens = tracing::field::Empty,
+                error = tracing::field::Empty,
+            ),
+        }
+    }
+
+    /// Runs `future` inside the span of the request.
+    #[cfg(feature = "tracing")]
+    pub fn instrument<F: std::future::Future>(
+        &self,
+        future: F,
+    ) -> tracing::instrument::Instrumented<F> {
+        tracing::Instrument::instrument(future, self.span.clone())
+    }
+
+    /// Runs `future` as is, since the `tracing` feature is disabled.
+    #[cfg(not(feature = "tracing"))]
+    pub fn instrument<F: std::future::Future>(&self, future: F) -> F {
+        future
+    }
+
+    /// Runs `f` inside the span of the request.
+    #[cfg(feature = "tracing")]
+    pub fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        self.span.in_scope(f)
+    }
+
+    /// Runs `f` as is, since the `tracing` feature is disabled.
+    #[cfg(not(feature = "tracing"))]
+    pub fn in_scope<R>(&self, f: impl FnOnce() -> R) -> R {
+        f()
+    }
+
+    /// Runs the `on_response` or `on_error` hooks with the outcome of the request, and records it.
+    pub fn finish(
+        self,
+        request: &ApiRequest,
+        attempts: u32,
+        result: Result<ApiResponse, GroqError>,
+    ) -> Result<ApiResponse, GroqError> {
+        let info = RequestInfo {
+            attempts,
+            elapsed: self.started.elapsed(),
+        };
+        match result {
+            Ok(mut response) => {
+                for hook in self.hooks {
+                    hook.on_response(request, &mut response, &info);
+                }
+                self.record(&info, Ok(&response));
+                Ok(response)
+            }
+            Err(error) => {
+                for hook in self.hooks {
+                    hook.on_error(request, &error, &info);
+                }
+                self.record(&info, Err(&error));
+                Err(error)
+            }
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn record(&self, info: &RequestInfo, result: Result<&ApiResponse, &GroqError>) {
+        let span = &self.span;
+        span.record("attempts", info.attempts);
+        span.record("latency_ms", info.elapsed.as_millis() as u64);
+        match result {
+            Ok(response) => {
+                span.record("status", response.status.as_u16());
+                let usage = serde_json::from_slice::<serde_json::Value>(&response.body)
+                    .ok()
+                    .map(|body| body["usage"].clone());
+                if let Some(usage) = usage.filter(|usage| usage.is_object()) {
+                    for field in ["prompt_tokens", "completion_tokens", "total_tokens"] {
+                        if let Some(tokens) = usage[field].as_u64() {
+                            span.record(field, tokens);
+                        }
+                    }
+                }
+            }
+            Err(error) => {
+                if let Some(status) = error.status() {
+                    span.record("status", status);
+                }
+                span.record("error", tracing::field::display(error));
+            }
+        }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    fn record(&self, _info: &RequestInfo, _result: Result<&ApiResponse, &GroqError>) {}
+}
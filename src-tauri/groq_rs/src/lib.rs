@@ -1,13 +1,63 @@
+mod audio;
+pub mod batches;
+mod builder;
+mod cache;
+mod cancel;
+mod chunking;
+mod codec;
+mod conversation;
+pub mod files;
+mod hooks;
+mod limiter;
 mod message;
+pub mod models;
+mod provider;
+mod retry;
+mod stream;
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+mod tokens;
+mod transcript;
+mod upload;
+mod vision;
+pub use audio::{AudioFormat, MAX_AUDIO_FILE_BYTES};
+pub use batches::{Batch, BatchOptions, BatchStatus, CreateBatchRequest};
+use batches::{BatchPoll, BatchResults};
+use builder::ClientConfig;
+pub use builder::{GroqClientBuilder, API_KEY_ENV, BASE_URL_ENV};
+use bytes::Bytes;
+pub use cache::{ResponseCache, DEFAULT_CACHE_SIZE, DEFAULT_CACHE_TTL};
+pub use cancel::{CancellationToken, RequestOptions};
+pub use chunking::ChunkingOptions;
+use codec::DEFAULT_ENDPOINT;
+pub use codec::{ApiRequest, ApiResponse};
+pub use conversation::{Conversation, HistoryPolicy, DEFAULT_CONTEXT_WINDOW, DEFAULT_MAX_TOKENS};
+pub use files::{DeletedFile, FileObject};
+use hooks::Exchange;
+pub use hooks::{Hook, RequestInfo};
+pub use limiter::{RateLimiter, RateLimits};
 pub use message::*;
+pub use models::{Capability, Model, ModelCapabilities, ModelRegistry};
+pub use provider::{
+    AuthScheme, ChatProvider, OpenAiCompatibleClient, SpeechToTextProvider, TextToSpeechProvider,
+    API_KEY_HEADER,
+};
 use reqwest::{
-    blocking::multipart::{Form, Part},
     blocking::{Client, Response},
-    multipart::{Form as AForm, Part as APart},
+    header::HeaderMap,
     Client as AClient, Response as AResponse,
 };
-use serde_json::{json, Value};
+pub use retry::{RateLimitInfo, RetryPolicy};
+use serde::de::DeserializeOwned;
+use std::collections::HashMap;
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::time::Duration;
+pub use stream::{ChatCompletionChunks, ChatCompletionStream};
+pub use tokens::{ContextUsage, ModelFamily, TokenEstimator, IMAGE_TOKENS};
+pub use transcript::{Transcript, TranscriptFormat};
+pub use upload::{FileUpload, UploadProgress};
+pub use vision::{ImageFormat, MAX_BASE64_IMAGE_BYTES, MAX_IMAGES_PER_REQUEST};
 
 /// An asynchronous client for interacting with the Groq API.
 ///
@@ -23,48 +73,368 @@ use std::sync::Arc;
 /// # Example
 ///
 ///```
-/// use groq_client::AsyncGroqClient;
+/// use groq_api_rust::AsyncGroqClient;
 ///
-/// let client = AsyncGroqClient::new("my_api_key".to_string(), None).await;
+/// let client = AsyncGroqClient::new("my_api_key".to_string(), None);
 ///```
+///
+/// Use `AsyncGroqClient::builder()` to configure timeouts, a proxy or default headers.
 pub struct AsyncGroqClient {
-    api_key: String,
+    auth: AuthScheme,
     client: Arc<AClient>,
     endpoint: String,
+    default_headers: HeaderMap,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    rate_limiter: Option<Arc<RateLimiter>>,
+    model_registry: Option<Arc<ModelRegistry>>,
+    strict_responses: bool,
+    hooks: Vec<Arc<dyn Hook>>,
+    response_cache: Option<Arc<ResponseCache>>,
+    model_aliases: HashMap<String, String>,
+    max_audio_size: usize,
 }
 
 impl AsyncGroqClient {
     /// Creates a new `AsyncGroqClient`
-    pub async fn new(api_key: String, endpoint: Option<String>) -> Self {
-        let ep = endpoint.unwrap_or_else(|| String::from("https://api.groq.com/openai/v1"));
+    pub fn new(api_key: String, endpoint: Option<String>) -> Self {
+        let ep = endpoint.unwrap_or_else(|| String::from(DEFAULT_ENDPOINT));
         Self {
-            api_key,
+            auth: AuthScheme::Bearer(api_key),
             client: Arc::new(AClient::new()),
             endpoint: ep,
+            default_headers: HeaderMap::new(),
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+            rate_limiter: None,
+            model_registry: None,
+            strict_responses: false,
+            hooks: Vec::new(),
+            response_cache: None,
+            model_aliases: HashMap::new(),
+            max_audio_size: MAX_AUDIO_FILE_BYTES,
+        }
+    }
+
+    /// Returns a `GroqClientBuilder` to configure a client, finished with `build_async`.
+    pub fn builder() -> GroqClientBuilder {
+        GroqClientBuilder::new()
+    }
+
+    pub(crate) fn from_config(
+        config: ClientConfig,
+        client: AClient,
+        rate_limiter: Option<Arc<RateLimiter>>,
+    ) -> Self {
+        Self {
+            auth: config.auth,
+            client: Arc::new(client),
+            endpoint: config.endpoint,
+            default_headers: config.default_headers,
+            timeout: config.timeout,
+            retry_policy: config.retry_policy,
+            rate_limiter,
+            model_registry: config.model_registry,
+            strict_responses: config.strict_responses,
+            hooks: config.hooks,
+            response_cache: config.response_cache,
+            model_aliases: config.model_aliases,
+            max_audio_size: config.max_audio_size,
+        }
+    }
+
+    /// Sets the policy used to retry failed requests.
+    ///
+    /// # Parameters
+    ///
+    /// - `retry_policy`: The `RetryPolicy` to use. `RetryPolicy::none()` disables retries.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the client-side rate limiter. Requests then wait for capacity before being sent.
+    ///
+    /// # Parameters
+    ///
+    /// - `rate_limiter`: The `RateLimiter` to use. It can be shared with other clients.
+    pub fn with_rate_limiter(mut self, rate_limiter: Arc<RateLimiter>) -> Self {
+        self.rate_limiter = Some(rate_limiter);
+        self
+    }
+
+    /// Sets the registry used to check requests against the capabilities of their model before sending them.
+    ///
+    /// # Parameters
+    ///
+    /// - `model_registry`: The `ModelRegistry` to use. It can be shared with other clients.
+    pub fn with_model_registry(mut self, model_registry: Arc<ModelRegistry>) -> Self {
+        self.model_registry = Some(model_registry);
+        self
+    }
+
+    /// Sets whether chat completion responses with unknown or missing fields are rejected,
+    /// for contract testing against the Groq API. Off by default.
+    ///
+    /// # Parameters
+    ///
+    /// - `strict_responses`: Whether to reject responses that do not match the known schema exactly.
+    pub fn with_strict_responses(mut self, strict_responses: bool) -> Self {
+        self.strict_responses = strict_responses;
+        self
+    }
+
+    /// Adds a hook that observes or changes every request and response, after any added before.
+    ///
+    /// # Parameters
+    ///
+    /// - `hook`: The `Hook` to add. It can be shared with other clients.
+    pub fn with_hook(mut self, hook: Arc<dyn Hook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Sets the on-disk cache serving the reproducible responses the client has already received.
+    ///
+    /// # Parameters
+    ///
+    /// - `response_cache`: The `ResponseCache` to use. It can be shared with other clients.
+    pub fn with_response_cache(mut self, response_cache: Arc<ResponseCache>) -> Self {
+        self.response_cache = Some(response_cache);
+        self
+    }
+
+    /// Sets how requests are authenticated, replacing the API key given to `new`.
+    ///
+    /// # Parameters
+    ///
+    /// - `auth`: The `AuthScheme` to use, such as `AuthScheme::None` for a local server.
+    pub fn with_auth(mut self, auth: AuthScheme) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Sends requests for the model `alias` to `model` instead, for servers naming their models
+    /// differently. The model registry still sees `alias`, while the rate limiter counts requests
+    /// against `model`.
+    ///
+    /// # Parameters
+    ///
+    /// - `alias`: The model requests are made for, such as `models::WHISPER_LARGE_V3`.
+    /// - `model`: The model the server knows, such as `ggml-large-v3`.
+    pub fn with_model_alias(mut self, alias: &str, model: &str) -> Self {
+        self.model_aliases
+            .insert(alias.to_string(), model.to_string());
+        self
+    }
+
+    /// Sets the maximum size of the audio files sent for transcription, larger files being
+    /// rejected before upload. Defaults to `MAX_AUDIO_FILE_BYTES`.
+    ///
+    /// # Parameters
+    ///
+    /// - `max_audio_size`: The maximum size in bytes, such as 100 MiB on the Groq developer tier.
+    pub fn with_max_audio_size(mut self, max_audio_size: usize) -> Self {
+        self.max_audio_size = max_audio_size;
+        self
+    }
+
+    /// Replaces `model` with the model it is an alias for, if any.
+    fn resolve_model(&self, model: &mut String) {
+        if let Some(target) = self.model_aliases.get(model.as_str()) {
+            model.clone_from(target);
+        }
+    }
+
+    /// Returns the response cache and the entry of `request` in it, unless the call bypasses it.
+    fn cache_entry(
+        &self,
+        request: &ApiRequest,
+        options: &RequestOptions,
+    ) -> (Option<&ResponseCache>, Option<PathBuf>) {
+        match &self.response_cache {
+            Some(cache) if !options.bypass_cache => (
+                Some(cache),
+                cache.entry(request, &self.endpoint, &self.auth),
+            ),
+            _ => (None, None),
+        }
+    }
+
+    /// Runs a check against the model registry, if any.
+    fn check(
+        &self,
+        check: impl FnOnce(&ModelRegistry) -> Result<(), GroqError>,
+    ) -> Result<(), GroqError> {
+        match &self.model_registry {
+            Some(registry) => check(registry),
+            None => Ok(()),
+        }
+    }
+
+    /// Waits until the rate limiter, if any, has capacity for another attempt of `request`.
+    async fn throttle(&self, request: &ApiRequest) {
+        if let (Some(rate_limiter), Some((model, cost))) = (&self.rate_limiter, &request.rate_limit)
+        {
+            rate_limiter.acquire(model, *cost).await;
         }
     }
 
-    /// Sends a request to the Groq API with the provided JSON body and returns the parsed response.
+    /// Sends an encoded request to the Groq API without reading the response body,
+    /// retrying it according to the client's `RetryPolicy`.
     ///
     /// # Parameters
     ///
-    /// - `body`: The JSON body to send in the request.
-    /// - `link`: The URL link to send the request to.
+    /// - `request`: The `ApiRequest` to send.
+    /// - `attempts`: Incremented every time the request is sent.
     ///
     /// # Returns
     ///
-    /// The parsed JSON response from the Groq API.
-    async fn send_request(&self, body: Value, link: &str) -> Result<Value, GroqError> {
-        let res = self
-            .client
-            .post(link)
-            .header("Content-Type", "application/json")
-            .header("Authorization", &format!("Bearer {}", self.api_key))
-            .json(&body)
-            .send()
-            .await?;
+    /// The HTTP response from the Groq API, whatever its status.
+    async fn send(&self, request: &ApiRequest, attempts: &mut u32) -> Result<AResponse, GroqError> {
+        loop {
+            self.throttle(request).await;
+            *attempts += 1;
+            let attempt = *attempts;
+            let mut builder = self
+                .client
+                .request(
+                    request.method.clone(),
+                    format!("{}{}", self.endpoint, request.path),
+                )
+                .headers(self.default_headers.clone())
+                .headers(request.headers.clone());
+            if let Some((name, value)) = self.auth.header() {
+                builder = builder.header(name, value);
+            }
+            if let Some(timeout) = self.timeout {
+                builder = builder.timeout(timeout);
+            }
+            let body = match &request.upload {
+                Some(upload) => upload.body(&request.body).await?,
+                None => request.body.clone().into(),
+            };
+            let result = builder.body(body).send().await.map_err(GroqError::from);
+
+            let delay = match &result {
+                Ok(response) => {
+                    self.retry_policy
+                        .retry_response(attempt, response.status(), response.headers())
+                }
+                Err(e) => self.retry_policy.retry_error(attempt, e),
+            };
+            // A file streamed from a reader cannot be sent again.
+            let replayable = request
+                .upload
+                .as_ref()
+                .map_or(true, FileUpload::is_replayable);
+            match delay {
+                Some(delay) if replayable => tokio::time::sleep(delay).await,
+                _ => return result,
+            }
+        }
+    }
+
+    /// Sends an encoded request to the Groq API and reads the whole response body.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ApiRequest` to send.
+    ///
+    /// # Returns
+    ///
+    /// The buffered `ApiResponse`, to be decoded by the `codec` functions, after the client's hooks
+    /// have seen it.
+    async fn execute(&self, request: ApiRequest) -> Result<ApiResponse, GroqError> {
+        self.execute_with(request, &RequestOptions::default()).await
+    }
+
+    /// Sends an encoded request to the Groq API and reads the whole response body, unless the
+    /// call is cancelled or its deadline passes first.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ApiRequest` to send.
+    /// - `options`: The cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// The buffered `ApiResponse`, to be decoded by the `codec` functions, after the client's hooks
+    /// have seen it.
+    async fn execute_with(
+        &self,
+        mut request: ApiRequest,
+        options: &RequestOptions,
+    ) -> Result<ApiResponse, GroqError> {
+        let (cache, entry) = self.cache_entry(&request, options);
+        let exchange = Exchange::begin(&self.hooks, &mut request);
+        if let Some(response) = entry.as_deref().and_then(|entry| cache?.get(entry)) {
+            return exchange.finish(&request, 0, Ok(response));
+        }
+        let mut attempts = 0;
+        let result = exchange
+            .instrument(options.run(async {
+                let response = self.send(&request, &mut attempts).await?;
+                Ok(ApiResponse {
+                    status: response.status(),
+                    headers: response.headers().clone(),
+                    body: response.bytes().await?,
+                })
+            }))
+            .await;
+        // Responses are stored as received, so hooks see cached responses as they saw the first.
+        if let (Some(cache), Some(entry), Ok(response)) = (cache, entry, &result) {
+            cache.put(&entry, response);
+        }
+        exchange.finish(&request, attempts, result)
+    }
 
-        self.parse_response(res).await
+    /// Sends an encoded streaming request to the Groq API and returns the response once its
+    /// status is known, leaving the body to be read as it arrives.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ApiRequest` to send.
+    /// - `options`: The cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// The HTTP response from the Groq API if its status is a success, or the decoded error.
+    async fn open(
+        &self,
+        mut request: ApiRequest,
+        options: &RequestOptions,
+    ) -> Result<AResponse, GroqError> {
+        let exchange = Exchange::begin(&self.hooks, &mut request);
+        let mut attempts = 0;
+        let mut stream = None;
+        let result = exchange
+            .instrument(options.run(async {
+                let response = self.send(&request, &mut attempts).await?;
+                let status = response.status();
+                let headers = response.headers().clone();
+                let body = if status.is_success() {
+                    stream = Some(response);
+                    Bytes::new()
+                } else {
+                    response.bytes().await?
+                };
+                Ok(ApiResponse {
+                    status,
+                    headers,
+                    body,
+                })
+            }))
+            .await;
+        let response = exchange.finish(&request, attempts, result)?;
+        match stream {
+            Some(stream) => Ok(stream),
+            None => Err(codec::decode_error(
+                response.status,
+                &response.headers,
+                &response.body,
+            )),
+        }
     }
 
     /// Sends a speech-to-text request to the Groq API and returns the parsed response.
@@ -80,459 +450,1502 @@ impl AsyncGroqClient {
         &self,
         request: SpeechToTextRequest,
     ) -> Result<SpeechToTextResponse, GroqError> {
-        let file = request.file;
-        let temperature = request.temperature;
-        let language = request.language;
-        let english_text = request.english_text;
-        let model = request.model;
+        self.speech_to_text_with(request, &RequestOptions::default())
+            .await
+    }
 
-        let mut form = AForm::new().part("file", APart::bytes(file).file_name("audio.wav"));
-        if let Some(temp) = temperature {
-            form = form.text("temperature", temp.to_string());
-        }
-        if let Some(lang) = language {
-            form = form.text("language", lang);
-        }
+    /// Sends a speech-to-text request that can be cancelled or given a deadline.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `SpeechToTextRequest` containing the audio file, temperature, language, and other options.
+    /// - `options`: The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `SpeechToTextResponse` from the Groq API, or `GroqError::Cancelled` or
+    /// `GroqError::DeadlineExceeded` if the call was interrupted.
+    pub async fn speech_to_text_with(
+        &self,
+        request: SpeechToTextRequest,
+        options: &RequestOptions,
+    ) -> Result<SpeechToTextResponse, GroqError> {
+        let format = request.response_format.unwrap_or_default();
+        let response = self.send_speech_to_text(request, options).await?;
+        codec::decode_speech_to_text(response, format)
+    }
 
-        let link_addition = if english_text {
-            "/audio/translations"
-        } else {
-            "/audio/transcriptions"
-        };
-        if let Some(mdl) = model {
-            form = form.text("model", mdl);
+    /// Sends a speech-to-text request and returns the transcription in the requested format.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `SpeechToTextRequest` to send, with the `TranscriptFormat` of the response.
+    ///
+    /// # Returns
+    ///
+    /// The `Transcript`, parsed for JSON formats and as sent for text and subtitles.
+    pub async fn transcript(&self, request: SpeechToTextRequest) -> Result<Transcript, GroqError> {
+        self.transcript_with(request, &RequestOptions::default())
+            .await
+    }
+
+    /// Sends a speech-to-text request that can be cancelled or given a deadline, and returns the
+    /// transcription in the requested format.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `SpeechToTextRequest` to send, with the `TranscriptFormat` of the response.
+    /// - `options`: The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// The `Transcript`, or `GroqError::Cancelled` or `GroqError::DeadlineExceeded` if the call was interrupted.
+    pub async fn transcript_with(
+        &self,
+        request: SpeechToTextRequest,
+        options: &RequestOptions,
+    ) -> Result<Transcript, GroqError> {
+        let format = request.response_format.unwrap_or_default();
+        let response = self.send_speech_to_text(request, options).await?;
+        codec::decode_transcript(response, format)
+    }
+
+    /// Checks and sends a speech-to-text request, returning the undecoded response.
+    async fn send_speech_to_text(
+        &self,
+        mut request: SpeechToTextRequest,
+        options: &RequestOptions,
+    ) -> Result<ApiResponse, GroqError> {
+        audio::check_upload(&request, self.max_audio_size)?;
+        self.check(|registry| registry.check_speech_to_text(&request))?;
+        if let Some(model) = request.model.as_mut() {
+            self.resolve_model(model);
         }
+        self.execute_with(ApiRequest::speech_to_text(request), options)
+            .await
+    }
+
+    /// Sends a chat completion request to the Groq API and returns the parsed response.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ChatCompletionRequest` containing the model, messages, temperature, max tokens, top-p, and other options.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `ChatCompletionResponse` from the Groq API.
+    pub async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, GroqError> {
+        self.chat_completion_with(request, &RequestOptions::default())
+            .await
+    }
 
-        let link = format!("{}{}", self.endpoint, link_addition);
+    /// Sends a chat completion request that can be cancelled or given a deadline.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ChatCompletionRequest` containing the model, messages, temperature, max tokens, top-p, and other options.
+    /// - `options`: The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `ChatCompletionResponse` from the Groq API, or `GroqError::Cancelled` or
+    /// `GroqError::DeadlineExceeded` if the call was interrupted.
+    pub async fn chat_completion_with(
+        &self,
+        mut request: ChatCompletionRequest,
+        options: &RequestOptions,
+    ) -> Result<ChatCompletionResponse, GroqError> {
+        self.check(|registry| registry.check_chat_completion(&request))?;
+        self.resolve_model(&mut request.model);
         let response = self
-            .client
-            .post(&link)
-            .header("Authorization", &format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()
+            .execute_with(ApiRequest::chat_completion(&request, false), options)
             .await?;
+        codec::decode_chat_completion(response, self.strict_responses)
+    }
 
-        let speech_to_text_response: SpeechToTextResponse = response.json().await?;
-        Ok(speech_to_text_response)
+    /// Sends a chat completion request in JSON mode and parses the generated content into `T`.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ChatCompletionRequest` to send. If no `response_format` is set, `ResponseFormat::JsonObject` is used.
+    ///
+    /// # Returns
+    ///
+    /// The content of the first choice deserialized as `T`, or `GroqError::StructuredOutputError`
+    /// if the model output is not valid JSON or does not match `T` or the requested schema.
+    pub async fn chat_completion_json<T: DeserializeOwned>(
+        &self,
+        mut request: ChatCompletionRequest,
+    ) -> Result<T, GroqError> {
+        request
+            .response_format
+            .get_or_insert(ResponseFormat::JsonObject);
+        let response = self.chat_completion(request).await?;
+        codec::structured_output(&response)
     }
 
-    /// Sends a chat completion request to the Groq API and returns the parsed response.
+    /// Sends a streamed chat completion request to the Groq API.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ChatCompletionRequest` to send. `stream` is always set to `true`.
+    ///
+    /// # Returns
+    ///
+    /// A `ChatCompletionStream` yielding a `ChatCompletionChunk` for every server-sent event.
+    /// The final chunk carries the `finish_reason` and the `x_groq.usage` of the request.
+    pub async fn chat_completion_stream(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionStream, GroqError> {
+        self.chat_completion_stream_with(request, &RequestOptions::default())
+            .await
+    }
+
+    /// Sends a streamed chat completion request that can be cancelled or given a deadline.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ChatCompletionRequest` to send. `stream` is always set to `true`.
+    /// - `options`: The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// A `ChatCompletionStream` yielding a `ChatCompletionChunk` for every server-sent event. If the
+    /// call is interrupted while the stream is read, it yields `GroqError::Cancelled` or
+    /// `GroqError::DeadlineExceeded` and ends.
+    pub async fn chat_completion_stream_with(
+        &self,
+        mut request: ChatCompletionRequest,
+        options: &RequestOptions,
+    ) -> Result<ChatCompletionStream, GroqError> {
+        self.check(|registry| registry.check_chat_completion(&request))?;
+        self.resolve_model(&mut request.model);
+        let response = self
+            .open(ApiRequest::chat_completion(&request, true), options)
+            .await?;
+        Ok(ChatCompletionStream::new(response).interrupt_on(options))
+    }
+
+    /// Sends a text-to-speech request to the Groq API and returns the generated audio.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `TextToSpeechRequest` containing the model, input text, voice and speed.
+    ///
+    /// # Returns
+    ///
+    /// The `TextToSpeechResponse` containing the raw audio data.
+    pub async fn text_to_speech(
+        &self,
+        request: TextToSpeechRequest,
+    ) -> Result<TextToSpeechResponse, GroqError> {
+        self.text_to_speech_with(request, &RequestOptions::default())
+            .await
+    }
+
+    /// Sends a text-to-speech request that can be cancelled or given a deadline.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `TextToSpeechRequest` containing the model, input text, voice and speed.
+    /// - `options`: The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// The `TextToSpeechResponse` containing the raw audio data, or `GroqError::Cancelled` or
+    /// `GroqError::DeadlineExceeded` if the call was interrupted.
+    pub async fn text_to_speech_with(
+        &self,
+        mut request: TextToSpeechRequest,
+        options: &RequestOptions,
+    ) -> Result<TextToSpeechResponse, GroqError> {
+        self.check(|registry| registry.check_text_to_speech(&request))?;
+        self.resolve_model(&mut request.model);
+        let response = self
+            .execute_with(ApiRequest::text_to_speech(&request), options)
+            .await?;
+        codec::decode_text_to_speech(response)
+    }
+
+    /// Lists the models available to the account.
+    ///
+    /// # Returns
+    ///
+    /// The `Model` records returned by the Groq API, including their context window and whether they are active.
+    pub async fn list_models(&self) -> Result<Vec<Model>, GroqError> {
+        let response = self.execute(ApiRequest::list_models()).await?;
+        codec::decode_models(response)
+    }
+
+    /// Retrieves a single model.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The id of the model, such as `llama-3.3-70b-versatile`.
+    ///
+    /// # Returns
+    ///
+    /// The `Model` record returned by the Groq API.
+    pub async fn retrieve_model(&self, id: &str) -> Result<Model, GroqError> {
+        let response = self.execute(ApiRequest::retrieve_model(id)).await?;
+        codec::decode_model(response)
+    }
+
+    /// Uploads a file, such as the JSONL input of a batch.
+    ///
+    /// # Parameters
+    ///
+    /// - `bytes`: The content of the file.
+    /// - `filename`: The name of the file, such as `batch.jsonl`.
+    /// - `purpose`: What the file is for, such as `files::BATCH_PURPOSE`.
+    ///
+    /// # Returns
+    ///
+    /// The `FileObject` of the uploaded file.
+    pub async fn upload_file(
+        &self,
+        bytes: Vec<u8>,
+        filename: &str,
+        purpose: &str,
+    ) -> Result<FileObject, GroqError> {
+        let response = self
+            .execute(ApiRequest::upload_file(bytes, filename, purpose))
+            .await?;
+        codec::decode_file(response)
+    }
+
+    /// Lists the files of the account.
+    pub async fn list_files(&self) -> Result<Vec<FileObject>, GroqError> {
+        let response = self.execute(ApiRequest::list_files()).await?;
+        codec::decode_files(response)
+    }
+
+    /// Retrieves the record of a single file.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The id of the file.
+    pub async fn retrieve_file(&self, id: &str) -> Result<FileObject, GroqError> {
+        let response = self.execute(ApiRequest::retrieve_file(id)).await?;
+        codec::decode_file(response)
+    }
+
+    /// Deletes a file.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The id of the file.
+    pub async fn delete_file(&self, id: &str) -> Result<DeletedFile, GroqError> {
+        let response = self.execute(ApiRequest::delete_file(id)).await?;
+        codec::decode_deleted_file(response)
+    }
+
+    /// Downloads the content of a file, such as the output of a batch.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The id of the file.
+    pub async fn file_content(&self, id: &str) -> Result<Vec<u8>, GroqError> {
+        let response = self.execute(ApiRequest::file_content(id)).await?;
+        codec::decode_file_content(response)
+    }
+
+    /// Creates a batch from an uploaded input file.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `CreateBatchRequest` naming the input file and endpoint.
+    pub async fn create_batch(&self, request: CreateBatchRequest) -> Result<Batch, GroqError> {
+        let response = self.execute(ApiRequest::create_batch(&request)).await?;
+        codec::decode_batch(response)
+    }
+
+    /// Retrieves a single batch, to check its status.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The id of the batch.
+    pub async fn retrieve_batch(&self, id: &str) -> Result<Batch, GroqError> {
+        let response = self.execute(ApiRequest::retrieve_batch(id)).await?;
+        codec::decode_batch(response)
+    }
+
+    /// Lists the batches of the account.
+    pub async fn list_batches(&self) -> Result<Vec<Batch>, GroqError> {
+        let response = self.execute(ApiRequest::list_batches()).await?;
+        codec::decode_batches(response)
+    }
+
+    /// Cancels a batch. Requests processed so far stay available in its output file.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The id of the batch.
+    pub async fn cancel_batch(&self, id: &str) -> Result<Batch, GroqError> {
+        let response = self.execute(ApiRequest::cancel_batch(id)).await?;
+        codec::decode_batch(response)
+    }
+
+    /// Runs chat completion requests as a batch, waiting until the batch ends.
+    ///
+    /// The requests are uploaded as a JSONL file, then the batch is polled every
+    /// `options.poll_interval` until it completes, expires or is cancelled, unless
+    /// `options.max_wait` passes or `options.request_options` interrupts the call first.
+    ///
+    /// # Parameters
+    ///
+    /// - `requests`: The requests to run.
+    /// - `options`: The `BatchOptions` of the batch.
+    ///
+    /// # Returns
+    ///
+    /// The result of every request, in the order of `requests`. Requests the batch did not
+    /// process, for example because it expired, get an error.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if a request to the Files or Batch API fails, if the batch failed
+    /// as a whole, for example because a request was invalid, or if it was not waited for until
+    /// its end.
+    pub async fn chat_completion_batch(
+        &self,
+        requests: &[ChatCompletionRequest],
+        options: BatchOptions,
+    ) -> Result<Vec<Result<ChatCompletionResponse, GroqError>>, GroqError> {
+        let run = async {
+            let input = batches::encode_chat_completions(requests);
+            let file = self
+                .upload_file(input, "batch.jsonl", files::BATCH_PURPOSE)
+                .await?;
+            let mut batch = self.create_batch(options.create_request(&file.id)).await?;
+            let mut poll = BatchPoll::new(&options);
+            while !poll.is_done(&batch)? {
+                tokio::time::sleep(options.poll_interval).await;
+                batch = self.retrieve_batch(&batch.id).await?;
+            }
+            if batch.status == BatchStatus::Failed {
+                return Err(batches::batch_failed(&batch));
+            }
+            let mut results = BatchResults::new(requests.len());
+            for file_id in [&batch.output_file_id, &batch.error_file_id]
+                .into_iter()
+                .flatten()
+            {
+                results.read(&self.file_content(file_id).await?);
+            }
+            Ok(results.finish(&batch))
+        };
+        options.request_options.run(run).await
+    }
+}
+
+/// An client for interacting with the Groq API.
+///
+/// # Parameters
+///
+/// - `api_key`: The API key for authenticating with the Groq API.
+/// - `endpoint`: The URL of the Groq API endpoint. If not provided, it defaults to <https://api.groq.com/openai/v1>.
+///
+/// # Returns
+///
+/// An instance of `GroqClient` configured with the provided API key and endpoint.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::GroqClient;
+///
+/// let client = GroqClient::new("my_api_key".to_string(), None);
+///```
+///
+/// Use `GroqClient::builder()` to configure timeouts, a proxy or default headers.
+pub struct GroqClient {
+    auth: AuthScheme,
+    client: Client,
+    endpoint: String,
+    default_headers: HeaderMap,
+    timeout: Option<Duration>,
+    retry_policy: RetryPolicy,
+    model_registry: Option<Arc<ModelRegistry>>,
+    strict_responses: bool,
+    hooks: Vec<Arc<dyn Hook>>,
+    response_cache: Option<Arc<ResponseCache>>,
+    model_aliases: HashMap<String, String>,
+    max_audio_size: usize,
+}
+
+impl GroqClient {
+    /// Constructs a new `GroqClient` instance with the provided API key and optional endpoint.
+    ///
+    /// # Parameters
+    ///
+    /// - `api_key`: The API key for authenticating with the Groq API.
+    /// - `endpoint`: The URL of the Groq API endpoint. If not provided, it defaults to <https://api.groq.com/openai/v1>.
+    ///
+    /// # Returns
+    ///
+    /// A new `GroqClient` instance configured with the provided API key and endpoint.
+    pub fn new(api_key: String, endpoint: Option<String>) -> Self {
+        let ep = endpoint.unwrap_or_else(|| String::from(DEFAULT_ENDPOINT));
+        Self {
+            auth: AuthScheme::Bearer(api_key),
+            client: Client::new(),
+            endpoint: ep,
+            default_headers: HeaderMap::new(),
+            timeout: None,
+            retry_policy: RetryPolicy::default(),
+            model_registry: None,
+            strict_responses: false,
+            hooks: Vec::new(),
+            response_cache: None,
+            model_aliases: HashMap::new(),
+            max_audio_size: MAX_AUDIO_FILE_BYTES,
+        }
+    }
+
+    /// Returns a `GroqClientBuilder` to configure a client, finished with `build`.
+    pub fn builder() -> GroqClientBuilder {
+        GroqClientBuilder::new()
+    }
+
+    pub(crate) fn from_config(config: ClientConfig, client: Client) -> Self {
+        Self {
+            auth: config.auth,
+            client,
+            endpoint: config.endpoint,
+            default_headers: config.default_headers,
+            timeout: config.timeout,
+            retry_policy: config.retry_policy,
+            model_registry: config.model_registry,
+            strict_responses: config.strict_responses,
+            hooks: config.hooks,
+            response_cache: config.response_cache,
+            model_aliases: config.model_aliases,
+            max_audio_size: config.max_audio_size,
+        }
+    }
+
+    /// Sets the policy used to retry failed requests.
+    ///
+    /// # Parameters
+    ///
+    /// - `retry_policy`: The `RetryPolicy` to use. `RetryPolicy::none()` disables retries.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.retry_policy = retry_policy;
+        self
+    }
+
+    /// Sets the registry used to check requests against the capabilities of their model before sending them.
+    ///
+    /// # Parameters
+    ///
+    /// - `model_registry`: The `ModelRegistry` to use. It can be shared with other clients.
+    pub fn with_model_registry(mut self, model_registry: Arc<ModelRegistry>) -> Self {
+        self.model_registry = Some(model_registry);
+        self
+    }
+
+    /// Sets whether chat completion responses with unknown or missing fields are rejected,
+    /// for contract testing against the Groq API. Off by default.
+    ///
+    /// # Parameters
+    ///
+    /// - `strict_responses`: Whether to reject responses that do not match the known schema exactly.
+    pub fn with_strict_responses(mut self, strict_responses: bool) -> Self {
+        self.strict_responses = strict_responses;
+        self
+    }
+
+    /// Adds a hook that observes or changes every request and response, after any added before.
+    ///
+    /// # Parameters
+    ///
+    /// - `hook`: The `Hook` to add. It can be shared with other clients.
+    pub fn with_hook(mut self, hook: Arc<dyn Hook>) -> Self {
+        self.hooks.push(hook);
+        self
+    }
+
+    /// Sets the on-disk cache serving the reproducible responses the client has already received.
+    ///
+    /// # Parameters
+    ///
+    /// - `response_cache`: The `ResponseCache` to use. It can be shared with other clients.
+    pub fn with_response_cache(mut self, response_cache: Arc<ResponseCache>) -> Self {
+        self.response_cache = Some(response_cache);
+        self
+    }
+
+    /// Sets how requests are authenticated, replacing the API key given to `new`.
+    ///
+    /// # Parameters
+    ///
+    /// - `auth`: The `AuthScheme` to use, such as `AuthScheme::None` for a local server.
+    pub fn with_auth(mut self, auth: AuthScheme) -> Self {
+        self.auth = auth;
+        self
+    }
+
+    /// Sends requests for the model `alias` to `model` instead, for servers naming their models
+    /// differently. The model registry still sees `alias`.
+    ///
+    /// # Parameters
+    ///
+    /// - `alias`: The model requests are made for, such as `models::WHISPER_LARGE_V3`.
+    /// - `model`: The model the server knows, such as `ggml-large-v3`.
+    pub fn with_model_alias(mut self, alias: &str, model: &str) -> Self {
+        self.model_aliases
+            .insert(alias.to_string(), model.to_string());
+        self
+    }
+
+    /// Sets the maximum size of the audio files sent for transcription, larger files being
+    /// rejected before upload. Defaults to `MAX_AUDIO_FILE_BYTES`.
+    ///
+    /// # Parameters
+    ///
+    /// - `max_audio_size`: The maximum size in bytes, such as 100 MiB on the Groq developer tier.
+    pub fn with_max_audio_size(mut self, max_audio_size: usize) -> Self {
+        self.max_audio_size = max_audio_size;
+        self
+    }
+
+    /// Replaces `model` with the model it is an alias for, if any.
+    fn resolve_model(&self, model: &mut String) {
+        if let Some(target) = self.model_aliases.get(model.as_str()) {
+            model.clone_from(target);
+        }
+    }
+
+    /// Returns the response cache and the entry of `request` in it, unless the call bypasses it.
+    fn cache_entry(
+        &self,
+        request: &ApiRequest,
+        options: &RequestOptions,
+    ) -> (Option<&ResponseCache>, Option<PathBuf>) {
+        match &self.response_cache {
+            Some(cache) if !options.bypass_cache => (
+                Some(cache),
+                cache.entry(request, &self.endpoint, &self.auth),
+            ),
+            _ => (None, None),
+        }
+    }
+
+    /// Runs a check against the model registry, if any.
+    fn check(
+        &self,
+        check: impl FnOnce(&ModelRegistry) -> Result<(), GroqError>,
+    ) -> Result<(), GroqError> {
+        match &self.model_registry {
+            Some(registry) => check(registry),
+            None => Ok(()),
+        }
+    }
+
+    /// Sends an encoded request to the Groq API without reading the response body,
+    /// retrying it according to the client's `RetryPolicy`.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ApiRequest` to send.
+    /// - `attempts`: Incremented every time the request is sent.
+    /// - `options`: The cancellation token and deadline of the call, checked before every attempt.
+    ///
+    /// # Returns
+    ///
+    /// The HTTP response from the Groq API, whatever its status.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if the request could not be sent, or was interrupted before an attempt.
+    fn send(
+        &self,
+        request: &ApiRequest,
+        attempts: &mut u32,
+        options: &RequestOptions,
+    ) -> Result<Response, GroqError> {
+        loop {
+            options.check()?;
+            *attempts += 1;
+            let attempt = *attempts;
+            let mut builder = self
+                .client
+                .request(
+                    request.method.clone(),
+                    format!("{}{}", self.endpoint, request.path),
+                )
+                .headers(self.default_headers.clone())
+                .headers(request.headers.clone());
+            if let Some((name, value)) = self.auth.header() {
+                builder = builder.header(name, value);
+            }
+            if let Some(timeout) = options.limit(self.timeout) {
+                builder = builder.timeout(timeout);
+            }
+            let body = match &request.upload {
+                Some(upload) => upload.blocking_body(&request.body)?,
+                None => request.body.clone().into(),
+            };
+            let result = builder.body(body).send().map_err(GroqError::from);
+
+            let delay = match &result {
+                Ok(response) => {
+                    self.retry_policy
+                        .retry_response(attempt, response.status(), response.headers())
+                }
+                Err(e) => self.retry_policy.retry_error(attempt, e),
+            };
+            match delay {
+                Some(delay) => options.sleep(delay)?,
+                None => return result,
+            }
+        }
+    }
+
+    /// Sends an encoded request to the Groq API and reads the whole response body.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ApiRequest` to send.
+    ///
+    /// # Returns
+    ///
+    /// The buffered `ApiResponse`, to be decoded by the `codec` functions, after the client's hooks
+    /// have seen it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if the request could not be sent or the body could not be read.
+    fn execute(&self, request: ApiRequest) -> Result<ApiResponse, GroqError> {
+        self.execute_with(request, &RequestOptions::default())
+    }
+
+    /// Sends an encoded request to the Groq API and reads the whole response body, within the
+    /// deadline of the call.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ApiRequest` to send.
+    /// - `options`: The cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// The buffered `ApiResponse`, to be decoded by the `codec` functions, after the client's hooks
+    /// have seen it.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if the request could not be sent, the body could not be read, or the
+    /// call was interrupted.
+    fn execute_with(
+        &self,
+        mut request: ApiRequest,
+        options: &RequestOptions,
+    ) -> Result<ApiResponse, GroqError> {
+        let (cache, entry) = self.cache_entry(&request, options);
+        let exchange = Exchange::begin(&self.hooks, &mut request);
+        if let Some(response) = entry.as_deref().and_then(|entry| cache?.get(entry)) {
+            return exchange.finish(&request, 0, Ok(response));
+        }
+        let mut attempts = 0;
+        let result = exchange
+            .in_scope(|| {
+                let response = self.send(&request, &mut attempts, options)?;
+                Ok(ApiResponse {
+                    status: response.status(),
+                    headers: response.headers().clone(),
+                    body: response.bytes()?,
+                })
+            })
+            .map_err(|error| options.check().err().unwrap_or(error));
+        // Responses are stored as received, so hooks see cached responses as they saw the first.
+        if let (Some(cache), Some(entry), Ok(response)) = (cache, entry, &result) {
+            cache.put(&entry, response);
+        }
+        exchange.finish(&request, attempts, result)
+    }
+
+    /// Sends an encoded streaming request to the Groq API and returns the response once its
+    /// status is known, leaving the body to be read as it arrives.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `ApiRequest` to send.
+    /// - `options`: The cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// The HTTP response from the Groq API if its status is a success.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if the request could not be sent, the API responds with an error
+    /// status, or the call was interrupted.
+    fn open(
+        &self,
+        mut request: ApiRequest,
+        options: &RequestOptions,
+    ) -> Result<Response, GroqError> {
+        let exchange = Exchange::begin(&self.hooks, &mut request);
+        let mut attempts = 0;
+        let mut stream = None;
+        let result = exchange.in_scope(|| {
+            let response = self.send(&request, &mut attempts, options)?;
+            let status = response.status();
+            let headers = response.headers().clone();
+            let body = if status.is_success() {
+                stream = Some(response);
+                Bytes::new()
+            } else {
+                response.bytes()?
+            };
+            Ok(ApiResponse {
+                status,
+                headers,
+                body,
+            })
+        });
+        let result = result.map_err(|error| options.check().err().unwrap_or(error));
+        let response = exchange.finish(&request, attempts, result)?;
+        match stream {
+            Some(stream) => Ok(stream),
+            None => Err(codec::decode_error(
+                response.status,
+                &response.headers,
+                &response.body,
+            )),
+        }
+    }
+
+    /// Sends a speech-to-text request to the Groq API and returns the parsed response.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: A `SpeechToTextRequest` containing the necessary parameters for the speech-to-text request.
+    ///
+    /// # Returns
+    ///
+    /// The parsed `SpeechToTextResponse` from the Groq API.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn speech_to_text(
+        &self,
+        request: SpeechToTextRequest,
+    ) -> Result<SpeechToTextResponse, GroqError> {
+        self.speech_to_text_with(request, &RequestOptions::default())
+    }
+
+    /// Sends a speech-to-text request that can be cancelled or given a deadline.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: A `SpeechToTextRequest` containing the necessary parameters for the speech-to-text request.
+    /// - `options`: The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::Cancelled` or `GroqError::DeadlineExceeded` if the call was interrupted,
+    /// and any other `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn speech_to_text_with(
+        &self,
+        request: SpeechToTextRequest,
+        options: &RequestOptions,
+    ) -> Result<SpeechToTextResponse, GroqError> {
+        let format = request.response_format.unwrap_or_default();
+        let response = self.send_speech_to_text(request, options)?;
+        codec::decode_speech_to_text(response, format)
+    }
+
+    /// Sends a speech-to-text request and returns the transcription in the requested format.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `SpeechToTextRequest` to send, with the `TranscriptFormat` of the response.
+    ///
+    /// # Returns
+    ///
+    /// The `Transcript`, parsed for JSON formats and as sent for text and subtitles.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn transcript(&self, request: SpeechToTextRequest) -> Result<Transcript, GroqError> {
+        self.transcript_with(request, &RequestOptions::default())
+    }
+
+    /// Sends a speech-to-text request that can be cancelled or given a deadline, and returns the
+    /// transcription in the requested format.
+    ///
+    /// # Parameters
+    ///
+    /// - `request`: The `SpeechToTextRequest` to send, with the `TranscriptFormat` of the response.
+    /// - `options`: The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::Cancelled` or `GroqError::DeadlineExceeded` if the call was interrupted,
+    /// and any other `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn transcript_with(
+        &self,
+        request: SpeechToTextRequest,
+        options: &RequestOptions,
+    ) -> Result<Transcript, GroqError> {
+        let format = request.response_format.unwrap_or_default();
+        let response = self.send_speech_to_text(request, options)?;
+        codec::decode_transcript(response, format)
+    }
+
+    /// Checks and sends a speech-to-text request, returning the undecoded response.
+    fn send_speech_to_text(
+        &self,
+        mut request: SpeechToTextRequest,
+        options: &RequestOptions,
+    ) -> Result<ApiResponse, GroqError> {
+        audio::check_upload(&request, self.max_audio_size)?;
+        self.check(|registry| registry.check_speech_to_text(&request))?;
+        if let Some(model) = request.model.as_mut() {
+            self.resolve_model(model);
+        }
+        self.execute_with(ApiRequest::speech_to_text(request), options)
+    }
+
+    /// Sends a chat completion request to the GROQ API and returns the response.
+    ///
+    /// # Parameters
+    ///
+    /// - `request` - A `ChatCompletionRequest` containing the details of the chat completion request.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, GroqError> {
+        self.chat_completion_with(request, &RequestOptions::default())
+    }
+
+    /// Sends a chat completion request that can be cancelled or given a deadline.
+    ///
+    /// # Parameters
+    ///
+    /// - `request` - A `ChatCompletionRequest` containing the details of the chat completion request.
+    /// - `options` - The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::Cancelled` or `GroqError::DeadlineExceeded` if the call was interrupted,
+    /// and any other `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn chat_completion_with(
+        &self,
+        mut request: ChatCompletionRequest,
+        options: &RequestOptions,
+    ) -> Result<ChatCompletionResponse, GroqError> {
+        self.check(|registry| registry.check_chat_completion(&request))?;
+        self.resolve_model(&mut request.model);
+        let response = self.execute_with(ApiRequest::chat_completion(&request, false), options)?;
+        codec::decode_chat_completion(response, self.strict_responses)
+    }
+
+    /// Sends a chat completion request in JSON mode and parses the generated content into `T`.
+    ///
+    /// # Parameters
+    ///
+    /// - `request` - The `ChatCompletionRequest` to send. If no `response_format` is set, `ResponseFormat::JsonObject` is used.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::StructuredOutputError` if the model output is not valid JSON or does
+    /// not match `T` or the requested schema, and any other `GroqError` if the request fails.
+    pub fn chat_completion_json<T: DeserializeOwned>(
+        &self,
+        mut request: ChatCompletionRequest,
+    ) -> Result<T, GroqError> {
+        request
+            .response_format
+            .get_or_insert(ResponseFormat::JsonObject);
+        let response = self.chat_completion(request)?;
+        codec::structured_output(&response)
+    }
+
+    /// Sends a streamed chat completion request to the GROQ API.
+    ///
+    /// # Parameters
+    ///
+    /// - `request` - The `ChatCompletionRequest` to send. `stream` is always set to `true`.
+    ///
+    /// # Returns
+    ///
+    /// A blocking iterator yielding a `ChatCompletionChunk` for every server-sent event.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if the request fails or the API responds with an error status.
+    pub fn chat_completion_stream(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionChunks, GroqError> {
+        self.chat_completion_stream_with(request, &RequestOptions::default())
+    }
+
+    /// Sends a streamed chat completion request that can be cancelled or given a deadline.
+    ///
+    /// # Parameters
+    ///
+    /// - `request` - The `ChatCompletionRequest` to send. `stream` is always set to `true`.
+    /// - `options` - The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Returns
+    ///
+    /// A blocking iterator yielding a `ChatCompletionChunk` for every server-sent event. The token
+    /// and deadline are checked before every chunk, yielding `GroqError::Cancelled` or
+    /// `GroqError::DeadlineExceeded` once the call is interrupted.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if the request fails, the API responds with an error status, or the
+    /// call was interrupted.
+    pub fn chat_completion_stream_with(
+        &self,
+        mut request: ChatCompletionRequest,
+        options: &RequestOptions,
+    ) -> Result<ChatCompletionChunks, GroqError> {
+        self.check(|registry| registry.check_chat_completion(&request))?;
+        self.resolve_model(&mut request.model);
+        let response = self.open(ApiRequest::chat_completion(&request, true), options)?;
+        Ok(ChatCompletionChunks::new(response).interrupt_on(options))
+    }
+
+    /// Sends a text-to-speech request to the GROQ API and returns the generated audio.
+    ///
+    /// # Parameters
+    ///
+    /// - `request` - The `TextToSpeechRequest` containing the model, input text, voice and speed.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or the API responds with an error status.
+    pub fn text_to_speech(
+        &self,
+        request: TextToSpeechRequest,
+    ) -> Result<TextToSpeechResponse, GroqError> {
+        self.text_to_speech_with(request, &RequestOptions::default())
+    }
+
+    /// Sends a text-to-speech request that can be cancelled or given a deadline.
+    ///
+    /// # Parameters
+    ///
+    /// - `request` - The `TextToSpeechRequest` containing the model, input text, voice and speed.
+    /// - `options` - The `RequestOptions` holding the cancellation token and deadline of the call.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::Cancelled` or `GroqError::DeadlineExceeded` if the call was interrupted,
+    /// and any other `GroqError` if there is an issue sending the request or the API responds with an error status.
+    pub fn text_to_speech_with(
+        &self,
+        mut request: TextToSpeechRequest,
+        options: &RequestOptions,
+    ) -> Result<TextToSpeechResponse, GroqError> {
+        self.check(|registry| registry.check_text_to_speech(&request))?;
+        self.resolve_model(&mut request.model);
+        let response = self.execute_with(ApiRequest::text_to_speech(&request), options)?;
+        codec::decode_text_to_speech(response)
+    }
+
+    /// Lists the models available to the account.
+    ///
+    /// # Returns
+    ///
+    /// The `Model` records returned by the GROQ API, including their context window and whether they are active.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn list_models(&self) -> Result<Vec<Model>, GroqError> {
+        let response = self.execute(ApiRequest::list_models())?;
+        codec::decode_models(response)
+    }
+
+    /// Retrieves a single model.
+    ///
+    /// # Parameters
+    ///
+    /// - `id` - The id of the model, such as `llama-3.3-70b-versatile`.
+    ///
+    /// # Returns
+    ///
+    /// The `Model` record returned by the GROQ API.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn retrieve_model(&self, id: &str) -> Result<Model, GroqError> {
+        let response = self.execute(ApiRequest::retrieve_model(id))?;
+        codec::decode_model(response)
+    }
+
+    /// Uploads a file, such as the JSONL input of a batch.
+    ///
+    /// # Parameters
+    ///
+    /// - `bytes`: The content of the file.
+    /// - `filename`: The name of the file, such as `batch.jsonl`.
+    /// - `purpose`: What the file is for, such as `files::BATCH_PURPOSE`.
+    ///
+    /// # Returns
+    ///
+    /// The `FileObject` of the uploaded file.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn upload_file(
+        &self,
+        bytes: Vec<u8>,
+        filename: &str,
+        purpose: &str,
+    ) -> Result<FileObject, GroqError> {
+        let response = self.execute(ApiRequest::upload_file(bytes, filename, purpose))?;
+        codec::decode_file(response)
+    }
+
+    /// Lists the files of the account.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn list_files(&self) -> Result<Vec<FileObject>, GroqError> {
+        let response = self.execute(ApiRequest::list_files())?;
+        codec::decode_files(response)
+    }
+
+    /// Retrieves the record of a single file.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The id of the file.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn retrieve_file(&self, id: &str) -> Result<FileObject, GroqError> {
+        let response = self.execute(ApiRequest::retrieve_file(id))?;
+        codec::decode_file(response)
+    }
+
+    /// Deletes a file.
     ///
     /// # Parameters
     ///
-    /// - `request`: The `ChatCompletionRequest` containing the model, messages, temperature, max tokens, top-p, and other options.
+    /// - `id`: The id of the file.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// The parsed `ChatCompletionResponse` from the Groq API.
-    pub async fn chat_completion(
-        &self,
-        request: ChatCompletionRequest,
-    ) -> Result<ChatCompletionResponse, GroqError> {
-        let messages = request
-            .messages
-            .iter()
-            .map(|m| {
-                let mut msg_json = json!({
-                    "role": m.role,
-                    "content": m.content,
-                });
-                if let Some(name) = &m.name {
-                    msg_json["name"] = json!(name);
-                }
-                msg_json
-            })
-            .collect::<Vec<Value>>();
-
-        let mut body = json!({
-            "model": request.model,
-            "messages": messages,
-            "temperature": request.temperature.unwrap_or(1.0),
-            "max_tokens": request.max_tokens.unwrap_or(1024),
-            "top_p": request.top_p.unwrap_or(1.0),
-            "stream": request.stream.unwrap_or(false),
-        });
-
-        if let Some(stop) = &request.stop {
-            body["stop"] = json!(stop);
-        }
-        if let Some(seed) = &request.seed {
-            body["seed"] = json!(seed);
-        }
-
-        let response = self
-            .send_request(body, &format!("{}/chat/completions", self.endpoint))
-            .await?;
-        let chat_completion_response: ChatCompletionResponse = serde_json::from_value(response)?;
-        Ok(chat_completion_response)
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn delete_file(&self, id: &str) -> Result<DeletedFile, GroqError> {
+        let response = self.execute(ApiRequest::delete_file(id))?;
+        codec::decode_deleted_file(response)
     }
 
-    /// Parses the response from a Groq API request and returns the response body as a JSON value.
+    /// Downloads the content of a file, such as the output of a batch.
     ///
     /// # Parameters
     ///
-    /// - `response`: The HTTP response from the Groq API request.
+    /// - `id`: The id of the file.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// The parsed JSON value from the response body, or a `GroqError` if the response was not successful.
-    async fn parse_response(&self, response: AResponse) -> Result<Value, GroqError> {
-        let status = response.status();
-        let body: Value = response.json().await?;
-
-        if !status.is_success() {
-            if let Some(error) = body.get("error") {
-                return Err(GroqError::ApiError {
-                    message: error["message"]
-                        .as_str()
-                        .unwrap_or("Unknown error")
-                        .to_string(),
-                    type_: error["type"]
-                        .as_str()
-                        .unwrap_or("unknown_error")
-                        .to_string(),
-                });
-            }
-        }
-
-        Ok(body)
-    }
-
-    pub async fn text_to_speech(
-        &self,
-        request: TextToSpeechRequest,
-    ) -> Result<TextToSpeechResponse, GroqError> {
-        let body = json!({
-            "model": request.model,
-            "input": request.input,
-            "voice": request.voice,
-            "speed": request.speed.unwrap_or(1.0),
-        });
-
-        let response = self
-            .client // Use the async client field
-            .post(&format!("{}/audio/speech", self.endpoint))
-            .header("Content-Type", "application/json")
-            .header("Authorization", &format!("Bearer {}", self.api_key))
-            .json(&body)
-            .send()
-            .await?; // Use .await
-
-        let status = response.status();
-
-        if !status.is_success() {
-            // Try to parse error as JSON using .await
-            let error_body: Result<Value, _> = response.json().await; // Use .await
-            if let Ok(body) = error_body {
-                if let Some(error) = body.get("error") {
-                    return Err(GroqError::ApiError {
-                        message: error["message"]
-                            .as_str()
-                            .unwrap_or("Unknown error")
-                            .to_string(),
-                        type_: error["type"]
-                            .as_str()
-                            .unwrap_or("unknown_error")
-                            .to_string(),
-                    });
-                }
-            }
-            return Err(GroqError::ApiError {
-                message: format!("Request failed with status code: {}", status),
-                type_: "request_error".to_string(),
-            });
-        }
-
-        // Get binary data directly using .await
-        let audio_data = response.bytes().await?.to_vec(); // Use .await
-
-        Ok(TextToSpeechResponse { audio_data })
+    /// Returns a `GroqError` if there is an issue sending the request or reading the response.
+    pub fn file_content(&self, id: &str) -> Result<Vec<u8>, GroqError> {
+        let response = self.execute(ApiRequest::file_content(id))?;
+        codec::decode_file_content(response)
     }
-}
-
-/// An client for interacting with the Groq API.
-///
-/// # Parameters
-///
-/// - `api_key`: The API key for authenticating with the Groq API.
-/// - `endpoint`: The URL of the Groq API endpoint. If not provided, it defaults to <https://api.groq.com/openai/v1>.
-///
-/// # Returns
-///
-/// An instance of `GroqClient` configured with the provided API key and endpoint.
-///
-/// # Example
-///
-///```
-/// use groq_client::GroqClient;
-///
-/// let client = GroqClient::new("my_api_key".to_string(), None);
-///```
-pub struct GroqClient {
-    api_key: String,
-    client: Client,
-    endpoint: String,
-}
 
-impl GroqClient {
-    /// Constructs a new `GroqClient` instance with the provided API key and optional endpoint.
+    /// Creates a batch from an uploaded input file.
     ///
     /// # Parameters
     ///
-    /// - `api_key`: The API key for authenticating with the Groq API.
-    /// - `endpoint`: The URL of the Groq API endpoint. If not provided, it defaults to <https://api.groq.com/openai/v1>.
+    /// - `request`: The `CreateBatchRequest` naming the input file and endpoint.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// A new `GroqClient` instance configured with the provided API key and endpoint.
-    pub fn new(api_key: String, endpoint: Option<String>) -> Self {
-        let ep = endpoint.unwrap_or_else(|| String::from("https://api.groq.com/openai/v1"));
-        Self {
-            api_key,
-            client: Client::new(),
-            endpoint: ep,
-        }
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn create_batch(&self, request: CreateBatchRequest) -> Result<Batch, GroqError> {
+        let response = self.execute(ApiRequest::create_batch(&request))?;
+        codec::decode_batch(response)
     }
 
-    /// Sends a request to the Groq API with the provided JSON body and returns the parsed response.
+    /// Retrieves a single batch, to check its status.
     ///
     /// # Parameters
     ///
-    /// - `body`: The JSON body to send in the request.
-    /// - `link`: The URL link to send the request to.
+    /// - `id`: The id of the batch.
     ///
-    /// # Returns
+    /// # Errors
     ///
-    /// The parsed response from the Groq API as a `Value`.
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn retrieve_batch(&self, id: &str) -> Result<Batch, GroqError> {
+        let response = self.execute(ApiRequest::retrieve_batch(id))?;
+        codec::decode_batch(response)
+    }
+
+    /// Lists the batches of the account.
     ///
     /// # Errors
     ///
     /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
-    fn send_request(&self, body: Value, link: &str) -> Result<Value, GroqError> {
-        let res = self
-            .client
-            .post(link)
-            .header("Content-Type", "application/json")
-            .header("Authorization", &format!("Bearer {}", self.api_key))
-            .json(&body)
-            .send()?;
+    pub fn list_batches(&self) -> Result<Vec<Batch>, GroqError> {
+        let response = self.execute(ApiRequest::list_batches())?;
+        codec::decode_batches(response)
+    }
 
-        parse_response(res)
+    /// Cancels a batch. Requests processed so far stay available in its output file.
+    ///
+    /// # Parameters
+    ///
+    /// - `id`: The id of the batch.
+    ///
+    /// # Errors
+    ///
+    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
+    pub fn cancel_batch(&self, id: &str) -> Result<Batch, GroqError> {
+        let response = self.execute(ApiRequest::cancel_batch(id))?;
+        codec::decode_batch(response)
     }
 
-    /// Sends a speech-to-text request to the Groq API and returns the parsed response.
+    /// Runs chat completion requests as a batch, blocking until the batch ends.
+    ///
+    /// The requests are uploaded as a JSONL file, then the batch is polled every
+    /// `options.poll_interval` until it completes, expires or is cancelled, unless
+    /// `options.max_wait` passes or `options.request_options` interrupts the call first.
     ///
     /// # Parameters
     ///
-    /// - `request`: A `SpeechToTextRequest` containing the necessary parameters for the speech-to-text request.
+    /// - `requests`: The requests to run.
+    /// - `options`: The `BatchOptions` of the batch.
     ///
     /// # Returns
     ///
-    /// The parsed `SpeechToTextResponse` from the Groq API.
+    /// The result of every request, in the order of `requests`. Requests the batch did not
+    /// process, for example because it expired, get an error.
     ///
     /// # Errors
     ///
-    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
-    pub fn speech_to_text(
+    /// Returns a `GroqError` if a request to the Files or Batch API fails, if the batch failed
+    /// as a whole, for example because a request was invalid, or if it was not waited for until
+    /// its end.
+    pub fn chat_completion_batch(
         &self,
-        request: SpeechToTextRequest,
-    ) -> Result<SpeechToTextResponse, GroqError> {
-        // Extract values from request
-        let file = request.file;
-        let temperature = request.temperature;
-        let language = request.language;
-        let english_text = request.english_text;
-        let model = request.model;
-        let prompt = request.prompt;
-        let mut form = Form::new().part("file", Part::bytes(file).file_name("audio.wav"));
-
-        if let Some(temp) = temperature {
-            form = form.text("temperature", temp.to_string());
+        requests: &[ChatCompletionRequest],
+        options: BatchOptions,
+    ) -> Result<Vec<Result<ChatCompletionResponse, GroqError>>, GroqError> {
+        options.request_options.check()?;
+        let input = batches::encode_chat_completions(requests);
+        let file = self.upload_file(input, "batch.jsonl", files::BATCH_PURPOSE)?;
+        let mut batch = self.create_batch(options.create_request(&file.id))?;
+        let mut poll = BatchPoll::new(&options);
+        while !poll.is_done(&batch)? {
+            options.request_options.sleep(options.poll_interval)?;
+            batch = self.retrieve_batch(&batch.id)?;
         }
-
-        if let Some(lang) = language {
-            form = form.text("language", lang);
+        if batch.status == BatchStatus::Failed {
+            return Err(batches::batch_failed(&batch));
+        }
+        let mut results = BatchResults::new(requests.len());
+        for file_id in [&batch.output_file_id, &batch.error_file_id]
+            .into_iter()
+            .flatten()
+        {
+            results.read(&self.file_content(file_id)?);
         }
+        Ok(results.finish(&batch))
+    }
+}
 
-        let link_addition = if english_text {
-            "/audio/translations"
-        } else {
-            "/audio/transcriptions"
-        };
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures_util::StreamExt;
+    use std::sync::Mutex;
+    use testing::{silent_wav, MockGroqServer, MockResponse};
+
+    /// Runs a future to completion on a fresh runtime, outside of any async context so the
+    /// blocking client can be used alongside it.
+    fn block_on<F: std::future::Future>(future: F) -> F::Output {
+        tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .unwrap()
+            .block_on(future)
+    }
 
-        if let Some(mdl) = model {
-            form = form.text("model", mdl);
-        }
-        if let Some(prompt) = prompt {
-            form = form.text("prompt", prompt.to_string());
-        }
+    fn parity_chat_request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            "llama3-70b-8192",
+            vec![ChatCompletionMessage::new(
+                ChatCompletionRoles::User,
+                "Hello",
+            )],
+        )
+        .stop(vec!["\n".to_string()])
+        .seed(7)
+        .response_format(ResponseFormat::JsonObject)
+    }
 
-        let link = format!("{}{}", self.endpoint, link_addition);
-        let response = self
-            .client
-            .post(link)
-            .header("Authorization", &format!("Bearer {}", self.api_key))
-            .multipart(form)
-            .send()?;
+    fn parity_speech_to_text_request() -> SpeechToTextRequest {
+        SpeechToTextRequest::new(b"RIFF....WAVEfmt ".to_vec())
+            .model("whisper-large-v3")
+            .temperature(0.7)
+            .language("en")
+            .prompt("dashi")
+            .response_format(TranscriptFormat::Json)
+    }
 
-        let speech_to_text_response: SpeechToTextResponse = response.json()?;
-        Ok(speech_to_text_response)
+    #[test]
+    fn test_parity_chat_completion() {
+        let server = MockGroqServer::start();
+        server.enqueue("/chat/completions", MockResponse::chat_completion("Hi!"));
+        server.enqueue("/chat/completions", MockResponse::chat_completion("Hi!"));
+        let blocking = GroqClient::new("test_key".to_string(), Some(server.endpoint()))
+            .chat_completion(parity_chat_request())
+            .unwrap();
+        let nonblocking = block_on(async {
+            AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()))
+                .chat_completion(parity_chat_request())
+                .await
+                .unwrap()
+        });
+        let requests = server.requests();
+        assert_eq!(requests[0], requests[1]);
+        assert_eq!(blocking.choices[0].message.content.as_deref(), Some("Hi!"));
+        assert_eq!(
+            nonblocking.choices[0].message.content.as_deref(),
+            Some("Hi!")
+        );
     }
 
-    /// Sends a chat completion request to the GROQ API and returns the response.
-    ///
-    /// # Parameters
-    ///
-    /// - `request` - A `ChatCompletionRequest` containing the details of the chat completion request.
-    ///
-    /// # Errors
-    ///
-    /// Returns a `GroqError` if there is an issue sending the request or parsing the response.
-    pub fn chat_completion(
-        &self,
-        request: ChatCompletionRequest,
-    ) -> Result<ChatCompletionResponse, GroqError> {
-        let messages = request
-            .messages
-            .iter()
-            .map(|m| {
-                let mut msg_json = json!({
-                    "role": m.role,
-                    "content": m.content,
-                });
-                if let Some(name) = &m.name {
-                    msg_json["name"] = json!(name);
-                }
-                msg_json
-            })
-            .collect::<Vec<_>>();
-
-        let mut body = json!({
-            "model": request.model,
-            "messages": messages,
-            "temperature": request.temperature.unwrap_or(1.0),
-            "max_tokens": request.max_tokens.unwrap_or(1024),
-            "top_p": request.top_p.unwrap_or(1.0),
-            "stream": request.stream.unwrap_or(false),
+    #[test]
+    fn test_parity_speech_to_text() {
+        let server = MockGroqServer::start();
+        let blocking = GroqClient::new("test_key".to_string(), Some(server.endpoint()))
+            .speech_to_text(parity_speech_to_text_request())
+            .unwrap();
+        let nonblocking = block_on(async {
+            AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()))
+                .speech_to_text(parity_speech_to_text_request())
+                .await
+                .unwrap()
         });
+        let requests = server.requests();
+        assert_eq!(requests[0], requests[1]);
+        // Both fields used to be dropped by the async client.
+        assert_eq!(requests[0].form_text("prompt").as_deref(), Some("dashi"));
+        assert_eq!(
+            requests[0].form_text("response_format").as_deref(),
+            Some("json")
+        );
+        assert_eq!(blocking.text, testing::MOCK_TEXT);
+        assert_eq!(nonblocking.text, testing::MOCK_TEXT);
+    }
 
-        if let Some(stop) = &request.stop {
-            body["stop"] = json!(stop);
-        }
-        if let Some(seed) = &request.seed {
-            body["seed"] = json!(seed);
-        }
+    #[test]
+    fn test_parity_text_to_speech() {
+        let request = TextToSpeechRequest::new(None, "Sure!", None, Some(1.2));
+        let server = MockGroqServer::start();
+        let blocking = GroqClient::new("test_key".to_string(), Some(server.endpoint()))
+            .text_to_speech(request.clone())
+            .unwrap();
+        let nonblocking = block_on(async {
+            AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()))
+                .text_to_speech(request)
+                .await
+                .unwrap()
+        });
+        let requests = server.requests();
+        assert_eq!(requests[0], requests[1]);
+        assert!(blocking.audio_data.starts_with(b"RIFF"));
+        assert_eq!(nonblocking.audio_data, blocking.audio_data);
+    }
 
-        let response = self.send_request(body, &format!("{}/chat/completions", self.endpoint))?;
-        let chat_completion_response: ChatCompletionResponse = serde_json::from_value(response)?;
-        Ok(chat_completion_response)
+    #[test]
+    fn test_retry_rate_limited() {
+        let server = MockGroqServer::start();
+        server.enqueue("/chat/completions", MockResponse::rate_limited(0));
+        server.enqueue(
+            "/chat/completions",
+            MockResponse::chat_completion("Hi!")
+                .header("x-ratelimit-remaining-requests", "99")
+                .header("x-ratelimit-reset-tokens", "7.66s"),
+        );
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let response = client.chat_completion(parity_chat_request()).unwrap();
+        let rate_limit = response.rate_limit.unwrap();
+        assert_eq!(rate_limit.remaining_requests, Some(99));
+        assert_eq!(
+            rate_limit.reset_tokens,
+            Some(std::time::Duration::from_millis(7660))
+        );
+        assert_eq!(server.requests().len(), 2);
     }
 
-    pub fn text_to_speech(
-        &self,
-        request: TextToSpeechRequest,
-    ) -> Result<TextToSpeechResponse, GroqError> {
-        let body = json!({
-            "model": request.model,
-            "input": request.input,
-            "voice": request.voice,
-            "speed": request.speed.unwrap_or(1.0),
+    #[test]
+    fn test_retry_gives_up() {
+        let server = MockGroqServer::start();
+        for _ in 0..2 {
+            server.enqueue(
+                "/chat/completions",
+                MockResponse::server_error().header("retry-after", "0"),
+            );
+        }
+        let result = block_on(async {
+            AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()))
+                .with_retry_policy(RetryPolicy::default().max_attempts(2))
+                .chat_completion(parity_chat_request())
+                .await
         });
+        assert!(matches!(result, Err(GroqError::ServerError(_))));
+        assert_eq!(server.requests().len(), 2);
+
+        let server = MockGroqServer::start();
+        server.enqueue("/chat/completions", MockResponse::server_error());
+        let result = GroqClient::new("test_key".to_string(), Some(server.endpoint()))
+            .with_retry_policy(RetryPolicy::none())
+            .chat_completion(parity_chat_request());
+        assert!(result.is_err());
+        assert_eq!(server.requests().len(), 1);
+    }
 
-        // We can't use the send_request method directly since it expects a JSON response
-        // Instead, let's use the client setup logic similar to send_request
-        let response = self
-            .client
-            .post(&format!("{}/audio/speech", self.endpoint))
-            .header("Content-Type", "application/json")
-            .header("Authorization", &format!("Bearer {}", self.api_key))
-            .json(&body)
-            .send()?;
-
-        let status = response.status();
-
-        // Handle error cases in a similar way to parse_response
-        if !status.is_success() {
-            // Try to parse error as JSON if possible
-            let error_body: Result<Value, _> = response.json();
-            if let Ok(body) = error_body {
-                if let Some(error) = body.get("error") {
-                    return Err(GroqError::ApiError {
-                        message: error["message"]
-                            .as_str()
-                            .unwrap_or("Unknown error")
-                            .to_string(),
-                        type_: error["type"]
-                            .as_str()
-                            .unwrap_or("unknown_error")
-                            .to_string(),
-                    });
-                }
-            }
-            // If we couldn't parse JSON error, return a generic error
-            return Err(GroqError::ApiError {
-                message: format!("Request failed with status code: {}", status),
-                type_: "request_error".to_string(),
+    #[test]
+    fn test_retry_rate_limiter() {
+        let server = MockGroqServer::start();
+        server.enqueue("/chat/completions", MockResponse::rate_limited(0));
+        server.enqueue("/chat/completions", MockResponse::chat_completion("Hi!"));
+        let limiter = RateLimiter::new().default_limits(RateLimits::new().requests_per_minute(1));
+        let client = AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()))
+            .with_rate_limiter(Arc::new(limiter));
+        // The retry waits for the limiter as well, a full minute on the paused clock.
+        let elapsed = tokio::runtime::Builder::new_current_thread()
+            .enable_all()
+            .start_paused(true)
+            .build()
+            .unwrap()
+            .block_on(async {
+                let start = tokio::time::Instant::now();
+                client.chat_completion(parity_chat_request()).await.unwrap();
+                start.elapsed()
             });
-        }
+        assert!(elapsed >= std::time::Duration::from_secs(60));
+        assert_eq!(server.requests().len(), 2);
+    }
 
-        // Get binary data directly
-        let audio_data = response.bytes()?.to_vec();
+    #[test]
+    fn test_builder_default_headers() {
+        let server = MockGroqServer::start();
+        GroqClient::builder()
+            .api_key("test_key")
+            .endpoint(&server.endpoint())
+            .user_agent("dashi/0.1")
+            .default_header("x-dashi-session", "42")
+            .timeout(std::time::Duration::from_secs(5))
+            .build()
+            .unwrap()
+            .chat_completion(parity_chat_request())
+            .unwrap();
+        let sent = &server.requests()[0];
+        assert_eq!(sent.header("user-agent"), Some("dashi/0.1"));
+        assert_eq!(sent.header("x-dashi-session"), Some("42"));
+        assert_eq!(sent.header("authorization"), Some("Bearer test_key"));
+    }
 
-        Ok(TextToSpeechResponse { audio_data })
+    /// Tags every request and records the path, status, body size and attempts of every response.
+    #[derive(Default)]
+    struct RecordingHook {
+        responses: std::sync::Mutex<Vec<(String, u16, usize, u32)>>,
     }
-}
 
-/// Parses the response from a GROQ API request and returns the response body as a JSON value.
-///
-/// # Parameters
-///
-/// - `response` - The HTTP response from the GROQ API request.
-///
-/// # Errors
-///
-/// Returns a `GroqError` if the response status is not successful or if there is an error parsing the response body.
-///
-/// # Returns
-///
-/// The response body as a JSON value.
-fn parse_response(response: Response) -> Result<Value, GroqError> {
-    let status = response.status();
-    let body: Value = response.json()?;
-
-    if !status.is_success() {
-        if let Some(error) = body.get("error") {
-            return Err(GroqError::ApiError {
-                message: error["message"]
-                    .as_str()
-                    .unwrap_or("Unknown error")
-                    .to_string(),
-                type_: error["type"]
-                    .as_str()
-                    .unwrap_or("unknown_error")
-                    .to_string(),
-            });
+    impl Hook for RecordingHook {
+        fn on_request(&self, request: &mut ApiRequest) {
+            request.headers.insert(
+                "x-request-tag",
+                reqwest::header::HeaderValue::from_static("dashi"),
+            );
         }
-    }
 
-    Ok(body)
-}
+        fn on_response(
+            &self,
+            request: &ApiRequest,
+            response: &mut ApiResponse,
+            info: &RequestInfo,
+        ) {
+            self.responses.lock().unwrap().push((
+                request.path.clone(),
+                response.status.as_u16(),
+                response.body.len(),
+                info.attempts,
+            ));
+        }
+    }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::fs::File;
-    use std::io::Read;
-    use tokio;
+    #[test]
+    fn test_hooks() {
+        let server = MockGroqServer::start();
+        server.enqueue("/chat/completions", MockResponse::rate_limited(0));
+        server.enqueue("/chat/completions", MockResponse::chat_completion("Hi!"));
+        server.enqueue(
+            "/chat/completions",
+            MockResponse::chat_completion_stream(&["Hi", "!"]),
+        );
+        let hook = Arc::new(RecordingHook::default());
+        let client = GroqClient::builder()
+            .api_key("test_key")
+            .endpoint(&server.endpoint())
+            .hook(hook.clone())
+            .build()
+            .unwrap();
+        client.chat_completion(parity_chat_request()).unwrap();
+        let content: String = client
+            .chat_completion_stream(parity_chat_request())
+            .unwrap()
+            .filter_map(|chunk| chunk.unwrap().content().map(str::to_string))
+            .collect();
+        assert_eq!(content, "Hi!");
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 3);
+        assert!(requests
+            .iter()
+            .all(|request| request.header("x-request-tag") == Some("dashi")));
+        let responses = hook.responses.lock().unwrap();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].0, "/chat/completions");
+        assert_eq!((responses[0].1, responses[0].3), (200, 2));
+        assert!(responses[0].2 > 0);
+        // Streams are seen once their status is known, before the body is read.
+        assert_eq!(
+            (responses[1].1, responses[1].2, responses[1].3),
+            (200, 0, 1)
+        );
+    }
 
     #[test]
     fn test_chat_completion() {
-        let api_key = std::env::var("GROQ_API_KEY").unwrap();
-        let client = GroqClient::new(api_key.to_string(), None);
+        let server = MockGroqServer::start();
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
         let messages = vec![ChatCompletionMessage {
             role: ChatCompletionRoles::User,
-            content: "Hello".to_string(),
+            content: "Hello".into(),
             name: None,
+            tool_calls: None,
+            tool_call_id: None,
         }];
         let request = ChatCompletionRequest::new("llama3-70b-8192", messages);
         let response = client.chat_completion(request).unwrap();
@@ -542,13 +1955,9 @@ mod tests {
 
     #[test]
     fn test_speech_to_text() {
-        let api_key = std::env::var("GROQ_API_KEY").unwrap();
-        let client = GroqClient::new(api_key.to_string(), None);
-        let audio_file_path = "onepiece_demo.mp4";
-        let mut file = File::open(audio_file_path).expect("Failed to open audio file");
-        let mut audio_data = Vec::new();
-        file.read_to_end(&mut audio_data)
-            .expect("Failed to read audio file");
+        let server = MockGroqServer::start();
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let audio_data = silent_wav(std::time::Duration::from_secs(1));
         let request = SpeechToTextRequest::new(audio_data)
             .temperature(0.7)
             .language("en")
@@ -560,22 +1969,217 @@ mod tests {
         assert!(!response.text.is_empty());
     }
 
+    #[test]
+    fn test_verbose_speech_to_text() {
+        let server = MockGroqServer::start();
+        server.enqueue(
+            "/audio/transcriptions",
+            MockResponse::verbose_transcription("Hello from Dashi"),
+        );
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let request = SpeechToTextRequest::new(silent_wav(std::time::Duration::from_secs(2)))
+            .model("whisper-large-v3")
+            .timestamp_granularities(&[TimestampGranularity::Segment, TimestampGranularity::Word]);
+        let response = client.speech_to_text(request).unwrap();
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].form_text("response_format").as_deref(),
+            Some("verbose_json")
+        );
+        let body = String::from_utf8_lossy(&requests[0].body);
+        assert!(body.contains("name=\"timestamp_granularities[]\"\r\n\r\nsegment\r\n"));
+        assert!(body.contains("name=\"timestamp_granularities[]\"\r\n\r\nword\r\n"));
+
+        assert_eq!(response.text, "Hello from Dashi");
+        assert_eq!(response.language.as_deref(), Some("English"));
+        assert_eq!(response.duration, Some(1.5));
+        let segments = response.segments.as_deref().unwrap();
+        assert_eq!(segments[0].end, 1.5);
+        assert_eq!(segments[0].no_speech_prob, 0.01);
+        assert_eq!(response.words.as_ref().unwrap().len(), 3);
+        assert!(response.word_at(-0.1).is_none());
+        assert_eq!(response.word_at(0.7).unwrap().word, "from");
+        assert_eq!(response.word_at(9.0).unwrap().word, "Dashi");
+    }
+
+    #[test]
+    fn test_speech_to_text_upload() {
+        let server = MockGroqServer::start();
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()))
+            .with_max_audio_size(1024);
+        let ogg = b"OggS\0\x02\0\0".to_vec();
+        client
+            .speech_to_text(SpeechToTextRequest::new(ogg.clone()))
+            .unwrap();
+        client
+            .speech_to_text(SpeechToTextRequest::new(ogg).file_name("memo.opus"))
+            .unwrap();
+
+        // Oversized and unsupported files are rejected before upload.
+        let oversized = SpeechToTextRequest::new(silent_wav(std::time::Duration::from_secs(1)));
+        assert!(matches!(
+            client.speech_to_text(oversized),
+            Err(GroqError::InvalidInput(_))
+        ));
+        let unsupported = SpeechToTextRequest::new(b"%PDF-1.7".to_vec());
+        assert!(matches!(
+            client.speech_to_text(unsupported),
+            Err(GroqError::InvalidInput(_))
+        ));
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 2);
+        let (file_name, _) = requests[0].form_file("file").unwrap();
+        assert_eq!(file_name, "audio.ogg");
+        assert!(String::from_utf8_lossy(&requests[0].body).contains("Content-Type: audio/ogg\r\n"));
+        let (file_name, _) = requests[1].form_file("file").unwrap();
+        assert_eq!(file_name, "memo.opus");
+    }
+
+    #[test]
+    fn test_speech_to_text_streaming() {
+        let server = MockGroqServer::start();
+        let wav = silent_wav(std::time::Duration::from_secs(3));
+        let path = std::env::temp_dir().join(format!("groq-rs-stream-{}.wav", std::process::id()));
+        std::fs::write(&path, &wav).unwrap();
+        let progress = Arc::new(Mutex::new(Vec::new()));
+        let seen = progress.clone();
+
+        let blocking = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let request = SpeechToTextRequest::from_path(&path)
+            .unwrap()
+            .on_progress(move |progress| seen.lock().unwrap().push(progress));
+        let response = blocking.speech_to_text(request).unwrap();
+        assert_eq!(response.text, testing::MOCK_TEXT);
+        let reader =
+            SpeechToTextRequest::from_reader(std::io::Cursor::new(wav.clone()), "a.wav", None);
+        assert!(matches!(
+            blocking.speech_to_text(reader),
+            Err(GroqError::InvalidInput(_))
+        ));
+        block_on(async {
+            let client = AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()));
+            let request = SpeechToTextRequest::from_path(&path)
+                .unwrap()
+                .language("en");
+            client.speech_to_text(request).await.unwrap();
+            // Readers of unknown length are sent chunked.
+            let reader =
+                SpeechToTextRequest::from_reader(std::io::Cursor::new(wav.clone()), "b.wav", None);
+            client.speech_to_text(reader).await.unwrap();
+            let remote = SpeechToTextRequest::from_url("https://example.com/memo.m4a");
+            client.speech_to_text(remote).await.unwrap();
+        });
+        std::fs::remove_file(&path).unwrap();
+
+        let requests = server.requests();
+        assert_eq!(requests.len(), 4);
+        for (request, name) in requests
+            .iter()
+            .zip(["groq-rs-stream", "groq-rs-stream", "b.wav"])
+        {
+            let (file_name, file) = request.form_file("file").unwrap();
+            assert!(file_name.starts_with(name));
+            assert_eq!(file, wav);
+        }
+        assert_eq!(requests[1].form_text("language").as_deref(), Some("en"));
+        assert!(requests[1].header("content-length").is_some());
+        assert_eq!(requests[2].header("transfer-encoding"), Some("chunked"));
+        assert_eq!(
+            requests[3].form_text("url").as_deref(),
+            Some("https://example.com/memo.m4a")
+        );
+        assert!(requests[3].form_file("file").is_none());
+        let progress = progress.lock().unwrap();
+        assert_eq!(
+            progress.last(),
+            Some(&UploadProgress {
+                sent: wav.len() as u64,
+                total: Some(wav.len() as u64)
+            })
+        );
+    }
+
+    #[test]
+    fn test_transcript_formats() {
+        let server = MockGroqServer::start();
+        let srt = "1\n00:00:00,000 --> 00:00:01,000\nHello!\n\n";
+        server.enqueue(
+            "/audio/transcriptions",
+            MockResponse::new(200, "text/plain", "Hello!\n"),
+        );
+        server.enqueue(
+            "/audio/transcriptions",
+            MockResponse::new(200, "application/x-subrip", srt),
+        );
+        server.enqueue(
+            "/audio/transcriptions",
+            MockResponse::new(200, "application/x-subrip", srt),
+        );
+        let request = |format| {
+            SpeechToTextRequest::new(silent_wav(std::time::Duration::from_millis(100)))
+                .response_format(format)
+        };
+
+        // Text responses used to fail to parse as JSON.
+        let blocking = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let response = blocking
+            .speech_to_text(request(TranscriptFormat::Text))
+            .unwrap();
+        assert_eq!(response.text, "Hello!");
+        let transcript = block_on(async {
+            AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()))
+                .transcript(request(TranscriptFormat::Srt))
+                .await
+                .unwrap()
+        });
+        assert_eq!(transcript.text(), "Hello!");
+        assert_eq!(
+            transcript.subtitles(TranscriptFormat::Srt).as_deref(),
+            Some(srt)
+        );
+        let transcript = blocking.transcript(request(TranscriptFormat::Srt)).unwrap();
+        assert!(matches!(
+            transcript,
+            Transcript::Subtitles {
+                format: TranscriptFormat::Srt,
+                ..
+            }
+        ));
+
+        let requests = server.requests();
+        assert_eq!(
+            requests[0].form_text("response_format").as_deref(),
+            Some("text")
+        );
+        assert_eq!(
+            requests[1].form_text("response_format").as_deref(),
+            Some("srt")
+        );
+        assert_eq!(requests[1], requests[2]);
+    }
+
     #[tokio::test]
     async fn test_async_chat_completion() {
-        let api_key = std::env::var("GROQ_API_KEY").unwrap();
-        let client = AsyncGroqClient::new(api_key, None).await;
+        let server = MockGroqServer::start();
+        let client = AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()));
 
         let messages1 = vec![ChatCompletionMessage {
             role: ChatCompletionRoles::User,
-            content: "Hello".to_string(),
+            content: "Hello".into(),
             name: None,
+            tool_calls: None,
+            tool_call_id: None,
         }];
         let request1 = ChatCompletionRequest::new("llama3-70b-8192", messages1);
 
         let messages2 = vec![ChatCompletionMessage {
             role: ChatCompletionRoles::User,
-            content: "How are you?".to_string(),
+            content: "How are you?".into(),
             name: None,
+            tool_calls: None,
+            tool_call_id: None,
         }];
         let request2 = ChatCompletionRequest::new("llama3-70b-8192", messages2);
 
@@ -587,28 +2191,153 @@ mod tests {
         let response1 = response1.expect("Failed to get response for request 1");
         let response2 = response2.expect("Failed to get response for request 2");
 
-        println!("Response 1: {}", response1.choices[0].message.content);
-        println!("Response 2: {}", response2.choices[0].message.content);
+        println!("Response 1: {:?}", response1.choices[0].message.content);
+        println!("Response 2: {:?}", response2.choices[0].message.content);
 
         assert!(!response1.choices.is_empty());
         assert!(!response2.choices.is_empty());
     }
 
     #[tokio::test]
-    async fn test_async_speech_to_text() {
-        let api_key = std::env::var("GROQ_API_KEY").unwrap();
-        let client = AsyncGroqClient::new(api_key, None).await;
+    async fn test_async_cancellation_and_deadline() {
+        let server = MockGroqServer::start();
+        let late = || MockResponse::chat_completion("late").delay(Duration::from_secs(2));
+        server.enqueue("/chat/completions", late());
+        server.enqueue("/chat/completions", late());
+        let client = AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()));
+
+        let token = CancellationToken::new();
+        let cancel = token.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+            cancel.cancel();
+        });
+        let started = std::time::Instant::now();
+        let options = RequestOptions::new().cancellation(token.clone());
+        let result = client
+            .chat_completion_with(parity_chat_request(), &options)
+            .await;
+        assert!(matches!(result, Err(GroqError::Cancelled)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        // A cancelled token stops later calls before they are sent.
+        let result = client
+            .text_to_speech_with(TextToSpeechRequest::new(None, "Hi", None, None), &options)
+            .await;
+        assert!(matches!(result, Err(GroqError::Cancelled)));
+
+        let options = RequestOptions::new().timeout(Duration::from_millis(50));
+        let result = client
+            .chat_completion_with(parity_chat_request(), &options)
+            .await;
+        assert!(matches!(result, Err(GroqError::DeadlineExceeded)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+        assert_eq!(server.requests().len(), 2);
+
+        server.enqueue(
+            "/chat/completions",
+            MockResponse::chat_completion_stream(&["Hi", "!"]),
+        );
+        let token = CancellationToken::new();
+        let mut stream = client
+            .chat_completion_stream_with(
+                parity_chat_request(),
+                &RequestOptions::new().cancellation(token.clone()),
+            )
+            .await
+            .unwrap();
+        token.cancel();
+        assert!(matches!(
+            stream.next().await,
+            Some(Err(GroqError::Cancelled))
+        ));
+        assert!(stream.next().await.is_none());
+    }
+
+    #[test]
+    fn test_blocking_cancellation_and_deadline() {
+        let server = MockGroqServer::start();
+        server.enqueue(
+            "/chat/completions",
+            MockResponse::chat_completion("late").delay(Duration::from_secs(2)),
+        );
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
 
-        let audio_file_path1 = "onepiece_demo.mp4";
-        let audio_file_path2 = "save.ogg";
+        let token = CancellationToken::new();
+        token.cancel();
+        let result = client.chat_completion_with(
+            parity_chat_request(),
+            &RequestOptions::new().cancellation(token),
+        );
+        assert!(matches!(result, Err(GroqError::Cancelled)));
+        assert!(server.requests().is_empty());
 
-        let (audio_data1, audio_data2) = tokio::join!(
-            tokio::fs::read(audio_file_path1),
-            tokio::fs::read(audio_file_path2)
+        let started = std::time::Instant::now();
+        let result = client.chat_completion_with(
+            parity_chat_request(),
+            &RequestOptions::new().timeout(Duration::from_millis(100)),
         );
+        assert!(matches!(result, Err(GroqError::DeadlineExceeded)));
+        assert!(started.elapsed() < Duration::from_secs(1));
+    }
+
+    #[test]
+    fn test_response_cache() {
+        let server = MockGroqServer::start();
+        let dir = std::env::temp_dir().join(format!("groq-rs-client-cache-{}", std::process::id()));
+        let cache = Arc::new(ResponseCache::new(&dir));
+        let hook = Arc::new(RecordingHook::default());
+        let client = GroqClient::builder()
+            .api_key("test_key")
+            .endpoint(&server.endpoint())
+            .response_cache(cache.clone())
+            .hook(hook.clone())
+            .build()
+            .unwrap();
+
+        let speech = || TextToSpeechRequest::new(None, "Sure!", None, None);
+        let first = client.text_to_speech(speech()).unwrap();
+        let second = client.text_to_speech(speech()).unwrap();
+        assert_eq!(first.audio_data, second.audio_data);
+        assert_eq!(server.requests().len(), 1);
+        // Hooks see cached responses too, as sent zero times.
+        let responses = hook.responses.lock().unwrap().clone();
+        assert_eq!(responses.len(), 2);
+        assert_eq!(responses[0].2, responses[1].2);
+        assert_eq!((responses[0].3, responses[1].3), (1, 0));
+        client
+            .text_to_speech_with(speech(), &RequestOptions::new().bypass_cache(true))
+            .unwrap();
+        assert_eq!(server.requests().len(), 2);
+
+        // Sampled chat completions are sent every time, seeded ones only once.
+        let sampled = || {
+            ChatCompletionRequest::new(
+                "llama3-70b-8192",
+                vec![ChatCompletionMessage::new(
+                    ChatCompletionRoles::User,
+                    "Hello",
+                )],
+            )
+        };
+        client.chat_completion(sampled()).unwrap();
+        client.chat_completion(sampled()).unwrap();
+        assert_eq!(server.requests().len(), 4);
+        let seeded = || sampled().seed(42);
+        client.chat_completion(seeded()).unwrap();
+        let cached = client.chat_completion(seeded()).unwrap();
+        assert_eq!(server.requests().len(), 5);
+        assert!(cached.rate_limit.is_none());
+        cache.clear().unwrap();
+        std::fs::remove_dir(&dir).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_async_speech_to_text() {
+        let server = MockGroqServer::start();
+        let client = AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()));
 
-        let audio_data1 = audio_data1.expect("Failed to read first audio file");
-        let audio_data2 = audio_data2.expect("Failed to read second audio file");
+        let audio_data1 = silent_wav(std::time::Duration::from_secs(1));
+        let audio_data2 = silent_wav(std::time::Duration::from_secs(2));
 
         let (request1, request2) = (
             SpeechToTextRequest::new(audio_data1)
@@ -633,5 +2362,162 @@ mod tests {
 
         assert!(!response1.text.is_empty());
         assert!(!response2.text.is_empty());
+        assert_eq!(server.requests().len(), 2);
+    }
+
+    #[test]
+    fn test_chat_completion_batch() {
+        let server = MockGroqServer::start();
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let requests: Vec<_> = ["one", "two", "three"]
+            .iter()
+            .map(|content| {
+                ChatCompletionRequest::new(
+                    models::LLAMA_3_1_8B_INSTANT,
+                    vec![ChatCompletionMessage::new(
+                        ChatCompletionRoles::User,
+                        content,
+                    )],
+                )
+            })
+            .collect();
+        let results = client
+            .chat_completion_batch(&requests, BatchOptions::new().poll_interval(Duration::ZERO))
+            .unwrap();
+        assert_eq!(results.len(), 3);
+        for result in results {
+            assert_eq!(
+                result.unwrap().choices[0].message.content.as_deref(),
+                Some(testing::MOCK_TEXT)
+            );
+        }
+
+        let requests = server.requests();
+        let calls: Vec<_> = requests
+            .iter()
+            .map(|r| format!("{} {}", r.method, r.path))
+            .collect();
+        assert_eq!(
+            calls,
+            [
+                "POST /files",
+                "POST /batches",
+                "GET /batches/batch_mock_0",
+                "GET /files/file_mock_1/content"
+            ]
+        );
+        assert_eq!(requests[0].form_text("purpose").as_deref(), Some("batch"));
+        let (filename, input) = requests[0].form_file("file").unwrap();
+        assert_eq!(filename, "batch.jsonl");
+        assert_eq!(
+            input
+                .split(|&b| b == b'\n')
+                .filter(|l| !l.is_empty())
+                .count(),
+            3
+        );
+        assert_eq!(requests[1].json().unwrap()["completion_window"], "24h");
+    }
+
+    #[test]
+    fn test_chat_completion_batch_never_ends() {
+        let batch = |status: &str| {
+            MockResponse::json(
+                200,
+                &serde_json::json!({
+                    "id": "batch_mock_0",
+                    "object": "batch",
+                    "endpoint": "/v1/chat/completions",
+                    "input_file_id": "file_mock_0",
+                    "completion_window": "24h",
+                    "status": status,
+                    "created_at": 0,
+                }),
+            )
+        };
+        let requests = [parity_chat_request()];
+        let options = BatchOptions::new().poll_interval(Duration::from_millis(10));
+
+        // A batch stuck in progress is waited for up to `max_wait`.
+        let server = MockGroqServer::start();
+        for _ in 0..100 {
+            server.enqueue("/batches/batch_mock_0", batch("in_progress"));
+        }
+        let error = GroqClient::new("test_key".to_string(), Some(server.endpoint()))
+            .chat_completion_batch(
+                &requests,
+                options.clone().max_wait(Duration::from_millis(50)),
+            )
+            .unwrap_err();
+        assert_eq!(
+            error.details().unwrap().code.as_deref(),
+            Some("batch_unfinished")
+        );
+
+        // A status this crate does not know is only polled a bounded number of times.
+        let server = MockGroqServer::start();
+        for _ in 0..100 {
+            server.enqueue("/batches/batch_mock_0", batch("paused"));
+        }
+        let error = GroqClient::new("test_key".to_string(), Some(server.endpoint()))
+            .chat_completion_batch(&requests, options.clone())
+            .unwrap_err();
+        assert_eq!(
+            error.details().unwrap().code.as_deref(),
+            Some("batch_unfinished")
+        );
+        assert_eq!(server.requests().len(), 12);
+
+        // Waiting can also be interrupted like any other call.
+        let server = MockGroqServer::start();
+        for _ in 0..100 {
+            server.enqueue("/batches/batch_mock_0", batch("in_progress"));
+        }
+        let result = block_on(async {
+            AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()))
+                .chat_completion_batch(
+                    &requests,
+                    options
+                        .request_options(RequestOptions::new().timeout(Duration::from_millis(50))),
+                )
+                .await
+        });
+        assert!(matches!(result, Err(GroqError::DeadlineExceeded)));
+    }
+
+    #[tokio::test]
+    async fn test_async_files_and_batches() {
+        let server = MockGroqServer::start();
+        let client = AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()));
+
+        let file = client
+            .upload_file(b"{}\n".to_vec(), "input.jsonl", files::BATCH_PURPOSE)
+            .await
+            .unwrap();
+        assert_eq!(file.filename, "input.jsonl");
+        assert_eq!(file.bytes, 3);
+        assert_eq!(client.retrieve_file(&file.id).await.unwrap(), file);
+        assert_eq!(client.file_content(&file.id).await.unwrap(), b"{}\n");
+
+        let batch = client
+            .create_batch(CreateBatchRequest::new(&file.id))
+            .await
+            .unwrap();
+        assert_eq!(batch.status, BatchStatus::Validating);
+        assert_eq!(
+            client.retrieve_batch(&batch.id).await.unwrap().status,
+            BatchStatus::Completed
+        );
+        assert_eq!(client.list_batches().await.unwrap().len(), 1);
+        let batch = client.cancel_batch(&batch.id).await.unwrap();
+        assert_eq!(batch.status, BatchStatus::Cancelled);
+
+        // The input and output files of the batch.
+        assert_eq!(client.list_files().await.unwrap().len(), 2);
+        assert!(client.delete_file(&file.id).await.unwrap().deleted);
+        assert!(matches!(
+            client.retrieve_file(&file.id).await,
+            Err(GroqError::InvalidRequest(_))
+        ));
     }
 }
@@ -0,0 +1,327 @@
+use crate::{ChatCompletionRequest, SpeechToTextRequest, TokenEstimator};
+use std::collections::HashMap;
+use std::io::Cursor;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+/// The model the API transcribes with when a speech-to-text request does not name one, whose
+/// limits such requests count against.
+pub(crate) const DEFAULT_SPEECH_TO_TEXT_MODEL: &str = "whisper-large-v3";
+
+/// Represents the limits enforced by a `RateLimiter` for a model.
+///
+/// - `requests_per_minute`: The maximum number of requests per minute.
+/// - `tokens_per_minute`: The maximum number of prompt and completion tokens per minute.
+/// - `audio_seconds_per_hour`: The maximum number of seconds of audio transcribed per hour.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RateLimits {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u32>,
+    pub audio_seconds_per_hour: Option<u32>,
+}
+
+impl RateLimits {
+    /// Creates limits with no restriction.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the maximum number of requests per minute.
+    pub fn requests_per_minute(mut self, requests_per_minute: u32) -> Self {
+        self.requests_per_minute = Some(requests_per_minute);
+        self
+    }
+
+    /// Sets the maximum number of tokens per minute.
+    pub fn tokens_per_minute(mut self, tokens_per_minute: u32) -> Self {
+        self.tokens_per_minute = Some(tokens_per_minute);
+        self
+    }
+
+    /// Sets the maximum number of seconds of audio per hour.
+    pub fn audio_seconds_per_hour(mut self, audio_seconds_per_hour: u32) -> Self {
+        self.audio_seconds_per_hour = Some(audio_seconds_per_hour);
+        self
+    }
+}
+
+/// The estimated cost of a request against the limits of its model.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub(crate) struct RequestCost {
+    pub tokens: f64,
+    pub audio_seconds: f64,
+}
+
+impl RequestCost {
+    /// Estimates the cost of a chat completion request.
+    ///
+    /// Prompt tokens are estimated with the `TokenEstimator` of the model, and `max_tokens` is
+    /// counted in full since the API reserves it up front.
+    pub fn chat_completion(request: &ChatCompletionRequest) -> Self {
+        let prompt_tokens = TokenEstimator::for_model(&request.model).count_request(request);
+        Self {
+            tokens: prompt_tokens as f64 + request.max_tokens.unwrap_or(0) as f64,
+            audio_seconds: 0.0,
+        }
+    }
+
+    /// Estimates the cost of a speech-to-text request.
+    ///
+    /// The duration of WAV files is read from their header; other containers are assumed to be
+    /// encoded at 128 kbps, and files of unknown size or fetched from a URL to be short. Groq
+    /// bills at least ten seconds per request.
+    pub fn speech_to_text(request: &SpeechToTextRequest) -> Self {
+        let seconds = match hound::WavReader::new(Cursor::new(request.file_head())) {
+            Ok(reader) => reader.duration() as f64 / reader.spec().sample_rate.max(1) as f64,
+            Err(_) => request.file_len().unwrap_or(0) as f64 * 8.0 / 128_000.0,
+        };
+        Self {
+            tokens: 0.0,
+            audio_seconds: seconds.max(10.0),
+        }
+    }
+}
+
+/// A token bucket that refills continuously up to its capacity.
+#[derive(Debug)]
+struct TokenBucket {
+    capacity: f64,
+    available: f64,
+    refill_per_second: f64,
+    updated: Instant,
+}
+
+impl TokenBucket {
+    fn new(capacity: u32, period: Duration, now: Instant) -> Self {
+        let capacity = capacity.max(1) as f64;
+        Self {
+            capacity,
+            available: capacity,
+            refill_per_second: capacity / period.as_secs_f64(),
+            updated: now,
+        }
+    }
+
+    fn refill(&mut self, now: Instant) {
+        let elapsed = now.saturating_duration_since(self.updated).as_secs_f64();
+        self.available = (self.available + elapsed * self.refill_per_second).min(self.capacity);
+        self.updated = now;
+    }
+
+    /// Returns how long until `amount` is available. Amounts above the capacity only wait for a full bucket.
+    fn wait_time(&self, amount: f64) -> Duration {
+        let missing = amount.min(self.capacity) - self.available;
+        if missing <= 0.0 {
+            return Duration::ZERO;
+        }
+        Duration::from_secs_f64(missing / self.refill_per_second)
+    }
+
+    fn take(&mut self, amount: f64) {
+        self.available -= amount.min(self.capacity);
+    }
+}
+
+/// The buckets tracking the usage of a single model.
+#[derive(Debug)]
+struct ModelBuckets {
+    requests: Option<TokenBucket>,
+    tokens: Option<TokenBucket>,
+    audio_seconds: Option<TokenBucket>,
+}
+
+impl ModelBuckets {
+    fn new(limits: RateLimits, now: Instant) -> Self {
+        let minute = Duration::from_secs(60);
+        let hour = Duration::from_secs(3600);
+        Self {
+            requests: limits
+                .requests_per_minute
+                .map(|n| TokenBucket::new(n, minute, now)),
+            tokens: limits
+                .tokens_per_minute
+                .map(|n| TokenBucket::new(n, minute, now)),
+            audio_seconds: limits
+                .audio_seconds_per_hour
+                .map(|n| TokenBucket::new(n, hour, now)),
+        }
+    }
+
+    /// Takes the cost of a request if every bucket can cover it, or returns how long to wait.
+    fn try_acquire(&mut self, cost: RequestCost, now: Instant) -> Option<Duration> {
+        let buckets = [
+            (self.requests.as_mut(), 1.0),
+            (self.tokens.as_mut(), cost.tokens),
+            (self.audio_seconds.as_mut(), cost.audio_seconds),
+        ];
+        let mut wait = Duration::ZERO;
+        let mut ready = Vec::new();
+        for (bucket, amount) in buckets {
+            if let Some(bucket) = bucket {
+                bucket.refill(now);
+                wait = wait.max(bucket.wait_time(amount));
+                ready.push((bucket, amount));
+            }
+        }
+        if !wait.is_zero() {
+            return Some(wait);
+        }
+        for (bucket, amount) in ready {
+            bucket.take(amount);
+        }
+        None
+    }
+}
+
+/// A client-side token-bucket rate limiter for `AsyncGroqClient`.
+///
+/// Requests wait until their model has capacity for them instead of being sent and rejected
+/// with `429`. Limits are configured per model, with optional defaults for every other model.
+/// Retries count against the limits like any other attempt.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::{RateLimiter, RateLimits};
+///
+/// let limiter = RateLimiter::new()
+///     .model(
+///         "llama3-70b-8192",
+///         RateLimits::new().requests_per_minute(30).tokens_per_minute(6_000),
+///     )
+///     .model(
+///         "whisper-large-v3",
+///         RateLimits::new().requests_per_minute(20).audio_seconds_per_hour(7_200),
+///     );
+///```
+#[derive(Debug, Default)]
+pub struct RateLimiter {
+    limits: HashMap<String, RateLimits>,
+    default_limits: Option<RateLimits>,
+    buckets: Mutex<HashMap<String, ModelBuckets>>,
+}
+
+impl RateLimiter {
+    /// Creates a rate limiter with no limits.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Sets the limits for a model.
+    ///
+    /// # Arguments
+    ///
+    /// * `model` - The name of the model.
+    /// * `limits` - The limits to enforce for it.
+    pub fn model(mut self, model: &str, limits: RateLimits) -> Self {
+        self.limits.insert(model.to_string(), limits);
+        self
+    }
+
+    /// Sets the limits for models without limits of their own.
+    ///
+    /// # Arguments
+    ///
+    /// * `limits` - The limits to enforce.
+    pub fn default_limits(mut self, limits: RateLimits) -> Self {
+        self.default_limits = Some(limits);
+        self
+    }
+
+    /// Waits until `model` has capacity for a request of the given cost, then takes it.
+    pub(crate) async fn acquire(&self, model: &str, cost: RequestCost) {
+        let Some(limits) = self.limits.get(model).copied().or(self.default_limits) else {
+            return;
+        };
+        loop {
+            let wait = {
+                let now = Instant::now();
+                let mut buckets = self.buckets.lock().unwrap_or_else(|e| e.into_inner());
+                buckets
+                    .entry(model.to_string())
+                    .or_insert_with(|| ModelBuckets::new(limits, now))
+                    .try_acquire(cost, now)
+            };
+            match wait {
+                Some(wait) => tokio::time::sleep(wait).await,
+                None => return,
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatCompletionMessage, ChatCompletionRoles};
+
+    #[tokio::test(start_paused = true)]
+    async fn test_requests_per_minute() {
+        let limiter =
+            RateLimiter::new().model("llama3-70b-8192", RateLimits::new().requests_per_minute(2));
+        let start = Instant::now();
+        for _ in 0..2 {
+            limiter
+                .acquire("llama3-70b-8192", RequestCost::default())
+                .await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+        limiter
+            .acquire("llama3-70b-8192", RequestCost::default())
+            .await;
+        assert!(start.elapsed() >= Duration::from_secs(30));
+
+        // Models without limits are never delayed.
+        let start = Instant::now();
+        for _ in 0..10 {
+            limiter.acquire("gemma-7b-it", RequestCost::default()).await;
+        }
+        assert_eq!(start.elapsed(), Duration::ZERO);
+    }
+
+    #[tokio::test(start_paused = true)]
+    async fn test_tokens_per_minute() {
+        let limiter = RateLimiter::new().default_limits(RateLimits::new().tokens_per_minute(1_000));
+        let request = ChatCompletionRequest::new(
+            "llama3-70b-8192",
+            vec![ChatCompletionMessage::new(
+                ChatCompletionRoles::User,
+                "Hello",
+            )],
+        );
+        let prompt_tokens = TokenEstimator::for_model(&request.model).count_request(&request);
+        let request = request.max_tokens(600 - prompt_tokens);
+        let cost = RequestCost::chat_completion(&request);
+        assert_eq!(cost.tokens, 600.0);
+
+        let start = Instant::now();
+        limiter.acquire("llama3-70b-8192", cost).await;
+        limiter.acquire("llama3-70b-8192", cost).await;
+        // 200 tokens were missing, refilled at 1000 per minute.
+        assert!(start.elapsed() >= Duration::from_secs(12));
+        assert!(start.elapsed() < Duration::from_secs(13));
+    }
+
+    #[test]
+    fn test_speech_to_text_cost() {
+        let spec = hound::WavSpec {
+            channels: 1,
+            sample_rate: 16000,
+            bits_per_sample: 16,
+            sample_format: hound::SampleFormat::Int,
+        };
+        let mut wav = Cursor::new(Vec::new());
+        let mut writer = hound::WavWriter::new(&mut wav, spec).unwrap();
+        for _ in 0..16000 * 30 {
+            writer.write_sample(0i16).unwrap();
+        }
+        writer.finalize().unwrap();
+        let cost = RequestCost::speech_to_text(&SpeechToTextRequest::new(wav.into_inner()));
+        assert_eq!(cost.audio_seconds, 30.0);
+
+        // Short clips are billed as ten seconds.
+        let cost = RequestCost::speech_to_text(&SpeechToTextRequest::new(vec![0; 1000]));
+        assert_eq!(cost.audio_seconds, 10.0);
+    }
+}
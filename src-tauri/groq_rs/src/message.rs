@@ -1,19 +1,154 @@
+use crate::upload::{AudioSource, ProgressCallback};
+use crate::{models, RateLimitInfo, TranscriptFormat};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
+use std::borrow::Cow;
+use std::collections::HashMap;
 use thiserror::Error;
 #[derive(Error, Debug)]
 /// Represents errors that can occur when interacting with the GROQ API.
 ///
 /// - `RequestFailed`: Indicates a failure in the underlying HTTP request.
 /// - `JsonParseError`: Indicates a failure in parsing the JSON response from the API.
-/// - `ApiError`: Indicates an error returned by the API, with a message and error type.
+/// - `Unauthorized`: Indicates that the API key is missing, invalid or lacks permission (`401`, `403`).
+/// - `RateLimited`: Indicates that a rate limit was exceeded (`429`).
+/// - `ContextLengthExceeded`: Indicates that the prompt and `max_tokens` do not fit in the context window of the model.
+/// - `InvalidRequest`: Indicates that the API rejected the request (`400`, `404`, `413`, `422`).
+/// - `ServerError`: Indicates that the API failed to process the request (`5xx`).
+/// - `Timeout`: Indicates that the request timed out before a response was received.
+/// - `ApiError`: Indicates any other error returned by the API.
+/// - `StreamError`: Indicates a failure while reading a streamed response body.
+/// - `StructuredOutputError`: Indicates that a JSON mode response was not valid JSON or did not match the expected schema,
+///   with the offending model output when available.
+/// - `InvalidConfig`: Indicates that a client could not be built from the given settings.
+/// - `InvalidInput`: Indicates that an input, such as an image, was rejected before sending the request.
+/// - `Cancelled`: Indicates that the request was abandoned through its `CancellationToken`.
+/// - `DeadlineExceeded`: Indicates that the deadline of the request passed before it completed.
 pub enum GroqError {
     #[error("API request failed: {0}")]
-    RequestFailed(#[from] reqwest::Error),
+    RequestFailed(reqwest::Error),
     #[error("Failed to parse JSON: {0}")]
     JsonParseError(#[from] serde_json::Error),
-    #[error("API error: {message}")]
-    ApiError { message: String, type_: String },
+    #[error("Unauthorized: {0}")]
+    Unauthorized(Box<ApiErrorDetails>),
+    #[error("Rate limited: {0}")]
+    RateLimited(Box<ApiErrorDetails>),
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(Box<ApiErrorDetails>),
+    #[error("Invalid request: {0}")]
+    InvalidRequest(Box<ApiErrorDetails>),
+    #[error("Server error: {0}")]
+    ServerError(Box<ApiErrorDetails>),
+    #[error("Request timed out: {0}")]
+    Timeout(reqwest::Error),
+    #[error("API error: {0}")]
+    ApiError(Box<ApiErrorDetails>),
+    #[error("Failed to read stream: {0}")]
+    StreamError(#[from] std::io::Error),
+    #[error("Invalid structured output: {message}")]
+    StructuredOutputError {
+        message: String,
+        content: Option<String>,
+    },
+    #[error("Invalid client configuration: {0}")]
+    InvalidConfig(String),
+    #[error("Invalid input: {0}")]
+    InvalidInput(String),
+    #[error("Request cancelled")]
+    Cancelled,
+    #[error("Request deadline exceeded")]
+    DeadlineExceeded,
+}
+
+impl GroqError {
+    /// Returns whether the request that failed with this error is worth retrying as is.
+    ///
+    /// Rate limits, server errors, timeouts and connection failures are transient; every other
+    /// error will fail the same way again.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            GroqError::RateLimited(_) | GroqError::ServerError(_) | GroqError::Timeout(_) => true,
+            GroqError::RequestFailed(e) => e.is_connect(),
+            GroqError::ApiError(details) => details.status == Some(408),
+            _ => false,
+        }
+    }
+
+    /// Returns the details of an error returned by the API.
+    pub fn details(&self) -> Option<&ApiErrorDetails> {
+        match self {
+            GroqError::Unauthorized(details)
+            | GroqError::RateLimited(details)
+            | GroqError::ContextLengthExceeded(details)
+            | GroqError::InvalidRequest(details)
+            | GroqError::ServerError(details)
+            | GroqError::ApiError(details) => Some(details),
+            _ => None,
+        }
+    }
+
+    /// Returns the HTTP status of the response that caused this error, if any.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            GroqError::RequestFailed(e) | GroqError::Timeout(e) => e.status().map(|s| s.as_u16()),
+            _ => self.details().and_then(|details| details.status),
+        }
+    }
+
+    /// Returns the `x-request-id` of the response that caused this error, if any.
+    pub fn request_id(&self) -> Option<&str> {
+        self.details()
+            .and_then(|details| details.request_id.as_deref())
+    }
+
+    /// Returns how long the API asked to wait before retrying, if it did.
+    pub fn retry_after(&self) -> Option<std::time::Duration> {
+        self.details()?.rate_limit.as_ref()?.retry_after
+    }
+}
+
+impl From<reqwest::Error> for GroqError {
+    fn from(error: reqwest::Error) -> Self {
+        if error.is_timeout() {
+            GroqError::Timeout(error)
+        } else {
+            GroqError::RequestFailed(error)
+        }
+    }
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Represents the details of an error returned by the GROQ API.
+///
+/// - `status`: The HTTP status of the response, or `None` for an error sent inside a stream.
+/// - `message`: The error message.
+/// - `type_`: The error type, such as `invalid_request_error`.
+/// - `code`: The error code, such as `model_not_found`.
+/// - `param`: The request parameter the error relates to.
+/// - `request_id`: The `x-request-id` header of the response, to quote when reporting an issue.
+/// - `rate_limit`: The rate limit state reported in the response headers, including `retry-after`.
+pub struct ApiErrorDetails {
+    pub status: Option<u16>,
+    pub message: String,
+    pub type_: Option<String>,
+    pub code: Option<String>,
+    pub param: Option<String>,
+    pub request_id: Option<String>,
+    pub rate_limit: Option<RateLimitInfo>,
+}
+
+impl std::fmt::Display for ApiErrorDetails {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.message)?;
+        if let Some(status) = self.status {
+            write!(f, " (status {}", status)?;
+            if let Some(request_id) = &self.request_id {
+                write!(f, ", request {}", request_id)?;
+            }
+            write!(f, ")")?;
+        }
+        Ok(())
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -23,22 +158,316 @@ pub enum GroqError {
 /// - `System`: Indicates a message from the system.
 /// - `User`: Indicates a message from the user.
 /// - `Assistant`: Indicates a message from the assistant.
+/// - `Tool`: Indicates the result of a tool call, sent back to the model.
 pub enum ChatCompletionRoles {
     System,
     User,
     Assistant,
+    Tool,
 }
 
 #[derive(Debug, Clone, Serialize)]
 /// Represents a message in a chat completion response.
 ///
 /// - `role`: The role of the message, such as `System`, `User`, or `Assistant`.
-/// - `content`: The content of the message.
+/// - `content`: The content of the message, either text or a list of text and image parts.
 /// - `name`: An optional name associated with the message.
+/// - `tool_calls`: The tool calls requested by an `Assistant` message.
+/// - `tool_call_id`: The id of the tool call a `Tool` message responds to.
 pub struct ChatCompletionMessage {
     pub role: ChatCompletionRoles,
-    pub content: String,
+    pub content: MessageContent,
     pub name: Option<String>,
+    pub tool_calls: Option<Vec<ToolCall>>,
+    pub tool_call_id: Option<String>,
+}
+
+impl ChatCompletionMessage {
+    /// Creates a new message with the given role and content.
+    pub fn new(role: ChatCompletionRoles, content: &str) -> Self {
+        Self {
+            role,
+            content: MessageContent::Text(content.to_string()),
+            name: None,
+            tool_calls: None,
+            tool_call_id: None,
+        }
+    }
+
+    /// Creates a new message with the given role and content parts, such as text and images.
+    ///
+    /// # Example
+    ///
+    ///```
+    /// use groq_api_rust::{ChatCompletionMessage, ChatCompletionRoles, ContentPart, ImageUrl};
+    ///
+    /// let message = ChatCompletionMessage::parts(
+    ///     ChatCompletionRoles::User,
+    ///     vec![
+    ///         ContentPart::text("What is in this screenshot?"),
+    ///         ContentPart::image(ImageUrl::new("https://example.com/screenshot.png")),
+    ///     ],
+    /// );
+    ///```
+    pub fn parts(role: ChatCompletionRoles, parts: Vec<ContentPart>) -> Self {
+        Self {
+            content: MessageContent::Parts(parts),
+            ..Self::new(role, "")
+        }
+    }
+
+    /// Creates a `Tool` message carrying the result of the tool call with the given id.
+    pub fn tool(tool_call_id: &str, content: &str) -> Self {
+        Self {
+            tool_call_id: Some(tool_call_id.to_string()),
+            ..Self::new(ChatCompletionRoles::Tool, content)
+        }
+    }
+}
+
+impl From<Message> for ChatCompletionMessage {
+    /// Converts a response message back into a request message, so an assistant turn
+    /// (including its tool calls) can be appended to the conversation history.
+    fn from(message: Message) -> Self {
+        Self {
+            role: message.role,
+            content: MessageContent::Text(message.content.unwrap_or_default()),
+            name: None,
+            tool_calls: message.tool_calls,
+            tool_call_id: None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(untagged)]
+/// Represents the content of a request message.
+///
+/// - `Text`: Plain text.
+/// - `Parts`: A list of text and image parts, for vision models.
+pub enum MessageContent {
+    Text(String),
+    Parts(Vec<ContentPart>),
+}
+
+impl MessageContent {
+    /// Returns the text of the content, joining the text parts with newlines.
+    pub fn text(&self) -> Cow<'_, str> {
+        match self {
+            MessageContent::Text(text) => Cow::Borrowed(text),
+            MessageContent::Parts(parts) => Cow::Owned(
+                parts
+                    .iter()
+                    .filter_map(|part| match part {
+                        ContentPart::Text { text } => Some(text.as_str()),
+                        ContentPart::ImageUrl { .. } => None,
+                    })
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        }
+    }
+
+    /// Returns the images of the content.
+    pub fn images(&self) -> Vec<&ImageUrl> {
+        match self {
+            MessageContent::Text(_) => Vec::new(),
+            MessageContent::Parts(parts) => parts
+                .iter()
+                .filter_map(|part| match part {
+                    ContentPart::ImageUrl { image_url } => Some(image_url),
+                    ContentPart::Text { .. } => None,
+                })
+                .collect(),
+        }
+    }
+}
+
+impl From<String> for MessageContent {
+    fn from(text: String) -> Self {
+        MessageContent::Text(text)
+    }
+}
+
+impl From<&str> for MessageContent {
+    fn from(text: &str) -> Self {
+        MessageContent::Text(text.to_string())
+    }
+}
+
+impl From<Vec<ContentPart>> for MessageContent {
+    fn from(parts: Vec<ContentPart>) -> Self {
+        MessageContent::Parts(parts)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// Represents a part of the content of a message.
+///
+/// - `Text`: A piece of text.
+/// - `ImageUrl`: An image, given by URL or as a base64 data URL.
+pub enum ContentPart {
+    Text { text: String },
+    ImageUrl { image_url: ImageUrl },
+}
+
+impl ContentPart {
+    /// Creates a text part.
+    pub fn text(text: &str) -> Self {
+        ContentPart::Text {
+            text: text.to_string(),
+        }
+    }
+
+    /// Creates an image part.
+    pub fn image(image_url: ImageUrl) -> Self {
+        ContentPart::ImageUrl { image_url }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents an image sent to a vision model.
+///
+/// - `url`: The URL of the image, or a `data:` URL holding it in base64.
+/// - `detail`: The level of detail the model should process the image at.
+///
+/// Use `ImageUrl::from_file` or `ImageUrl::from_bytes` to send a local image.
+pub struct ImageUrl {
+    pub url: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub detail: Option<ImageDetail>,
+}
+
+impl ImageUrl {
+    /// Creates an image from its URL.
+    pub fn new(url: &str) -> Self {
+        Self {
+            url: url.to_string(),
+            detail: None,
+        }
+    }
+
+    /// Sets the level of detail the model should process the image at.
+    pub fn detail(mut self, detail: ImageDetail) -> Self {
+        self.detail = Some(detail);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Represents the level of detail an image is processed at.
+pub enum ImageDetail {
+    Auto,
+    Low,
+    High,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents a tool the model may call.
+///
+/// - `type_`: The type of the tool. Currently only `function` is supported.
+/// - `function`: The definition of the function.
+pub struct Tool {
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub function: FunctionDefinition,
+}
+
+impl Tool {
+    /// Creates a new `function` tool.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the function.
+    /// * `description` - A description of what the function does, used by the model to choose when to call it.
+    /// * `parameters` - The parameters the function accepts, described as a JSON Schema object.
+    pub fn function(name: &str, description: &str, parameters: Value) -> Self {
+        Self {
+            type_: "function".to_string(),
+            function: FunctionDefinition {
+                name: name.to_string(),
+                description: Some(description.to_string()),
+                parameters: Some(parameters),
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the definition of a function tool.
+///
+/// - `name`: The name of the function.
+/// - `description`: An optional description of what the function does.
+/// - `parameters`: The parameters the function accepts, described as a JSON Schema object.
+pub struct FunctionDefinition {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub parameters: Option<Value>,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+/// Controls which (if any) tool is called by the model.
+///
+/// - `None`: The model will not call any tool and generates a message instead.
+/// - `Auto`: The model can pick between generating a message or calling tools.
+/// - `Required`: The model must call one or more tools.
+/// - `Function`: The model must call the named function.
+pub enum ToolChoice {
+    None,
+    Auto,
+    Required,
+    Function(String),
+}
+
+impl Serialize for ToolChoice {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match self {
+            ToolChoice::None => serializer.serialize_str("none"),
+            ToolChoice::Auto => serializer.serialize_str("auto"),
+            ToolChoice::Required => serializer.serialize_str("required"),
+            ToolChoice::Function(name) => serde_json::json!({
+                "type": "function",
+                "function": { "name": name },
+            })
+            .serialize(serializer),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents a tool call requested by the model.
+///
+/// - `id`: The id of the tool call, echoed back in the `tool_call_id` of the `Tool` message.
+/// - `type_`: The type of the tool. Currently only `function` is supported.
+/// - `function`: The function the model wants to call.
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub type_: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the function called by a tool call.
+///
+/// - `name`: The name of the function to call.
+/// - `arguments`: The arguments to call the function with, as a JSON-encoded string generated by the model.
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+impl FunctionCall {
+    /// Parses the JSON-encoded `arguments` into `T`.
+    ///
+    /// The model does not always generate valid JSON, so callers should handle the error.
+    pub fn parse_arguments<T: serde::de::DeserializeOwned>(&self) -> Result<T, GroqError> {
+        Ok(serde_json::from_str(&self.arguments)?)
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -52,39 +481,177 @@ pub struct ChatCompletionMessage {
 /// - `system_fingerprint`: A unique identifier for the system that generated the response.
 /// - `usage`: Usage statistics for the request, including token counts and processing times.
 /// - `x_groq`: Additional metadata about the response, including the GROQ API ID.
+/// - `extra`: Fields this crate does not know about yet, kept as sent.
+/// - `rate_limit`: The rate limit state reported in the response headers.
+///
+/// Fields that OpenAI-compatible servers may leave out are optional, and unknown fields are kept
+/// in `extra` rather than rejected. Use `strict_responses` on the client to reject both instead.
 pub struct ChatCompletionResponse {
     pub choices: Vec<Choice>,
+    #[serde(default)]
     pub created: u64,
     pub id: String,
     pub model: String,
+    #[serde(default)]
     pub object: String,
-    pub system_fingerprint: String,
-    pub usage: Usage,
-    pub x_groq: XGroq,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    #[serde(default)]
+    pub x_groq: Option<XGroq>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+    #[serde(skip)]
+    pub rate_limit: Option<RateLimitInfo>,
+}
+
+impl ChatCompletionResponse {
+    /// Returns the paths of the fields this crate does not know about, such as `choices[0].message.reasoning`.
+    pub fn unknown_fields(&self) -> Vec<String> {
+        let mut fields = extra_fields("", &self.extra);
+        for (i, choice) in self.choices.iter().enumerate() {
+            let path = format!("choices[{}].", i);
+            fields.extend(extra_fields(&path, &choice.extra));
+            fields.extend(extra_fields(
+                &format!("{}message.", path),
+                &choice.message.extra,
+            ));
+        }
+        if let Some(usage) = &self.usage {
+            fields.extend(extra_fields("usage.", &usage.extra));
+        }
+        if let Some(x_groq) = &self.x_groq {
+            fields.extend(extra_fields("x_groq.", &x_groq.extra));
+        }
+        fields.sort();
+        fields
+    }
+
+    /// Returns the fields the Groq API always sends that are missing from this response.
+    pub fn missing_fields(&self) -> Vec<&'static str> {
+        let mut fields = Vec::new();
+        if self.system_fingerprint.is_none() {
+            fields.push("system_fingerprint");
+        }
+        if self.usage.is_none() {
+            fields.push("usage");
+        }
+        if self.x_groq.is_none() {
+            fields.push("x_groq");
+        }
+        if self.choices.iter().any(|c| c.finish_reason.is_none()) {
+            fields.push("choices[].finish_reason");
+        }
+        fields
+    }
+}
+
+/// Lists the keys of an `extra` map, prefixed with the path of the object holding them.
+fn extra_fields(prefix: &str, extra: &HashMap<String, Value>) -> Vec<String> {
+    extra
+        .keys()
+        .map(|key| format!("{}{}", prefix, key))
+        .collect()
 }
 
 #[derive(Debug, Clone, Deserialize)]
 /// Represents a single choice in a chat completion response.
 ///
-/// - `finish_reason`: The reason the generation finished, such as "stop" or "length".
+/// - `finish_reason`: The reason the generation finished, such as `Stop` or `Length`.
 /// - `index`: The index of the choice within the list of choices.
-/// - `logprobs`: Optional log probabilities for the tokens in the generated text.
+/// - `logprobs`: The log probabilities of the generated tokens, when requested with `logprobs`.
 /// - `message`: The message associated with this choice, containing the role, content, and optional name.
+/// - `extra`: Fields this crate does not know about yet, kept as sent.
 pub struct Choice {
-    pub finish_reason: String,
+    #[serde(default)]
+    pub finish_reason: Option<FinishReason>,
+    #[serde(default)]
     pub index: u64,
-    pub logprobs: Option<Value>,
+    #[serde(default)]
+    pub logprobs: Option<LogProbs>,
     pub message: Message,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+/// Represents the reason a choice stopped generating.
+///
+/// - `Stop`: The model finished its answer or hit a stop sequence.
+/// - `Length`: The generation reached `max_tokens` or the context window.
+/// - `ToolCalls`: The model requested tool calls.
+/// - `ContentFilter`: The output was withheld by a content filter.
+/// - `FunctionCall`: The model called a function, in the legacy function calling format.
+/// - `Unknown`: A reason this crate does not know about yet, kept as sent.
+pub enum FinishReason {
+    Stop,
+    Length,
+    ToolCalls,
+    ContentFilter,
+    FunctionCall,
+    Unknown(String),
+}
+
+impl FinishReason {
+    /// Returns the reason as sent by the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            FinishReason::Stop => "stop",
+            FinishReason::Length => "length",
+            FinishReason::ToolCalls => "tool_calls",
+            FinishReason::ContentFilter => "content_filter",
+            FinishReason::FunctionCall => "function_call",
+            FinishReason::Unknown(reason) => reason,
+        }
+    }
+}
+
+impl From<&str> for FinishReason {
+    fn from(reason: &str) -> Self {
+        match reason {
+            "stop" => FinishReason::Stop,
+            "length" => FinishReason::Length,
+            "tool_calls" => FinishReason::ToolCalls,
+            "content_filter" => FinishReason::ContentFilter,
+            "function_call" => FinishReason::FunctionCall,
+            other => FinishReason::Unknown(other.to_string()),
+        }
+    }
+}
+
+impl std::fmt::Display for FinishReason {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl Serialize for FinishReason {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(self.as_str())
+    }
+}
+
+impl<'de> Deserialize<'de> for FinishReason {
+    fn deserialize<D: serde::Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let reason = String::deserialize(deserializer)?;
+        Ok(FinishReason::from(reason.as_str()))
+    }
 }
 
 #[derive(Debug, Clone, Deserialize)]
 /// Represents a message in a chat completion response.
 ///
-/// - `content`: The content of the message.
+/// - `content`: The content of the message, absent when the model only returns tool calls.
 /// - `role`: The role of the message, such as `System`, `User`, or `Assistant`.
+/// - `tool_calls`: The tool calls requested by the model, if any.
+/// - `extra`: Fields this crate does not know about yet, such as the reasoning of some models.
 pub struct Message {
-    pub content: String,
+    pub content: Option<String>,
     pub role: ChatCompletionRoles,
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
@@ -96,42 +663,242 @@ pub struct Message {
 /// - `prompt_tokens`: The number of tokens in the prompt.
 /// - `total_time`: The total time (in seconds) for the entire request.
 /// - `total_tokens`: The total number of tokens used in the request.
+/// - `queue_time`: The time (in seconds) the request waited before being processed.
+/// - `extra`: Fields this crate does not know about yet, kept as sent.
+///
+/// Times are only sent by the Groq API, so they are `None` for other OpenAI-compatible servers.
 pub struct Usage {
-    pub completion_time: f64,
+    #[serde(default)]
+    pub completion_time: Option<f64>,
+    #[serde(default)]
     pub completion_tokens: u64,
-    pub prompt_time: f64,
+    #[serde(default)]
+    pub prompt_time: Option<f64>,
+    #[serde(default)]
     pub prompt_tokens: u64,
-    pub total_time: f64,
+    #[serde(default)]
+    pub total_time: Option<f64>,
+    #[serde(default)]
     pub total_tokens: u64,
+    #[serde(default)]
+    pub queue_time: Option<f64>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
 }
 
 #[derive(Debug, Clone, Deserialize)]
 /// Represents a GROQ-related data structure.
 ///
 /// - `id`: The unique identifier for this GROQ-related data.
+/// - `usage`: Usage statistics, only sent on the final chunk of a streamed response.
+/// - `extra`: Fields this crate does not know about yet, kept as sent.
 pub struct XGroq {
     pub id: String,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+/// Represents a single server-sent event from a streamed chat completion.
+///
+/// - `choices`: The deltas produced for each choice since the previous chunk.
+/// - `created`: The timestamp (in seconds since the epoch) when the response was generated.
+/// - `id`: The unique identifier for the response, shared by every chunk of the stream.
+/// - `model`: The name of the model used to generate the response.
+/// - `object`: The type of the response object, always `chat.completion.chunk`.
+/// - `system_fingerprint`: A unique identifier for the system that generated the response.
+/// - `x_groq`: Additional metadata; the final chunk carries the request `usage`.
+/// - `usage`: The request usage, sent on the final chunk by OpenAI-compatible servers instead of `x_groq`.
+/// - `extra`: Fields this crate does not know about yet, kept as sent.
+pub struct ChatCompletionChunk {
+    pub choices: Vec<ChunkChoice>,
+    #[serde(default)]
+    pub created: u64,
+    pub id: String,
+    pub model: String,
+    #[serde(default)]
+    pub object: String,
+    #[serde(default)]
+    pub system_fingerprint: Option<String>,
+    #[serde(default)]
+    pub x_groq: Option<XGroq>,
+    #[serde(default)]
+    pub usage: Option<Usage>,
+    #[serde(flatten)]
+    pub extra: HashMap<String, Value>,
+}
+
+impl ChatCompletionChunk {
+    /// Returns the content delta of the first choice, if this chunk carries any text.
+    pub fn content(&self) -> Option<&str> {
+        self.choices.first()?.delta.content.as_deref()
+    }
+
+    /// Returns the usage statistics reported on the final chunk of the stream.
+    pub fn usage(&self) -> Option<&Usage> {
+        self.x_groq
+            .as_ref()
+            .and_then(|x_groq| x_groq.usage.as_ref())
+            .or(self.usage.as_ref())
+    }
+}
+
+#[derive(Debug, Clone, Deserialize)]
+/// Represents a single choice in a streamed chat completion chunk.
+///
+/// - `delta`: The incremental message content for this choice.
+/// - `finish_reason`: Set on the last chunk of the choice, such as `Stop` or `Length`.
+/// - `index`: The index of the choice within the list of choices.
+/// - `logprobs`: The log probabilities of the tokens in the delta, when requested with `logprobs`.
+pub struct ChunkChoice {
+    #[serde(default)]
+    pub delta: Delta,
+    #[serde(default)]
+    pub finish_reason: Option<FinishReason>,
+    #[serde(default)]
+    pub index: u64,
+    #[serde(default)]
+    pub logprobs: Option<LogProbs>,
+}
+
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+/// Represents the log probabilities of the tokens of a choice.
+///
+/// - `content`: The log probability of each generated token, in order.
+pub struct LogProbs {
+    #[serde(default)]
+    pub content: Option<Vec<TokenLogProb>>,
+}
+
+impl LogProbs {
+    /// Returns the tokens, or an empty slice when the API sent none.
+    pub fn tokens(&self) -> &[TokenLogProb] {
+        self.content.as_deref().unwrap_or_default()
+    }
+
+    /// Returns the average log probability of the tokens, or `None` if there are none.
+    pub fn mean_logprob(&self) -> Option<f64> {
+        let tokens = self.tokens();
+        if tokens.is_empty() {
+            return None;
+        }
+        Some(tokens.iter().map(|t| t.logprob).sum::<f64>() / tokens.len() as f64)
+    }
+
+    /// Returns the geometric mean of the token probabilities, a confidence score between 0 and 1.
+    pub fn confidence(&self) -> Option<f64> {
+        self.mean_logprob().map(f64::exp)
+    }
+
+    /// Returns the perplexity of the generated text; lower values mean a more confident model.
+    pub fn perplexity(&self) -> Option<f64> {
+        self.mean_logprob().map(|mean| (-mean).exp())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents the log probability of a generated token.
+///
+/// - `token`: The token.
+/// - `logprob`: The natural log of the probability of the token.
+/// - `bytes`: The UTF-8 bytes of the token, useful when a character spans several tokens.
+/// - `top_logprobs`: The most likely tokens at this position, when requested with `top_logprobs`.
+pub struct TokenLogProb {
+    pub token: String,
+    pub logprob: f64,
+    #[serde(default)]
+    pub bytes: Option<Vec<u8>>,
+    #[serde(default)]
+    pub top_logprobs: Vec<TopLogProb>,
+}
+
+impl TokenLogProb {
+    /// Returns the probability of the token, between 0 and 1.
+    pub fn probability(&self) -> f64 {
+        self.logprob.exp()
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents one of the most likely tokens at a position.
+///
+/// - `token`: The token.
+/// - `logprob`: The natural log of the probability of the token.
+/// - `bytes`: The UTF-8 bytes of the token.
+pub struct TopLogProb {
+    pub token: String,
+    pub logprob: f64,
+    #[serde(default)]
+    pub bytes: Option<Vec<u8>>,
+}
+
+#[derive(Debug, Clone, Default, Deserialize)]
+/// Represents the incremental message content of a streamed choice.
+///
+/// - `content`: The text generated since the previous chunk.
+/// - `role`: The role of the message, only sent on the first chunk.
+/// - `tool_calls`: Fragments of tool calls, to be merged by `index`.
+pub struct Delta {
+    pub content: Option<String>,
+    pub role: Option<ChatCompletionRoles>,
+    pub tool_calls: Option<Vec<ToolCallDelta>>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+/// Represents a fragment of a tool call in a streamed chat completion.
+///
+/// - `index`: The position of the tool call the fragment belongs to.
+/// - `id`: The id of the tool call, only sent on its first fragment.
+/// - `type_`: The type of the tool, only sent on its first fragment.
+/// - `function`: The name and a piece of the arguments of the called function.
+pub struct ToolCallDelta {
+    pub index: u64,
+    pub id: Option<String>,
+    #[serde(rename = "type")]
+    pub type_: Option<String>,
+    pub function: Option<FunctionCallDelta>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+/// Represents a fragment of a function call in a streamed chat completion.
+///
+/// - `name`: The name of the function, only sent on its first fragment.
+/// - `arguments`: The next piece of the JSON-encoded arguments.
+pub struct FunctionCallDelta {
+    pub name: Option<String>,
+    pub arguments: Option<String>,
 }
 
 #[derive(Debug, Clone)]
 /// Represents a request to the speech-to-text API.
 ///
-/// - `file`: The audio file to be transcribed.
+/// - `file`: The audio file to be transcribed. Empty for requests created with `from_path`,
+///   `from_reader` or `from_url`, whose audio is streamed or downloaded instead.
+/// - `file_name`: The name the file is uploaded with. Defaults to `audio` with the extension of its format.
 /// - `model`: The speech recognition model to use.
 /// - `temperature`: The temperature parameter to control the randomness of the transcription.
 /// - `language`: The language of the audio file.
 /// - `english_text`: If true, the API will use the translation endpoint instead of the transcription endpoint.
 /// - `prompt`: An optional prompt to provide context for the transcription.
-/// - `response_format`: The desired format of the transcription response, either "text" or "json".
+/// - `response_format`: The desired format of the transcription response, JSON by default.
+/// - `timestamp_granularities`: The timestamps returned with a `VerboseJson` response, for segments and/or words.
+/// - `url`: The URL of an audio file for the API to download, instead of uploading `file`.
 pub struct SpeechToTextRequest {
     pub file: Vec<u8>,
+    pub file_name: Option<String>,
     pub model: Option<String>,
     pub temperature: Option<f64>,
     pub language: Option<String>,
     /// If true, the API will use following path: `/audio/translations` instead of `/audio/transcriptions`
     pub english_text: bool,
     pub prompt: Option<String>,
-    pub response_format: Option<String>,
+    pub response_format: Option<TranscriptFormat>,
+    pub timestamp_granularities: Option<Vec<TimestampGranularity>>,
+    pub url: Option<String>,
+    pub(crate) source: Option<AudioSource>,
+    pub(crate) progress: Option<ProgressCallback>,
 }
 
 /// Constructs a new `SpeechToTextRequest` with the given audio file.
@@ -145,12 +912,17 @@ impl SpeechToTextRequest {
     pub fn new(file: Vec<u8>) -> Self {
         Self {
             file,
+            file_name: None,
             model: None,
             temperature: None,
             language: None,
             english_text: false,
             prompt: None,
             response_format: None,
+            timestamp_granularities: None,
+            url: None,
+            source: None,
+            progress: None,
         }
     }
 
@@ -190,6 +962,18 @@ impl SpeechToTextRequest {
         self
     }
 
+    /// Sets the name the audio file is uploaded with, such as `memo.m4a`.
+    ///
+    /// # Arguments
+    /// * `file_name` - The file name. Its extension gives the format of files that cannot be sniffed.
+    ///
+    /// # Returns
+    /// The modified `SpeechToTextRequest` instance with the updated file name.
+    pub fn file_name(mut self, file_name: &str) -> Self {
+        self.file_name = Some(file_name.to_string());
+        self
+    }
+
     /// Sets the speech recognition model to use.
     ///
     /// # Arguments
@@ -217,23 +1001,127 @@ impl SpeechToTextRequest {
     /// Sets the desired format of the transcription response.
     ///
     /// # Arguments
-    /// * `response_format` - The desired format of the transcription response, either "text" or "json".
+    /// * `response_format` - The desired format of the transcription response.
     ///
     /// # Returns
     /// The modified `SpeechToTextRequest` instance with the updated response format.
-    pub fn response_format(mut self, response_format: &str) -> Self {
-        // Currently only "text" and "json" are supported.
-        self.response_format = Some(response_format.to_string());
+    pub fn response_format(mut self, response_format: TranscriptFormat) -> Self {
+        self.response_format = Some(response_format);
+        self
+    }
+
+    /// Sets the timestamps returned with the transcription, for segments and/or words.
+    ///
+    /// # Arguments
+    /// * `timestamp_granularities` - The granularities of the timestamps to return.
+    ///
+    /// # Returns
+    /// The modified `SpeechToTextRequest` instance with the updated granularities. Timestamps
+    /// are only returned in the `VerboseJson` format, used unless another format was set.
+    pub fn timestamp_granularities(
+        mut self,
+        timestamp_granularities: &[TimestampGranularity],
+    ) -> Self {
+        self.timestamp_granularities = Some(timestamp_granularities.to_vec());
+        self.response_format
+            .get_or_insert(TranscriptFormat::VerboseJson);
         self
     }
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+/// Represents the granularity of the timestamps of a `VerboseJson` transcription.
+///
+/// - `Segment`: Returns `segments`, with their timings and confidence. The default.
+/// - `Word`: Returns `words`, with their timings.
+pub enum TimestampGranularity {
+    Segment,
+    Word,
+}
+
+impl TimestampGranularity {
+    /// Returns the granularity as sent to the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            TimestampGranularity::Segment => "segment",
+            TimestampGranularity::Word => "word",
+        }
+    }
+}
+
 #[derive(Debug, Clone, Deserialize)]
 /// Represents the response from a speech-to-text transcription request.
 ///
 /// The `text` field contains the transcribed text from the audio input.
+/// The `language`, `duration`, `segments` and `words` fields are only set in the `VerboseJson` format,
+/// `segments` and `words` according to the requested `timestamp_granularities`.
+/// The `rate_limit` field contains the rate limit state reported in the response headers.
 pub struct SpeechToTextResponse {
     pub text: String,
+    pub language: Option<String>,
+    pub duration: Option<f64>,
+    pub segments: Option<Vec<TranscriptionSegment>>,
+    pub words: Option<Vec<TranscriptionWord>>,
+    #[serde(skip)]
+    pub rate_limit: Option<RateLimitInfo>,
+}
+
+impl SpeechToTextResponse {
+    /// Returns the word being spoken `time` seconds into the audio, to highlight it during playback.
+    ///
+    /// Between two words, the last word spoken is returned. Returns `None` before the first word,
+    /// or if no word timestamps were requested.
+    pub fn word_at(&self, time: f64) -> Option<&TranscriptionWord> {
+        let words = self.words.as_deref()?;
+        let spoken = words.partition_point(|word| word.start <= time);
+        spoken.checked_sub(1).map(|index| &words[index])
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents a segment of a `VerboseJson` transcription.
+///
+/// - `id`: The index of the segment.
+/// - `seek`: The offset of the audio window the segment was decoded from, in frames.
+/// - `start`: The start of the segment, in seconds.
+/// - `end`: The end of the segment, in seconds.
+/// - `text`: The transcribed text of the segment.
+/// - `tokens`: The ids of the tokens of the text.
+/// - `temperature`: The temperature the segment was decoded with.
+/// - `avg_logprob`: The average log probability of the tokens. Below -1, the segment is likely wrong.
+/// - `compression_ratio`: The gzip compression ratio of the text. Above 2.4, the text is likely repetitive.
+/// - `no_speech_prob`: The probability that the segment contains no speech.
+pub struct TranscriptionSegment {
+    #[serde(default)]
+    pub id: u32,
+    #[serde(default)]
+    pub seek: u32,
+    pub start: f64,
+    pub end: f64,
+    pub text: String,
+    #[serde(default)]
+    pub tokens: Vec<u32>,
+    #[serde(default)]
+    pub temperature: f64,
+    #[serde(default)]
+    pub avg_logprob: f64,
+    #[serde(default)]
+    pub compression_ratio: f64,
+    #[serde(default)]
+    pub no_speech_prob: f64,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+/// Represents a word of a `VerboseJson` transcription.
+///
+/// - `word`: The transcribed word.
+/// - `start`: The time the word starts, in seconds.
+/// - `end`: The time the word ends, in seconds.
+pub struct TranscriptionWord {
+    pub word: String,
+    pub start: f64,
+    pub end: f64,
 }
 
 /// Represents a request to the OpenAI chat completion API.
@@ -246,6 +1134,18 @@ pub struct SpeechToTextResponse {
 /// - `stream`: Whether to stream the response or return it all at once.
 /// - `stop`: A list of strings to stop the generation when encountered.
 /// - `seed`: The seed value to use for the random number generator.
+/// - `tools`: The tools the model may call.
+/// - `tool_choice`: Controls which (if any) tool is called by the model.
+/// - `response_format`: The format the model must output, such as a JSON object.
+/// - `n`: The number of choices to generate.
+/// - `presence_penalty`: Penalizes tokens that already appeared, between -2.0 and 2.0.
+/// - `frequency_penalty`: Penalizes tokens by how often they appeared, between -2.0 and 2.0.
+/// - `logit_bias`: Biases added to the logits of token ids, between -100 and 100.
+/// - `logprobs`: Whether to return the log probabilities of the generated tokens.
+/// - `top_logprobs`: The number of most likely alternatives to return for each token.
+/// - `user`: An id for the end user, to help detect abuse.
+/// - `parallel_tool_calls`: Whether the model may request several tool calls at once.
+/// - `service_tier`: The service tier to process the request with.
 #[derive(Debug, Clone)]
 pub struct ChatCompletionRequest {
     pub model: String,
@@ -256,6 +1156,18 @@ pub struct ChatCompletionRequest {
     pub stream: Option<bool>,
     pub stop: Option<Vec<String>>,
     pub seed: Option<u64>,
+    pub tools: Option<Vec<Tool>>,
+    pub tool_choice: Option<ToolChoice>,
+    pub response_format: Option<ResponseFormat>,
+    pub n: Option<u32>,
+    pub presence_penalty: Option<f64>,
+    pub frequency_penalty: Option<f64>,
+    pub logit_bias: Option<HashMap<u32, f64>>,
+    pub logprobs: Option<bool>,
+    pub top_logprobs: Option<u32>,
+    pub user: Option<String>,
+    pub parallel_tool_calls: Option<bool>,
+    pub service_tier: Option<ServiceTier>,
 }
 
 /// Represents a request to the OpenAI chat completion API.
@@ -270,9 +1182,23 @@ pub struct ChatCompletionRequest {
 /// - `stream`: Whether to stream the response or return it all at once.
 /// - `stop`: A list of strings to stop the generation when encountered.
 /// - `seed`: The seed value to use for the random number generator.
+/// - `tools`: The tools the model may call.
+/// - `tool_choice`: Controls which (if any) tool is called by the model.
+/// - `response_format`: The format the model must output, such as a JSON object.
+/// - `n`: The number of choices to generate.
+/// - `presence_penalty`: Penalizes tokens that already appeared, between -2.0 and 2.0.
+/// - `frequency_penalty`: Penalizes tokens by how often they appeared, between -2.0 and 2.0.
+/// - `logit_bias`: Biases added to the logits of token ids, between -100 and 100.
+/// - `logprobs`: Whether to return the log probabilities of the generated tokens.
+/// - `top_logprobs`: The number of most likely alternatives to return for each token.
+/// - `user`: An id for the end user, to help detect abuse.
+/// - `parallel_tool_calls`: Whether the model may request several tool calls at once.
+/// - `service_tier`: The service tier to process the request with.
 impl ChatCompletionRequest {
     /// Creates a new `ChatCompletionRequest` instance with the given model and messages.
     ///
+    /// Parameters left unset are not sent, so the API applies its own defaults.
+    ///
     /// # Arguments
     ///
     /// * `model` - The language model to use for the chat completion.
@@ -281,12 +1207,24 @@ impl ChatCompletionRequest {
         ChatCompletionRequest {
             model: model.to_string(),
             messages,
-            temperature: Some(1.0),
-            max_tokens: Some(1024),
-            top_p: Some(1.0),
-            stream: Some(false),
+            temperature: None,
+            max_tokens: None,
+            top_p: None,
+            stream: None,
             stop: None,
             seed: None,
+            tools: None,
+            tool_choice: None,
+            response_format: None,
+            n: None,
+            presence_penalty: None,
+            frequency_penalty: None,
+            logit_bias: None,
+            logprobs: None,
+            top_logprobs: None,
+            user: None,
+            parallel_tool_calls: None,
+            service_tier: None,
         }
     }
 
@@ -327,6 +1265,9 @@ impl ChatCompletionRequest {
 
     /// Sets whether to stream the response or return it all at once.
     ///
+    /// `chat_completion` always requests a complete response and `chat_completion_stream`
+    /// always requests a streamed one, regardless of this flag.
+    ///
     /// # Arguments
     ///
     /// * `stream` - Whether to stream the response or not.
@@ -354,6 +1295,188 @@ impl ChatCompletionRequest {
         self.seed = Some(seed);
         self
     }
+
+    /// Sets the tools the model may call.
+    ///
+    /// # Arguments
+    ///
+    /// * `tools` - The list of tools.
+    pub fn tools(mut self, tools: Vec<Tool>) -> Self {
+        self.tools = Some(tools);
+        self
+    }
+
+    /// Sets which (if any) tool is called by the model.
+    ///
+    /// # Arguments
+    ///
+    /// * `tool_choice` - The tool choice to use.
+    pub fn tool_choice(mut self, tool_choice: ToolChoice) -> Self {
+        self.tool_choice = Some(tool_choice);
+        self
+    }
+
+    /// Sets the format the model must output.
+    ///
+    /// # Arguments
+    ///
+    /// * `response_format` - The response format to use.
+    pub fn response_format(mut self, response_format: ResponseFormat) -> Self {
+        self.response_format = Some(response_format);
+        self
+    }
+
+    /// Sets the number of choices to generate.
+    ///
+    /// # Arguments
+    ///
+    /// * `n` - The number of choices.
+    pub fn n(mut self, n: u32) -> Self {
+        self.n = Some(n);
+        self
+    }
+
+    /// Sets the presence penalty, which makes the model more likely to talk about new topics.
+    ///
+    /// # Arguments
+    ///
+    /// * `presence_penalty` - The penalty, between -2.0 and 2.0.
+    pub fn presence_penalty(mut self, presence_penalty: f64) -> Self {
+        self.presence_penalty = Some(presence_penalty);
+        self
+    }
+
+    /// Sets the frequency penalty, which makes the model less likely to repeat itself.
+    ///
+    /// # Arguments
+    ///
+    /// * `frequency_penalty` - The penalty, between -2.0 and 2.0.
+    pub fn frequency_penalty(mut self, frequency_penalty: f64) -> Self {
+        self.frequency_penalty = Some(frequency_penalty);
+        self
+    }
+
+    /// Sets the biases added to the logits of the given token ids.
+    ///
+    /// # Arguments
+    ///
+    /// * `logit_bias` - The bias of each token id, between -100 and 100.
+    pub fn logit_bias(mut self, logit_bias: HashMap<u32, f64>) -> Self {
+        self.logit_bias = Some(logit_bias);
+        self
+    }
+
+    /// Sets whether to return the log probabilities of the generated tokens.
+    ///
+    /// # Arguments
+    ///
+    /// * `logprobs` - Whether to return log probabilities.
+    pub fn logprobs(mut self, logprobs: bool) -> Self {
+        self.logprobs = Some(logprobs);
+        self
+    }
+
+    /// Sets the number of most likely alternatives returned for each token, and enables `logprobs`.
+    ///
+    /// # Arguments
+    ///
+    /// * `top_logprobs` - The number of alternatives, between 0 and 20.
+    pub fn top_logprobs(mut self, top_logprobs: u32) -> Self {
+        self.logprobs = Some(true);
+        self.top_logprobs = Some(top_logprobs);
+        self
+    }
+
+    /// Sets an id for the end user, to help detect abuse.
+    ///
+    /// # Arguments
+    ///
+    /// * `user` - The id of the end user.
+    pub fn user(mut self, user: &str) -> Self {
+        self.user = Some(user.to_string());
+        self
+    }
+
+    /// Sets whether the model may request several tool calls at once.
+    ///
+    /// # Arguments
+    ///
+    /// * `parallel_tool_calls` - Whether to allow parallel tool calls.
+    pub fn parallel_tool_calls(mut self, parallel_tool_calls: bool) -> Self {
+        self.parallel_tool_calls = Some(parallel_tool_calls);
+        self
+    }
+
+    /// Sets the service tier to process the request with.
+    ///
+    /// # Arguments
+    ///
+    /// * `service_tier` - The service tier to use.
+    pub fn service_tier(mut self, service_tier: ServiceTier) -> Self {
+        self.service_tier = Some(service_tier);
+        self
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+/// Represents the service tier a request is processed with.
+///
+/// - `OnDemand`: The default tier, with the rate limits of the account.
+/// - `Flex`: Higher rate limits, but requests may fail fast when capacity is short.
+/// - `Auto`: Uses `OnDemand` and falls back to `Flex` when rate limited.
+pub enum ServiceTier {
+    OnDemand,
+    Flex,
+    Auto,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+/// Represents the format the model must output.
+///
+/// - `Text`: Plain text, the default.
+/// - `JsonObject`: JSON mode; the output is guaranteed to be a valid JSON object.
+/// - `JsonSchema`: Structured outputs; the output must match the given JSON Schema.
+pub enum ResponseFormat {
+    Text,
+    JsonObject,
+    JsonSchema { json_schema: JsonSchema },
+}
+
+impl ResponseFormat {
+    /// Creates a `JsonSchema` response format with the given name and schema.
+    ///
+    /// # Arguments
+    ///
+    /// * `name` - The name of the schema.
+    /// * `schema` - The JSON Schema the output must match.
+    pub fn json_schema(name: &str, schema: Value) -> Self {
+        ResponseFormat::JsonSchema {
+            json_schema: JsonSchema {
+                name: name.to_string(),
+                description: None,
+                schema,
+                strict: None,
+            },
+        }
+    }
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents a JSON Schema used for structured outputs.
+///
+/// - `name`: The name of the schema.
+/// - `description`: An optional description of what the output represents.
+/// - `schema`: The JSON Schema the output must match.
+/// - `strict`: Whether the schema must be followed exactly.
+pub struct JsonSchema {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub schema: Value,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub strict: Option<bool>,
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -367,13 +1490,13 @@ pub struct TextToSpeechRequest {
 impl TextToSpeechRequest {
     pub fn new(
         model: Option<String>,
-        input: &String,
+        input: &str,
         voice: Option<String>,
         speed: Option<f64>,
     ) -> Self {
         Self {
-            model: model.unwrap_or("playai-tts".to_string()),
-            input: input.clone(),
+            model: model.unwrap_or(models::PLAYAI_TTS.to_string()),
+            input: input.to_string(),
             voice: voice.unwrap_or("Chip-PlayAI".to_string()),
             speed,
         }
@@ -391,4 +1514,6 @@ impl TextToSpeechRequest {
 #[derive(Debug, Clone, Deserialize)]
 pub struct TextToSpeechResponse {
     pub audio_data: Vec<u8>,
+    #[serde(skip)]
+    pub rate_limit: Option<RateLimitInfo>,
 }
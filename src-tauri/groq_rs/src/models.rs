@@ -0,0 +1,602 @@
+//! Model records returned by the `/models` endpoint, and a registry of model capabilities.
+//!
+//! The constants of this module name the models the crate knows about, so callers do not have
+//! to scatter model ids through their code.
+
+use crate::{
+    ApiErrorDetails, ChatCompletionRequest, ContextUsage, GroqError, SpeechToTextRequest,
+    TextToSpeechRequest, TokenEstimator, MAX_IMAGES_PER_REQUEST,
+};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::HashMap;
+
+/// Llama 3.3 70B, a general purpose chat model with tool use.
+pub const LLAMA_3_3_70B_VERSATILE: &str = "llama-3.3-70b-versatile";
+/// Llama 3.1 8B, a fast and inexpensive chat model with tool use.
+pub const LLAMA_3_1_8B_INSTANT: &str = "llama-3.1-8b-instant";
+/// Llama 4 Scout, a chat model accepting images.
+pub const LLAMA_4_SCOUT: &str = "meta-llama/llama-4-scout-17b-16e-instruct";
+/// Llama 4 Maverick, a chat model accepting images.
+pub const LLAMA_4_MAVERICK: &str = "meta-llama/llama-4-maverick-17b-128e-instruct";
+/// Gemma 2 9B, a small chat model.
+pub const GEMMA2_9B_IT: &str = "gemma2-9b-it";
+/// Whisper large v3, the most accurate speech-to-text model, and the only one that translates.
+pub const WHISPER_LARGE_V3: &str = "whisper-large-v3";
+/// Whisper large v3 turbo, a faster speech-to-text model.
+pub const WHISPER_LARGE_V3_TURBO: &str = "whisper-large-v3-turbo";
+/// PlayAI TTS, the English text-to-speech model.
+pub const PLAYAI_TTS: &str = "playai-tts";
+/// PlayAI TTS Arabic, the Arabic text-to-speech model.
+pub const PLAYAI_TTS_ARABIC: &str = "playai-tts-arabic";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents a model available to the account, as returned by the `/models` endpoint.
+///
+/// - `id`: The id of the model, used as `model` in requests.
+/// - `object`: The object type, always `model`.
+/// - `created`: The Unix timestamp of when the model was created.
+/// - `owned_by`: The organization that published the model.
+/// - `active`: Whether the model can currently be used.
+/// - `context_window`: The maximum number of prompt and completion tokens.
+/// - `max_completion_tokens`: The maximum number of tokens the model can generate, when limited.
+/// - `public_apps`: Apps the model is restricted to, if any.
+pub struct Model {
+    pub id: String,
+    pub object: String,
+    pub created: u64,
+    pub owned_by: String,
+    #[serde(default = "default_active")]
+    pub active: bool,
+    #[serde(default)]
+    pub context_window: Option<u32>,
+    #[serde(default)]
+    pub max_completion_tokens: Option<u32>,
+    #[serde(default)]
+    pub public_apps: Option<Value>,
+}
+
+fn default_active() -> bool {
+    true
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+/// Represents the response of the `/models` endpoint.
+///
+/// - `object`: The object type, always `list`.
+/// - `data`: The models available to the account.
+pub struct ModelList {
+    pub object: String,
+    pub data: Vec<Model>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+/// Represents a kind of request a model can serve.
+///
+/// - `Chat`: Chat completions.
+/// - `SpeechToText`: Transcriptions and translations.
+/// - `TextToSpeech`: Speech synthesis.
+/// - `Vision`: Chat completions with images.
+/// - `ToolUse`: Chat completions with tools.
+pub enum Capability {
+    Chat,
+    SpeechToText,
+    TextToSpeech,
+    Vision,
+    ToolUse,
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+/// Represents what a model can do.
+///
+/// - `capabilities`: The kinds of requests the model serves.
+/// - `context_window`: The maximum number of prompt and completion tokens, if known.
+/// - `max_completion_tokens`: The maximum value of `max_tokens`, if known.
+pub struct ModelCapabilities {
+    pub capabilities: Vec<Capability>,
+    pub context_window: Option<u32>,
+    pub max_completion_tokens: Option<u32>,
+}
+
+impl ModelCapabilities {
+    /// Creates the capabilities of a model serving the given kinds of requests.
+    ///
+    /// # Arguments
+    ///
+    /// * `capabilities` - The kinds of requests the model serves.
+    pub fn new(capabilities: &[Capability]) -> Self {
+        Self {
+            capabilities: capabilities.to_vec(),
+            ..Self::default()
+        }
+    }
+
+    /// Sets the maximum number of prompt and completion tokens.
+    pub fn context_window(mut self, context_window: u32) -> Self {
+        self.context_window = Some(context_window);
+        self
+    }
+
+    /// Sets the maximum value of `max_tokens`.
+    pub fn max_completion_tokens(mut self, max_completion_tokens: u32) -> Self {
+        self.max_completion_tokens = Some(max_completion_tokens);
+        self
+    }
+
+    /// Returns whether the model serves the given kind of request.
+    pub fn supports(&self, capability: Capability) -> bool {
+        self.capabilities.contains(&capability)
+    }
+}
+
+/// A registry of model capabilities, used to check requests before sending them.
+///
+/// `ModelRegistry::default()` knows the production models of the Groq API, and which models
+/// replaced the decommissioned ones. Models missing from the registry are never rejected, so a
+/// registry that is out of date does not block new models.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::models::{self, Capability, ModelCapabilities, ModelRegistry};
+///
+/// let registry = ModelRegistry::default().model(
+///     "my-fine-tune",
+///     ModelCapabilities::new(&[Capability::Chat]).context_window(8192),
+/// );
+/// assert!(registry.supports(models::WHISPER_LARGE_V3, Capability::SpeechToText));
+/// assert_eq!(
+///     registry.replacement("llama3-70b-8192"),
+///     Some(models::LLAMA_3_3_70B_VERSATILE)
+/// );
+///```
+#[derive(Debug, Clone)]
+pub struct ModelRegistry {
+    models: HashMap<String, ModelCapabilities>,
+    replacements: HashMap<String, String>,
+}
+
+impl Default for ModelRegistry {
+    fn default() -> Self {
+        use Capability::*;
+        let chat = |context_window, max_completion_tokens, capabilities: &[Capability]| {
+            ModelCapabilities::new(capabilities)
+                .context_window(context_window)
+                .max_completion_tokens(max_completion_tokens)
+        };
+        Self::new()
+            .model(
+                LLAMA_3_3_70B_VERSATILE,
+                chat(131_072, 32_768, &[Chat, ToolUse]),
+            )
+            .model(
+                LLAMA_3_1_8B_INSTANT,
+                chat(131_072, 131_072, &[Chat, ToolUse]),
+            )
+            .model(
+                LLAMA_4_SCOUT,
+                chat(131_072, 8_192, &[Chat, Vision, ToolUse]),
+            )
+            .model(
+                LLAMA_4_MAVERICK,
+                chat(131_072, 8_192, &[Chat, Vision, ToolUse]),
+            )
+            .model(GEMMA2_9B_IT, chat(8_192, 8_192, &[Chat, ToolUse]))
+            .model(WHISPER_LARGE_V3, ModelCapabilities::new(&[SpeechToText]))
+            .model(
+                WHISPER_LARGE_V3_TURBO,
+                ModelCapabilities::new(&[SpeechToText]),
+            )
+            .model(
+                PLAYAI_TTS,
+                ModelCapabilities::new(&[TextToSpeech]).context_window(10_000),
+            )
+            .model(
+                PLAYAI_TTS_ARABIC,
+                ModelCapabilities::new(&[TextToSpeech]).context_window(10_000),
+            )
+            .replaced("llama3-70b-8192", LLAMA_3_3_70B_VERSATILE)
+            .replaced("llama3-8b-8192", LLAMA_3_1_8B_INSTANT)
+            .replaced("llama-3.1-70b-versatile", LLAMA_3_3_70B_VERSATILE)
+            .replaced("mixtral-8x7b-32768", LLAMA_3_3_70B_VERSATILE)
+            .replaced("gemma-7b-it", GEMMA2_9B_IT)
+            .replaced("llama-3.2-11b-vision-preview", LLAMA_4_SCOUT)
+            .replaced("llama-3.2-90b-vision-preview", LLAMA_4_MAVERICK)
+            .replaced("distil-whisper-large-v3-en", WHISPER_LARGE_V3_TURBO)
+    }
+}
+
+impl ModelRegistry {
+    /// Creates an empty registry, which accepts every request.
+    pub fn new() -> Self {
+        Self {
+            models: HashMap::new(),
+            replacements: HashMap::new(),
+        }
+    }
+
+    /// Sets the capabilities of a model.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the model.
+    /// * `capabilities` - What the model can do.
+    pub fn model(mut self, id: &str, capabilities: ModelCapabilities) -> Self {
+        self.replacements.remove(id);
+        self.models.insert(id.to_string(), capabilities);
+        self
+    }
+
+    /// Marks a model as decommissioned, so requests to it are rejected with a pointer to its successor.
+    ///
+    /// # Arguments
+    ///
+    /// * `id` - The id of the decommissioned model.
+    /// * `replacement` - The id of the model to use instead.
+    pub fn replaced(mut self, id: &str, replacement: &str) -> Self {
+        self.models.remove(id);
+        self.replacements
+            .insert(id.to_string(), replacement.to_string());
+        self
+    }
+
+    /// Updates the context limits of known models from the records returned by `list_models`.
+    ///
+    /// # Arguments
+    ///
+    /// * `models` - The models available to the account.
+    pub fn update(&mut self, models: &[Model]) {
+        for model in models.iter().filter(|m| m.active) {
+            if let Some(capabilities) = self.models.get_mut(&model.id) {
+                capabilities.context_window = model.context_window.or(capabilities.context_window);
+                capabilities.max_completion_tokens = model
+                    .max_completion_tokens
+                    .or(capabilities.max_completion_tokens);
+            }
+        }
+    }
+
+    /// Returns the capabilities of a model, if it is known.
+    pub fn get(&self, id: &str) -> Option<&ModelCapabilities> {
+        self.models.get(id)
+    }
+
+    /// Returns whether a known model serves the given kind of request.
+    pub fn supports(&self, id: &str, capability: Capability) -> bool {
+        self.get(id).is_some_and(|m| m.supports(capability))
+    }
+
+    /// Returns the model that replaced a decommissioned one.
+    pub fn replacement(&self, id: &str) -> Option<&str> {
+        self.replacements.get(id).map(String::as_str)
+    }
+
+    /// Checks a chat completion request against the capabilities of its model.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidRequest` if the model is decommissioned, does not serve chat
+    /// completions, tools or images, if there are more than `MAX_IMAGES_PER_REQUEST` images, or if
+    /// `max_tokens` is above what the model can generate. Returns
+    /// `GroqError::ContextLengthExceeded` if the estimated prompt and `max_tokens` do not fit in
+    /// the context window of the model.
+    pub fn check_chat_completion(&self, request: &ChatCompletionRequest) -> Result<(), GroqError> {
+        let Some(model) = self.lookup(&request.model)? else {
+            return Ok(());
+        };
+        require(&request.model, model, Capability::Chat)?;
+        if request
+            .tools
+            .as_ref()
+            .is_some_and(|tools| !tools.is_empty())
+        {
+            require(&request.model, model, Capability::ToolUse)?;
+        }
+        let images: usize = request
+            .messages
+            .iter()
+            .map(|m| m.content.images().len())
+            .sum();
+        if images > 0 {
+            require(&request.model, model, Capability::Vision)?;
+        }
+        if images > MAX_IMAGES_PER_REQUEST {
+            return Err(invalid_request(
+                format!(
+                    "the request has {} images but at most {} are allowed",
+                    images, MAX_IMAGES_PER_REQUEST
+                ),
+                "messages",
+            ));
+        }
+        if let (Some(max_tokens), Some(limit)) = (request.max_tokens, model.max_completion_tokens) {
+            if max_tokens > limit {
+                return Err(invalid_request(
+                    format!(
+                        "`max_tokens` is {} but `{}` generates at most {} tokens",
+                        max_tokens, request.model, limit
+                    ),
+                    "max_tokens",
+                ));
+            }
+        }
+        if let Some(usage) = self.context_usage(request) {
+            if !usage.fits() {
+                return Err(context_length_exceeded(&request.model, &usage));
+            }
+        }
+        Ok(())
+    }
+
+    /// Estimates how much of the context window of its model a chat completion request takes.
+    ///
+    /// Returns `None` if the context window of the model is not known.
+    pub fn context_usage(&self, request: &ChatCompletionRequest) -> Option<ContextUsage> {
+        let context_window = self.get(&request.model)?.context_window?;
+        Some(ContextUsage {
+            prompt_tokens: TokenEstimator::for_model(&request.model).count_request(request),
+            max_tokens: request.max_tokens,
+            context_window,
+        })
+    }
+
+    /// Lowers `max_tokens` to what the model can still generate after the estimated prompt.
+    ///
+    /// `max_tokens` is left unset if it was not set. Returns the context usage of the clamped
+    /// request, or `None` if the context window of the model is not known.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::ContextLengthExceeded` if the prompt alone fills the context window.
+    pub fn clamp_max_tokens(
+        &self,
+        request: &mut ChatCompletionRequest,
+    ) -> Result<Option<ContextUsage>, GroqError> {
+        let Some(mut usage) = self.context_usage(request) else {
+            return Ok(None);
+        };
+        let available = usage.context_window.saturating_sub(usage.prompt_tokens);
+        if available == 0 {
+            return Err(context_length_exceeded(&request.model, &usage));
+        }
+        let limit = self
+            .get(&request.model)
+            .and_then(|model| model.max_completion_tokens)
+            .map_or(available, |limit| limit.min(available));
+        if let Some(max_tokens) = request.max_tokens.filter(|&max_tokens| max_tokens > limit) {
+            log::debug!(
+                "clamped max_tokens of {} from {} to {}",
+                request.model,
+                max_tokens,
+                limit
+            );
+            request.max_tokens = Some(limit);
+            usage.max_tokens = Some(limit);
+        }
+        Ok(Some(usage))
+    }
+
+    /// Checks a speech-to-text request against the capabilities of its model.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidRequest` if the model is decommissioned or does not transcribe audio.
+    pub fn check_speech_to_text(&self, request: &SpeechToTextRequest) -> Result<(), GroqError> {
+        let Some(id) = request.model.as_deref() else {
+            return Ok(());
+        };
+        match self.lookup(id)? {
+            Some(model) => require(id, model, Capability::SpeechToText),
+            None => Ok(()),
+        }
+    }
+
+    /// Checks a text-to-speech request against the capabilities of its model.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidRequest` if the model is decommissioned, does not synthesize
+    /// speech, or if the input is longer than the model accepts.
+    pub fn check_text_to_speech(&self, request: &TextToSpeechRequest) -> Result<(), GroqError> {
+        let Some(model) = self.lookup(&request.model)? else {
+            return Ok(());
+        };
+        require(&request.model, model, Capability::TextToSpeech)?;
+        if let Some(limit) = model.context_window {
+            if request.input.chars().count() > limit as usize {
+                return Err(invalid_request(
+                    format!(
+                        "`input` is longer than the {} characters `{}` accepts",
+                        limit, request.model
+                    ),
+                    "input",
+                ));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the capabilities of a model, or an error if it was decommissioned.
+    fn lookup(&self, id: &str) -> Result<Option<&ModelCapabilities>, GroqError> {
+        if let Some(replacement) = self.replacement(id) {
+            return Err(GroqError::InvalidRequest(Box::new(ApiErrorDetails {
+                message: format!(
+                    "The model `{}` has been decommissioned, use `{}` instead",
+                    id, replacement
+                ),
+                type_: Some("invalid_request_error".to_string()),
+                code: Some("model_decommissioned".to_string()),
+                param: Some("model".to_string()),
+                ..ApiErrorDetails::default()
+            })));
+        }
+        Ok(self.get(id))
+    }
+}
+
+/// Fails unless `model` serves the given kind of request.
+fn require(id: &str, model: &ModelCapabilities, capability: Capability) -> Result<(), GroqError> {
+    if model.supports(capability) {
+        return Ok(());
+    }
+    Err(invalid_request(
+        format!("The model `{}` does not support {:?}", id, capability),
+        "model",
+    ))
+}
+
+/// Builds the error for a request that does not fit in the context window of its model.
+fn context_length_exceeded(id: &str, usage: &ContextUsage) -> GroqError {
+    GroqError::ContextLengthExceeded(Box::new(ApiErrorDetails {
+        message: format!(
+            "The request needs about {} prompt tokens and {} completion tokens, but `{}` has a context window of {} tokens",
+            usage.prompt_tokens,
+            usage.max_tokens.unwrap_or(0),
+            id,
+            usage.context_window
+        ),
+        type_: Some("invalid_request_error".to_string()),
+        code: Some("context_length_exceeded".to_string()),
+        param: Some("messages".to_string()),
+        ..ApiErrorDetails::default()
+    }))
+}
+
+/// Builds the error for a request rejected before being sent.
+fn invalid_request(message: String, param: &str) -> GroqError {
+    GroqError::InvalidRequest(Box::new(ApiErrorDetails {
+        message,
+        type_: Some("invalid_request_error".to_string()),
+        code: Some("model_not_supported".to_string()),
+        param: Some(param.to_string()),
+        ..ApiErrorDetails::default()
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatCompletionMessage, ChatCompletionRoles, ContentPart, ImageUrl, Tool};
+    use serde_json::json;
+
+    #[test]
+    fn test_model_list() {
+        let list: ModelList = serde_json::from_value(json!({
+            "object": "list",
+            "data": [{
+                "id": "llama-3.3-70b-versatile",
+                "object": "model",
+                "created": 1733447754,
+                "owned_by": "Meta",
+                "active": true,
+                "context_window": 131072,
+                "public_apps": null,
+                "max_completion_tokens": 32768
+            }, {
+                "id": "whisper-large-v3",
+                "object": "model",
+                "created": 1693721698,
+                "owned_by": "OpenAI"
+            }]
+        }))
+        .unwrap();
+        assert_eq!(list.data[0].context_window, Some(131_072));
+        assert!(list.data[1].active);
+
+        let mut registry = ModelRegistry::default();
+        let mut model = list.data[0].clone();
+        model.max_completion_tokens = Some(1_000);
+        registry.update(&[model]);
+        assert_eq!(
+            registry
+                .get(LLAMA_3_3_70B_VERSATILE)
+                .unwrap()
+                .max_completion_tokens,
+            Some(1_000)
+        );
+    }
+
+    #[test]
+    fn test_check_requests() {
+        let registry = ModelRegistry::default();
+        let message = ChatCompletionMessage::new(ChatCompletionRoles::User, "Hello");
+        let request = ChatCompletionRequest::new(LLAMA_3_3_70B_VERSATILE, vec![message.clone()]);
+        assert!(registry.check_chat_completion(&request).is_ok());
+
+        let request = request.max_tokens(100_000);
+        let error = registry.check_chat_completion(&request).unwrap_err();
+        assert_eq!(
+            error.details().unwrap().param.as_deref(),
+            Some("max_tokens")
+        );
+
+        let request = ChatCompletionRequest::new(WHISPER_LARGE_V3, vec![message.clone()]);
+        assert!(matches!(
+            registry.check_chat_completion(&request),
+            Err(GroqError::InvalidRequest(_))
+        ));
+
+        let request = ChatCompletionRequest::new("llama3-70b-8192", vec![message.clone()]);
+        let error = registry.check_chat_completion(&request).unwrap_err();
+        assert_eq!(
+            error.details().unwrap().code.as_deref(),
+            Some("model_decommissioned")
+        );
+
+        // Unknown models are left for the API to judge.
+        let request = ChatCompletionRequest::new("my-fine-tune", vec![message])
+            .tools(vec![Tool::function("noop", "Does nothing", json!({}))]);
+        assert!(registry.check_chat_completion(&request).is_ok());
+
+        let image = || ContentPart::image(ImageUrl::new("https://example.com/a.png"));
+        let request = ChatCompletionRequest::new(
+            LLAMA_3_3_70B_VERSATILE,
+            vec![ChatCompletionMessage::parts(
+                ChatCompletionRoles::User,
+                vec![image()],
+            )],
+        );
+        assert!(registry.check_chat_completion(&request).is_err());
+        let request = ChatCompletionRequest::new(
+            LLAMA_4_SCOUT,
+            vec![ChatCompletionMessage::parts(
+                ChatCompletionRoles::User,
+                vec![image(); 6],
+            )],
+        );
+        assert!(registry.check_chat_completion(&request).is_err());
+
+        let request = SpeechToTextRequest::new(vec![]).model(PLAYAI_TTS);
+        assert!(registry.check_speech_to_text(&request).is_err());
+        let request = TextToSpeechRequest::new(None, &"a".repeat(10_001), None, None);
+        assert!(registry.check_text_to_speech(&request).is_err());
+    }
+
+    #[test]
+    fn test_context_window() {
+        let registry = ModelRegistry::default();
+        let long = "word ".repeat(6_000);
+        let message = ChatCompletionMessage::new(ChatCompletionRoles::User, &long);
+        let request = ChatCompletionRequest::new(GEMMA2_9B_IT, vec![message.clone()]);
+        let usage = registry.context_usage(&request).unwrap();
+        assert_eq!(usage.prompt_tokens, 6_010);
+        assert_eq!(usage.context_window, 8_192);
+        assert!(registry.check_chat_completion(&request).is_ok());
+
+        let mut request = request.max_tokens(4_000);
+        assert!(matches!(
+            registry.check_chat_completion(&request),
+            Err(GroqError::ContextLengthExceeded(_))
+        ));
+        let usage = registry.clamp_max_tokens(&mut request).unwrap().unwrap();
+        assert_eq!(request.max_tokens, Some(8_192 - 6_010));
+        assert_eq!(usage.remaining(), 0);
+        assert!(registry.check_chat_completion(&request).is_ok());
+
+        let mut request =
+            ChatCompletionRequest::new(GEMMA2_9B_IT, vec![message.clone(), message.clone()]);
+        assert!(registry.clamp_max_tokens(&mut request).is_err());
+        let mut request = ChatCompletionRequest::new("my-fine-tune", vec![message]);
+        assert_eq!(registry.clamp_max_tokens(&mut request).unwrap(), None);
+    }
+}
@@ -0,0 +1,357 @@
+//! Traits over the chat, speech-to-text and text-to-speech APIs, so Groq and other
+//! OpenAI-compatible backends can be used interchangeably.
+
+use crate::chunking;
+use crate::{
+    AsyncGroqClient, ChatCompletionRequest, ChatCompletionResponse, ChatCompletionStream,
+    ChunkingOptions, GroqError, RequestOptions, RetryPolicy, SpeechToTextRequest,
+    SpeechToTextResponse, TextToSpeechRequest, TextToSpeechResponse,
+};
+use futures_util::future::{BoxFuture, FutureExt};
+use reqwest::header::{HeaderName, HeaderValue, AUTHORIZATION};
+use std::fmt;
+use std::sync::Arc;
+
+/// The header carrying the key with `AuthScheme::ApiKey`.
+pub const API_KEY_HEADER: &str = "api-key";
+
+/// Represents how requests are authenticated.
+///
+/// - `None`: No credentials are sent, as with most local servers.
+/// - `Bearer`: The key is sent as `Authorization: Bearer <key>`, as with Groq and OpenAI.
+/// - `ApiKey`: The key is sent in the `api-key` header, as with Azure OpenAI.
+#[derive(Clone, PartialEq, Eq)]
+pub enum AuthScheme {
+    None,
+    Bearer(String),
+    ApiKey(String),
+}
+
+impl AuthScheme {
+    /// Returns the header authenticating a request, if any.
+    pub(crate) fn header(&self) -> Option<(HeaderName, String)> {
+        match self {
+            AuthScheme::None => None,
+            AuthScheme::Bearer(key) => Some((AUTHORIZATION, format!("Bearer {}", key))),
+            AuthScheme::ApiKey(key) => Some((HeaderName::from_static(API_KEY_HEADER), key.clone())),
+        }
+    }
+
+    /// Returns whether the credentials are valid header values.
+    pub(crate) fn is_valid(&self) -> bool {
+        self.header()
+            .map_or(true, |(_, value)| HeaderValue::from_str(&value).is_ok())
+    }
+}
+
+impl fmt::Debug for AuthScheme {
+    // Keys are redacted, so clients can be logged safely.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            AuthScheme::None => f.write_str("None"),
+            AuthScheme::Bearer(_) => f.write_str("Bearer(..)"),
+            AuthScheme::ApiKey(_) => f.write_str("ApiKey(..)"),
+        }
+    }
+}
+
+/// A backend answering chat completion requests.
+///
+/// The methods return boxed futures, so providers can be used as `dyn ChatProvider`.
+pub trait ChatProvider: Send + Sync {
+    /// Sends a chat completion request and returns the parsed response.
+    fn chat<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<ChatCompletionResponse, GroqError>>;
+
+    /// Sends a streamed chat completion request.
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<ChatCompletionStream, GroqError>>;
+}
+
+/// A backend transcribing or translating audio.
+pub trait SpeechToTextProvider: Send + Sync {
+    /// Sends a speech-to-text request and returns the parsed response.
+    fn transcribe<'a>(
+        &'a self,
+        request: SpeechToTextRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<SpeechToTextResponse, GroqError>>;
+
+    /// Transcribes audio of any length, for recordings too long or too large for a single request.
+    ///
+    /// WAV files above the limits of `chunking` are split into overlapping chunks, ending in
+    /// silence where possible, which are transcribed concurrently. Their transcriptions are
+    /// stitched back together: timestamps are offset to the whole file, and the segments and
+    /// words heard twice in an overlap are only kept once. Other formats are sent whole, and
+    /// rejected with `GroqError::InvalidInput` if they are too large.
+    ///
+    /// The `VerboseJson` format is always requested, with segment and word timestamps, then the
+    /// transcription is converted to the `response_format` of `request`: `Text` and `Json` keep
+    /// the text, `Srt` and `Vtt` put subtitles built from the segments in `text`, and
+    /// `VerboseJson` keeps the `timestamp_granularities` asked for.
+    fn transcribe_chunked<'a>(
+        &'a self,
+        request: SpeechToTextRequest,
+        chunking: &'a ChunkingOptions,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<SpeechToTextResponse, GroqError>> {
+        chunking::transcribe_chunked(self, request, chunking, options).boxed()
+    }
+}
+
+/// A backend generating speech from text.
+pub trait TextToSpeechProvider: Send + Sync {
+    /// Sends a text-to-speech request and returns the generated audio.
+    fn synthesize<'a>(
+        &'a self,
+        request: TextToSpeechRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<TextToSpeechResponse, GroqError>>;
+}
+
+impl ChatProvider for AsyncGroqClient {
+    fn chat<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<ChatCompletionResponse, GroqError>> {
+        self.chat_completion_with(request, options).boxed()
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<ChatCompletionStream, GroqError>> {
+        self.chat_completion_stream_with(request, options).boxed()
+    }
+}
+
+impl SpeechToTextProvider for AsyncGroqClient {
+    fn transcribe<'a>(
+        &'a self,
+        request: SpeechToTextRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<SpeechToTextResponse, GroqError>> {
+        self.speech_to_text_with(request, options).boxed()
+    }
+}
+
+impl TextToSpeechProvider for AsyncGroqClient {
+    fn synthesize<'a>(
+        &'a self,
+        request: TextToSpeechRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<TextToSpeechResponse, GroqError>> {
+        self.text_to_speech_with(request, options).boxed()
+    }
+}
+
+/// A client for any server implementing the OpenAI API, such as the llama.cpp or whisper.cpp
+/// servers, vLLM or OpenAI itself.
+///
+/// Requests are encoded and responses decoded as for Groq, but no Groq environment variable or
+/// default applies: the endpoint and the authentication are always explicit. Model aliases map
+/// the models the application asks for, such as `models::WHISPER_LARGE_V3`, to the models the
+/// server knows, so the same requests can be sent to either backend.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::{models, AuthScheme, OpenAiCompatibleClient, SpeechToTextProvider};
+///
+/// let whisper = OpenAiCompatibleClient::new("http://localhost:8080/v1", AuthScheme::None)
+///     .model_alias(models::WHISPER_LARGE_V3, "ggml-large-v3");
+/// let provider: &dyn SpeechToTextProvider = &whisper;
+///```
+pub struct OpenAiCompatibleClient {
+    client: AsyncGroqClient,
+}
+
+impl OpenAiCompatibleClient {
+    /// Creates a client for the OpenAI-compatible API at `endpoint`, such as `http://localhost:8080/v1`.
+    pub fn new(endpoint: &str, auth: AuthScheme) -> Self {
+        let endpoint = endpoint.trim_end_matches('/').to_string();
+        Self::from_client(AsyncGroqClient::new(String::new(), Some(endpoint)).with_auth(auth))
+    }
+
+    /// Wraps a client configured with `AsyncGroqClient::builder()`, for timeouts, a proxy,
+    /// hooks or a cache. Its endpoint and `AuthScheme` should be set explicitly.
+    pub fn from_client(client: AsyncGroqClient) -> Self {
+        Self { client }
+    }
+
+    /// Sends requests for the model `alias` to `model` instead.
+    pub fn model_alias(mut self, alias: &str, model: &str) -> Self {
+        self.client = self.client.with_model_alias(alias, model);
+        self
+    }
+
+    /// Sets the policy used to retry failed requests.
+    pub fn with_retry_policy(mut self, retry_policy: RetryPolicy) -> Self {
+        self.client = self.client.with_retry_policy(retry_policy);
+        self
+    }
+
+    /// Returns the underlying client, for the endpoints not covered by the provider traits.
+    pub fn client(&self) -> &AsyncGroqClient {
+        &self.client
+    }
+}
+
+impl ChatProvider for OpenAiCompatibleClient {
+    fn chat<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<ChatCompletionResponse, GroqError>> {
+        self.client.chat(request, options)
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<ChatCompletionStream, GroqError>> {
+        self.client.chat_stream(request, options)
+    }
+}
+
+impl SpeechToTextProvider for OpenAiCompatibleClient {
+    fn transcribe<'a>(
+        &'a self,
+        request: SpeechToTextRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<SpeechToTextResponse, GroqError>> {
+        self.client.transcribe(request, options)
+    }
+}
+
+impl TextToSpeechProvider for OpenAiCompatibleClient {
+    fn synthesize<'a>(
+        &'a self,
+        request: TextToSpeechRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<TextToSpeechResponse, GroqError>> {
+        self.client.synthesize(request, options)
+    }
+}
+
+impl<P: ChatProvider + ?Sized> ChatProvider for Arc<P> {
+    fn chat<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<ChatCompletionResponse, GroqError>> {
+        (**self).chat(request, options)
+    }
+
+    fn chat_stream<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<ChatCompletionStream, GroqError>> {
+        (**self).chat_stream(request, options)
+    }
+}
+
+impl<P: SpeechToTextProvider + ?Sized> SpeechToTextProvider for Arc<P> {
+    fn transcribe<'a>(
+        &'a self,
+        request: SpeechToTextRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<SpeechToTextResponse, GroqError>> {
+        (**self).transcribe(request, options)
+    }
+
+    fn transcribe_chunked<'a>(
+        &'a self,
+        request: SpeechToTextRequest,
+        chunking: &'a ChunkingOptions,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<SpeechToTextResponse, GroqError>> {
+        (**self).transcribe_chunked(request, chunking, options)
+    }
+}
+
+impl<P: TextToSpeechProvider + ?Sized> TextToSpeechProvider for Arc<P> {
+    fn synthesize<'a>(
+        &'a self,
+        request: TextToSpeechRequest,
+        options: &'a RequestOptions,
+    ) -> BoxFuture<'a, Result<TextToSpeechResponse, GroqError>> {
+        (**self).synthesize(request, options)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::{silent_wav, MockGroqServer, MockResponse, MOCK_TEXT};
+    use crate::{models, ChatCompletionMessage, ChatCompletionRoles};
+    use std::time::Duration;
+
+    async fn transcribe(provider: &dyn SpeechToTextProvider) -> String {
+        let request = SpeechToTextRequest::new(silent_wav(Duration::from_millis(100)))
+            .model(models::WHISPER_LARGE_V3);
+        let response = provider
+            .transcribe(request, &RequestOptions::default())
+            .await
+            .unwrap();
+        response.text
+    }
+
+    #[tokio::test]
+    async fn test_providers() {
+        let server = MockGroqServer::start();
+        let groq = AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let local =
+            OpenAiCompatibleClient::new(&format!("{}/", server.endpoint()), AuthScheme::None)
+                .model_alias(models::WHISPER_LARGE_V3, "ggml-large-v3");
+        let azure = OpenAiCompatibleClient::new(
+            &server.endpoint(),
+            AuthScheme::ApiKey("azure_key".to_string()),
+        );
+
+        assert_eq!(transcribe(&groq).await, MOCK_TEXT);
+        let local: Arc<dyn SpeechToTextProvider> = Arc::new(local);
+        assert_eq!(transcribe(&local).await, MOCK_TEXT);
+        server.enqueue("/chat/completions", MockResponse::chat_completion("Hi!"));
+        let request = ChatCompletionRequest::new(
+            "llama3-70b-8192",
+            vec![ChatCompletionMessage::new(
+                ChatCompletionRoles::User,
+                "Hello",
+            )],
+        );
+        let response = azure
+            .chat(request, &RequestOptions::default())
+            .await
+            .unwrap();
+        assert_eq!(response.choices[0].message.content.as_deref(), Some("Hi!"));
+
+        let requests = server.requests();
+        assert_eq!(requests[0].header("authorization"), Some("Bearer test_key"));
+        assert_eq!(
+            requests[0].form_text("model").as_deref(),
+            Some(models::WHISPER_LARGE_V3)
+        );
+        assert_eq!(requests[1].header("authorization"), None);
+        assert_eq!(
+            requests[1].form_text("model").as_deref(),
+            Some("ggml-large-v3")
+        );
+        assert_eq!(requests[2].header("authorization"), None);
+        assert_eq!(requests[2].header("api-key"), Some("azure_key"));
+        assert_eq!(
+            format!("{:?}", AuthScheme::ApiKey("azure_key".to_string())),
+            "ApiKey(..)"
+        );
+    }
+}
@@ -0,0 +1,338 @@
+use crate::GroqError;
+use reqwest::{header::HeaderMap, StatusCode};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::Duration;
+
+/// Controls how failed requests are retried by `GroqClient` and `AsyncGroqClient`.
+///
+/// Only requests that failed before reaching the model are retried: connection errors, timeouts,
+/// `408`, `429` and `5xx` responses, unless the API sends `x-should-retry: false`. The delay
+/// before each retry is taken from the `retry-after` header when present, and otherwise grows
+/// exponentially from `initial_backoff` up to `max_backoff`, with random jitter.
+///
+/// - `max_attempts`: The maximum number of attempts, including the first one.
+/// - `initial_backoff`: The delay before the first retry.
+/// - `max_backoff`: The upper bound for any single delay, including one requested by `retry-after`.
+/// - `multiplier`: The factor the delay grows by after each retry.
+/// - `jitter`: Whether to randomize delays, so concurrent clients do not retry in lockstep.
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    pub initial_backoff: Duration,
+    pub max_backoff: Duration,
+    pub multiplier: f64,
+    pub jitter: bool,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        Self {
+            max_attempts: 3,
+            initial_backoff: Duration::from_millis(500),
+            max_backoff: Duration::from_secs(30),
+            multiplier: 2.0,
+            jitter: true,
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Creates a policy that never retries.
+    pub fn none() -> Self {
+        Self {
+            max_attempts: 1,
+            ..Self::default()
+        }
+    }
+
+    /// Sets the maximum number of attempts, including the first one.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_attempts` - The maximum number of attempts. `0` is treated as `1`.
+    pub fn max_attempts(mut self, max_attempts: u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Sets the delay before the first retry.
+    ///
+    /// # Arguments
+    ///
+    /// * `initial_backoff` - The initial delay.
+    pub fn initial_backoff(mut self, initial_backoff: Duration) -> Self {
+        self.initial_backoff = initial_backoff;
+        self
+    }
+
+    /// Sets the upper bound for any single delay.
+    ///
+    /// # Arguments
+    ///
+    /// * `max_backoff` - The maximum delay.
+    pub fn max_backoff(mut self, max_backoff: Duration) -> Self {
+        self.max_backoff = max_backoff;
+        self
+    }
+
+    /// Sets whether delays are randomized.
+    ///
+    /// # Arguments
+    ///
+    /// * `jitter` - Whether to apply jitter.
+    pub fn jitter(mut self, jitter: bool) -> Self {
+        self.jitter = jitter;
+        self
+    }
+
+    /// Returns how long to wait before retrying a request that received `status` and `headers`
+    /// on its `attempt`-th try, or `None` if it should not be retried.
+    pub(crate) fn retry_response(
+        &self,
+        attempt: u32,
+        status: StatusCode,
+        headers: &HeaderMap,
+    ) -> Option<Duration> {
+        if attempt >= self.max_attempts || !is_retryable_status(status, headers) {
+            return None;
+        }
+        let delay = retry_after(headers).unwrap_or_else(|| self.backoff(attempt));
+        Some(delay.min(self.max_backoff))
+    }
+
+    /// Returns how long to wait before retrying a request that failed with `error` on its
+    /// `attempt`-th try, or `None` if it should not be retried.
+    pub(crate) fn retry_error(&self, attempt: u32, error: &GroqError) -> Option<Duration> {
+        if attempt >= self.max_attempts || !error.is_retryable() {
+            return None;
+        }
+        Some(self.backoff(attempt))
+    }
+
+    /// Computes the exponential backoff delay after the `attempt`-th try.
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponent = attempt.saturating_sub(1).min(32) as i32;
+        let delay = self
+            .initial_backoff
+            .mul_f64(self.multiplier.max(1.0).powi(exponent))
+            .min(self.max_backoff);
+        if !self.jitter {
+            return delay;
+        }
+        // Equal jitter: wait at least half the delay, plus a random share of the other half.
+        let half = delay / 2;
+        half + half.mul_f64(random_fraction())
+    }
+}
+
+/// Returns whether a response status is worth retrying.
+fn is_retryable_status(status: StatusCode, headers: &HeaderMap) -> bool {
+    match headers.get("x-should-retry").and_then(|v| v.to_str().ok()) {
+        Some("true") => return true,
+        Some("false") => return false,
+        _ => {}
+    }
+    status == StatusCode::REQUEST_TIMEOUT
+        || status == StatusCode::TOO_MANY_REQUESTS
+        || status.is_server_error()
+}
+
+/// Returns a random number in `[0, 1)`, seeded from the randomly keyed std hasher.
+fn random_fraction() -> f64 {
+    let bits = RandomState::new().build_hasher().finish();
+    (bits >> 11) as f64 / (1u64 << 53) as f64
+}
+
+/// Parses the `retry-after` header, given in seconds.
+pub(crate) fn retry_after(headers: &HeaderMap) -> Option<Duration> {
+    let value = headers.get("retry-after")?.to_str().ok()?;
+    let seconds: f64 = value.trim().parse().ok()?;
+    (seconds.is_finite() && seconds >= 0.0).then(|| Duration::from_secs_f64(seconds))
+}
+
+/// Represents the rate limit state reported by the `x-ratelimit-*` headers of a response.
+///
+/// - `limit_requests`: The number of requests allowed per day.
+/// - `limit_tokens`: The number of tokens allowed per minute.
+/// - `remaining_requests`: The number of requests left before the request limit is reached.
+/// - `remaining_tokens`: The number of tokens left before the token limit is reached.
+/// - `reset_requests`: The time until the request limit resets.
+/// - `reset_tokens`: The time until the token limit resets.
+/// - `retry_after`: The time to wait before retrying, sent with `429` responses.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RateLimitInfo {
+    pub limit_requests: Option<u64>,
+    pub limit_tokens: Option<u64>,
+    pub remaining_requests: Option<u64>,
+    pub remaining_tokens: Option<u64>,
+    pub reset_requests: Option<Duration>,
+    pub reset_tokens: Option<Duration>,
+    pub retry_after: Option<Duration>,
+}
+
+impl RateLimitInfo {
+    /// Parses the rate limit headers of a response, or returns `None` if there are none.
+    pub(crate) fn from_headers(headers: &HeaderMap) -> Option<Self> {
+        let number = |name: &str| -> Option<u64> { headers.get(name)?.to_str().ok()?.parse().ok() };
+        let duration =
+            |name: &str| -> Option<Duration> { parse_duration(headers.get(name)?.to_str().ok()?) };
+        let info = Self {
+            limit_requests: number("x-ratelimit-limit-requests"),
+            limit_tokens: number("x-ratelimit-limit-tokens"),
+            remaining_requests: number("x-ratelimit-remaining-requests"),
+            remaining_tokens: number("x-ratelimit-remaining-tokens"),
+            reset_requests: duration("x-ratelimit-reset-requests"),
+            reset_tokens: duration("x-ratelimit-reset-tokens"),
+            retry_after: retry_after(headers),
+        };
+        (info != Self::default()).then_some(info)
+    }
+}
+
+/// Parses a duration such as `2m59.56s`, `7.66s` or `250ms`, as sent in the reset headers.
+fn parse_duration(value: &str) -> Option<Duration> {
+    let mut rest = value.trim();
+    if rest.is_empty() {
+        return None;
+    }
+    let mut total = 0.0;
+    while !rest.is_empty() {
+        let number_end = rest
+            .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+            .unwrap_or(rest.len());
+        let number: f64 = rest[..number_end].parse().ok()?;
+        rest = &rest[number_end..];
+        let unit_end = rest
+            .find(|c: char| c.is_ascii_digit() || c == '.')
+            .unwrap_or(rest.len());
+        let seconds = match &rest[..unit_end] {
+            "h" => 3600.0,
+            "m" => 60.0,
+            "s" | "" => 1.0,
+            "ms" => 0.001,
+            "us" | "µs" => 0.000_001,
+            "ns" => 0.000_000_001,
+            _ => return None,
+        };
+        total += number * seconds;
+        rest = &rest[unit_end..];
+    }
+    Some(Duration::from_secs_f64(total))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use reqwest::header::HeaderValue;
+
+    fn headers(pairs: &[(&'static str, &'static str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_static(value));
+        }
+        headers
+    }
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("7.66s"), Some(Duration::from_millis(7660)));
+        assert_eq!(
+            parse_duration("2m59.5s"),
+            Some(Duration::from_millis(179_500))
+        );
+        assert_eq!(parse_duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(parse_duration("1h"), Some(Duration::from_secs(3600)));
+        assert_eq!(parse_duration("soon"), None);
+    }
+
+    #[test]
+    fn test_rate_limit_info() {
+        let info = RateLimitInfo::from_headers(&headers(&[
+            ("x-ratelimit-limit-requests", "14400"),
+            ("x-ratelimit-limit-tokens", "18000"),
+            ("x-ratelimit-remaining-requests", "14370"),
+            ("x-ratelimit-remaining-tokens", "17997"),
+            ("x-ratelimit-reset-requests", "2m59.56s"),
+            ("x-ratelimit-reset-tokens", "7.66s"),
+            ("retry-after", "2"),
+        ]))
+        .unwrap();
+        assert_eq!(info.remaining_requests, Some(14370));
+        assert_eq!(info.remaining_tokens, Some(17997));
+        assert_eq!(info.reset_tokens, Some(Duration::from_millis(7660)));
+        assert_eq!(info.retry_after, Some(Duration::from_secs(2)));
+        assert_eq!(RateLimitInfo::from_headers(&HeaderMap::new()), None);
+    }
+
+    #[test]
+    fn test_retry_response() {
+        let policy = RetryPolicy::default()
+            .initial_backoff(Duration::from_millis(100))
+            .jitter(false);
+        let none = HeaderMap::new();
+        assert_eq!(
+            policy.retry_response(1, StatusCode::TOO_MANY_REQUESTS, &none),
+            Some(Duration::from_millis(100))
+        );
+        assert_eq!(
+            policy.retry_response(2, StatusCode::BAD_GATEWAY, &none),
+            Some(Duration::from_millis(200))
+        );
+        // Out of attempts.
+        assert_eq!(
+            policy.retry_response(3, StatusCode::BAD_GATEWAY, &none),
+            None
+        );
+        // Client errors are not retried unless the server says so.
+        assert_eq!(
+            policy.retry_response(1, StatusCode::BAD_REQUEST, &none),
+            None
+        );
+        assert!(policy
+            .retry_response(
+                1,
+                StatusCode::CONFLICT,
+                &headers(&[("x-should-retry", "true")])
+            )
+            .is_some());
+        assert_eq!(
+            policy.retry_response(
+                1,
+                StatusCode::SERVICE_UNAVAILABLE,
+                &headers(&[("x-should-retry", "false")])
+            ),
+            None
+        );
+        // `retry-after` wins over the computed backoff, within `max_backoff`.
+        assert_eq!(
+            policy.retry_response(
+                1,
+                StatusCode::TOO_MANY_REQUESTS,
+                &headers(&[("retry-after", "3")])
+            ),
+            Some(Duration::from_secs(3))
+        );
+        assert_eq!(
+            policy.retry_response(
+                1,
+                StatusCode::TOO_MANY_REQUESTS,
+                &headers(&[("retry-after", "3600")])
+            ),
+            Some(Duration::from_secs(30))
+        );
+        assert_eq!(
+            RetryPolicy::none().retry_response(1, StatusCode::TOO_MANY_REQUESTS, &none),
+            None
+        );
+    }
+
+    #[test]
+    fn test_backoff_jitter_bounds() {
+        let policy = RetryPolicy::default().initial_backoff(Duration::from_millis(400));
+        for _ in 0..100 {
+            let delay = policy.backoff(2);
+            assert!(delay >= Duration::from_millis(400) && delay <= Duration::from_millis(800));
+        }
+    }
+}
@@ -0,0 +1,341 @@
+use crate::{codec, ChatCompletionChunk, GroqError, RateLimitInfo, RequestOptions};
+use bytes::Bytes;
+use futures_util::future::{BoxFuture, FutureExt};
+use futures_util::stream::{BoxStream, Stream, StreamExt};
+use reqwest::header::HeaderMap;
+use std::collections::VecDeque;
+use std::io::{BufRead, BufReader, Read};
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// Incrementally decodes a server-sent event body into the `data` payloads of its events.
+///
+/// Bytes can be fed in arbitrarily sized pieces; payloads are only produced once the
+/// blank line terminating their event has been received.
+#[derive(Debug, Default)]
+struct SseDecoder {
+    buffer: Vec<u8>,
+    data: Vec<String>,
+}
+
+impl SseDecoder {
+    /// Appends `bytes` to the decoder and returns every event payload completed by them.
+    fn feed(&mut self, bytes: &[u8]) -> Vec<String> {
+        self.buffer.extend_from_slice(bytes);
+        let mut events = Vec::new();
+        while let Some(pos) = self.buffer.iter().position(|&b| b == b'\n') {
+            let line: Vec<u8> = self.buffer.drain(..=pos).collect();
+            if let Some(event) = self.line(&String::from_utf8_lossy(&line)) {
+                events.push(event);
+            }
+        }
+        events
+    }
+
+    /// Flushes an event left unterminated at the end of the body.
+    fn finish(&mut self) -> Option<String> {
+        let rest = std::mem::take(&mut self.buffer);
+        if !rest.is_empty() {
+            if let Some(event) = self.line(&String::from_utf8_lossy(&rest)) {
+                return Some(event);
+            }
+        }
+        self.line("")
+    }
+
+    /// Processes a single line, returning the event payload when the line ends an event.
+    fn line(&mut self, line: &str) -> Option<String> {
+        let line = line.trim_end_matches(['\r', '\n']);
+        if line.is_empty() {
+            if self.data.is_empty() {
+                return None;
+            }
+            return Some(std::mem::take(&mut self.data).join("\n"));
+        }
+        if let Some(value) = line.strip_prefix("data:") {
+            self.data
+                .push(value.strip_prefix(' ').unwrap_or(value).to_string());
+        }
+        // Comments (`:`) and other fields (`event`, `id`, `retry`) carry nothing we need.
+        None
+    }
+}
+
+/// Turns a single event payload into a chunk, or `None` once the `[DONE]` sentinel is seen.
+fn decode_event(data: &str, headers: &HeaderMap) -> Option<Result<ChatCompletionChunk, GroqError>> {
+    if data.trim() == "[DONE]" {
+        return None;
+    }
+    Some(parse_event(data, headers))
+}
+
+/// Parses an event payload, surfacing in-band `error` objects as the matching `GroqError`.
+fn parse_event(data: &str, headers: &HeaderMap) -> Result<ChatCompletionChunk, GroqError> {
+    let value: serde_json::Value = serde_json::from_str(data)?;
+    if value.get("error").is_some() {
+        return Err(codec::api_error(None, headers, Some(value)));
+    }
+    Ok(serde_json::from_value(value)?)
+}
+
+/// A stream of `ChatCompletionChunk`s returned by `AsyncGroqClient::chat_completion_stream`.
+///
+/// The stream ends after the server sends `[DONE]`, and stops after yielding the first error,
+/// such as `GroqError::Cancelled` once the call it was opened with is interrupted.
+pub struct ChatCompletionStream {
+    body: BoxStream<'static, reqwest::Result<Bytes>>,
+    decoder: SseDecoder,
+    pending: VecDeque<String>,
+    done: bool,
+    headers: HeaderMap,
+    rate_limit: Option<RateLimitInfo>,
+    interrupt: Option<BoxFuture<'static, GroqError>>,
+}
+
+impl ChatCompletionStream {
+    pub(crate) fn new(response: reqwest::Response) -> Self {
+        let headers = response.headers().clone();
+        Self {
+            rate_limit: RateLimitInfo::from_headers(&headers),
+            headers,
+            ..Self::from_body(response.bytes_stream().boxed())
+        }
+    }
+
+    fn from_body(body: BoxStream<'static, reqwest::Result<Bytes>>) -> Self {
+        Self {
+            body,
+            decoder: SseDecoder::default(),
+            pending: VecDeque::new(),
+            done: false,
+            headers: HeaderMap::new(),
+            rate_limit: None,
+            interrupt: None,
+        }
+    }
+
+    /// Ends the stream with an error once the call is cancelled or its deadline passes.
+    pub(crate) fn interrupt_on(mut self, options: &RequestOptions) -> Self {
+        if options.is_interruptible() {
+            self.interrupt = Some(options.interrupted().boxed());
+        }
+        self
+    }
+
+    /// Returns the rate limit state reported in the response headers.
+    pub fn rate_limit(&self) -> Option<&RateLimitInfo> {
+        self.rate_limit.as_ref()
+    }
+
+    /// Takes the next buffered event, ending the stream on `[DONE]` or an error.
+    fn next_pending(&mut self) -> Option<Result<ChatCompletionChunk, GroqError>> {
+        let data = self.pending.pop_front()?;
+        let item = decode_event(&data, &self.headers);
+        if !matches!(item, Some(Ok(_))) {
+            self.done = true;
+        }
+        item
+    }
+}
+
+impl Stream for ChatCompletionStream {
+    type Item = Result<ChatCompletionChunk, GroqError>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        if let Some(interrupt) = this.interrupt.as_mut().filter(|_| !this.done) {
+            if let Poll::Ready(error) = interrupt.poll_unpin(cx) {
+                this.done = true;
+                return Poll::Ready(Some(Err(error)));
+            }
+        }
+        loop {
+            if this.done {
+                return Poll::Ready(None);
+            }
+            if let Some(item) = this.next_pending() {
+                return Poll::Ready(Some(item));
+            }
+            if this.done {
+                return Poll::Ready(None);
+            }
+            match this.body.poll_next_unpin(cx) {
+                Poll::Pending => return Poll::Pending,
+                Poll::Ready(Some(Ok(bytes))) => this.pending.extend(this.decoder.feed(&bytes)),
+                Poll::Ready(Some(Err(e))) => {
+                    this.done = true;
+                    return Poll::Ready(Some(Err(e.into())));
+                }
+                Poll::Ready(None) => match this.decoder.finish() {
+                    Some(data) => this.pending.push_back(data),
+                    None => {
+                        this.done = true;
+                        return Poll::Ready(None);
+                    }
+                },
+            }
+        }
+    }
+}
+
+/// A blocking iterator of `ChatCompletionChunk`s returned by `GroqClient::chat_completion_stream`.
+///
+/// Each call to `next` reads from the response body until a complete event is available.
+pub struct ChatCompletionChunks {
+    reader: BufReader<Box<dyn Read + Send>>,
+    decoder: SseDecoder,
+    done: bool,
+    headers: HeaderMap,
+    rate_limit: Option<RateLimitInfo>,
+    options: RequestOptions,
+}
+
+impl ChatCompletionChunks {
+    pub(crate) fn new(response: reqwest::blocking::Response) -> Self {
+        let headers = response.headers().clone();
+        Self {
+            rate_limit: RateLimitInfo::from_headers(&headers),
+            headers,
+            ..Self::from_reader(Box::new(response))
+        }
+    }
+
+    fn from_reader(reader: Box<dyn Read + Send>) -> Self {
+        Self {
+            reader: BufReader::new(reader),
+            decoder: SseDecoder::default(),
+            done: false,
+            headers: HeaderMap::new(),
+            rate_limit: None,
+            options: RequestOptions::default(),
+        }
+    }
+
+    /// Ends the iterator with an error once the call is cancelled or its deadline passes,
+    /// checked before every chunk.
+    pub(crate) fn interrupt_on(mut self, options: &RequestOptions) -> Self {
+        self.options = options.clone();
+        self
+    }
+
+    /// Returns the rate limit state reported in the response headers.
+    pub fn rate_limit(&self) -> Option<&RateLimitInfo> {
+        self.rate_limit.as_ref()
+    }
+
+    /// Reads lines until an event payload is complete, or returns `None` at end of body.
+    fn read_event(&mut self) -> Result<Option<String>, GroqError> {
+        let mut line = Vec::new();
+        loop {
+            line.clear();
+            let read = self.reader.read_until(b'\n', &mut line)?;
+            if read == 0 {
+                return Ok(self.decoder.finish());
+            }
+            if let Some(data) = self.decoder.feed(&line).pop() {
+                return Ok(Some(data));
+            }
+        }
+    }
+}
+
+impl Iterator for ChatCompletionChunks {
+    type Item = Result<ChatCompletionChunk, GroqError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let item = match self.options.check().and_then(|_| self.read_event()) {
+            Ok(Some(data)) => decode_event(&data, &self.headers),
+            Ok(None) => None,
+            // A read cut short by the deadline is reported as such.
+            Err(e) => Some(Err(self.options.check().err().unwrap_or(e))),
+        };
+        if !matches!(item, Some(Ok(_))) {
+            self.done = true;
+        }
+        item
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BODY: &str = concat!(
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"llama3-70b-8192\",\"system_fingerprint\":\"fp_1\",\"choices\":[{\"index\":0,\"delta\":{\"role\":\"assistant\",\"content\":\"\"},\"logprobs\":null,\"finish_reason\":null}],\"x_groq\":{\"id\":\"req_1\"}}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"llama3-70b-8192\",\"system_fingerprint\":\"fp_1\",\"choices\":[{\"index\":0,\"delta\":{\"content\":\"Hello\"},\"logprobs\":null,\"finish_reason\":null}]}\n\n",
+        "data: {\"id\":\"chatcmpl-1\",\"object\":\"chat.completion.chunk\",\"created\":1,\"model\":\"llama3-70b-8192\",\"system_fingerprint\":\"fp_1\",\"choices\":[{\"index\":0,\"delta\":{},\"logprobs\":null,\"finish_reason\":\"stop\"}],\"x_groq\":{\"id\":\"req_1\",\"usage\":{\"queue_time\":0.01,\"prompt_tokens\":11,\"prompt_time\":0.002,\"completion_tokens\":2,\"completion_time\":0.003,\"total_tokens\":13,\"total_time\":0.005}}}\n\n",
+        "data: [DONE]\n\n",
+    );
+
+    #[test]
+    fn test_sse_decoder_split_input() {
+        let mut decoder = SseDecoder::default();
+        let mut events = Vec::new();
+        for piece in BODY.as_bytes().chunks(7) {
+            events.extend(decoder.feed(piece));
+        }
+        assert_eq!(decoder.finish(), None);
+        assert_eq!(events.len(), 4);
+        assert_eq!(events[3], "[DONE]");
+    }
+
+    #[test]
+    fn test_sse_decoder_multiline_and_comments() {
+        let mut decoder = SseDecoder::default();
+        let events = decoder.feed(b": keep-alive\r\ndata: a\r\ndata:b\r\n\r\n");
+        assert_eq!(events, vec!["a\nb".to_string()]);
+        assert!(decoder.feed(b"data: tail").is_empty());
+        assert_eq!(decoder.finish(), Some("tail".to_string()));
+    }
+
+    #[test]
+    fn test_blocking_chunks() {
+        let chunks: Vec<_> = ChatCompletionChunks::from_reader(Box::new(BODY.as_bytes()))
+            .collect::<Result<_, _>>()
+            .unwrap();
+        assert_eq!(chunks.len(), 3);
+        assert!(matches!(
+            chunks[0].choices[0].delta.role,
+            Some(crate::ChatCompletionRoles::Assistant)
+        ));
+        let text: String = chunks.iter().filter_map(|c| c.content()).collect();
+        assert_eq!(text, "Hello");
+        assert_eq!(
+            chunks[2].choices[0].finish_reason,
+            Some(crate::FinishReason::Stop)
+        );
+        assert_eq!(chunks[2].usage().unwrap().total_tokens, 13);
+    }
+
+    #[tokio::test]
+    async fn test_async_stream() {
+        let pieces = BODY
+            .as_bytes()
+            .chunks(13)
+            .map(|p| Ok(Bytes::copy_from_slice(p)))
+            .collect::<Vec<_>>();
+        let stream = ChatCompletionStream::from_body(futures_util::stream::iter(pieces).boxed());
+        let chunks: Vec<_> = stream.collect().await;
+        assert_eq!(chunks.len(), 3);
+        let last = chunks[2].as_ref().unwrap();
+        assert_eq!(
+            last.choices[0].finish_reason,
+            Some(crate::FinishReason::Stop)
+        );
+        assert_eq!(last.usage().unwrap().prompt_tokens, 11);
+    }
+
+    #[test]
+    fn test_blocking_chunks_error_event() {
+        let body = "data: {\"error\":{\"message\":\"boom\",\"type\":\"server_error\"}}\n\n";
+        let mut chunks = ChatCompletionChunks::from_reader(Box::new(body.as_bytes()));
+        assert!(matches!(
+            chunks.next(),
+            Some(Err(GroqError::ServerError(_)))
+        ));
+        assert!(chunks.next().is_none());
+    }
+}
@@ -0,0 +1,852 @@
+//! A local stand-in for the Groq API, for tests that must not touch the network.
+//!
+//! `MockGroqServer` listens on a random local port and answers every endpoint the clients use
+//! with fixture responses. Tests can queue their own responses per path, including injected
+//! failures, and inspect every request the server received. Enable the `testing` feature to use
+//! it outside of this crate.
+//!
+//! # Example
+//!
+//!```
+//! use groq_api_rust::testing::{MockGroqServer, MockResponse};
+//! use groq_api_rust::{ChatCompletionMessage, ChatCompletionRequest, ChatCompletionRoles, GroqClient};
+//!
+//! let server = MockGroqServer::start();
+//! server.enqueue("/chat/completions", MockResponse::rate_limited(0));
+//! server.enqueue("/chat/completions", MockResponse::chat_completion("Hi!"));
+//!
+//! let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+//! let request = ChatCompletionRequest::new(
+//!     "llama-3.3-70b-versatile",
+//!     vec![ChatCompletionMessage::new(ChatCompletionRoles::User, "Hello")],
+//! );
+//! let response = client.chat_completion(request).unwrap();
+//! assert_eq!(response.choices[0].message.content.as_deref(), Some("Hi!"));
+//! assert_eq!(server.requests().len(), 2);
+//!```
+
+use crate::models;
+use serde_json::{json, Value};
+use std::collections::{HashMap, VecDeque};
+use std::io::{Cursor, Read, Write};
+use std::net::{SocketAddr, TcpListener, TcpStream};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// The content of the default chat completion and transcription fixtures.
+pub const MOCK_TEXT: &str = "Hello from the mock server!";
+
+/// A response served by `MockGroqServer`.
+///
+/// - `status`: The HTTP status of the response.
+/// - `headers`: The headers of the response, besides `Content-Length` and `Connection`.
+/// - `body`: The body of the response, written in one piece per entry.
+/// - `delay`: How long to wait before answering.
+#[derive(Debug, Clone, PartialEq)]
+pub struct MockResponse {
+    pub status: u16,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<Vec<u8>>,
+    pub delay: Option<Duration>,
+}
+
+impl MockResponse {
+    /// Creates a response with the given status, content type and body.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The HTTP status.
+    /// * `content_type` - The `Content-Type` of the body.
+    /// * `body` - The body.
+    pub fn new(status: u16, content_type: &str, body: impl Into<Vec<u8>>) -> Self {
+        Self {
+            status,
+            headers: vec![("Content-Type".to_string(), content_type.to_string())],
+            body: vec![body.into()],
+            delay: None,
+        }
+    }
+
+    /// Creates a JSON response.
+    pub fn json(status: u16, body: &Value) -> Self {
+        Self::new(status, "application/json", body.to_string())
+    }
+
+    /// Creates an error response in the format of the Groq API.
+    ///
+    /// # Arguments
+    ///
+    /// * `status` - The HTTP status.
+    /// * `type_` - The error type, such as `invalid_request_error`.
+    /// * `code` - The error code, such as `model_not_found`.
+    /// * `message` - The error message.
+    pub fn error(status: u16, type_: &str, code: &str, message: &str) -> Self {
+        Self::json(
+            status,
+            &json!({ "error": { "message": message, "type": type_, "code": code } }),
+        )
+    }
+
+    /// Creates a `429` response asking to retry after `retry_after` seconds.
+    pub fn rate_limited(retry_after: u64) -> Self {
+        Self::error(
+            429,
+            "tokens",
+            "rate_limit_exceeded",
+            "Rate limit reached, please try again later",
+        )
+        .header("retry-after", &retry_after.to_string())
+    }
+
+    /// Creates a `500` response.
+    pub fn server_error() -> Self {
+        Self::error(
+            500,
+            "internal_server_error",
+            "internal_server_error",
+            "Internal server error",
+        )
+    }
+
+    /// Creates a `401` response for an invalid API key.
+    pub fn unauthorized() -> Self {
+        Self::error(
+            401,
+            "invalid_request_error",
+            "invalid_api_key",
+            "Invalid API Key",
+        )
+    }
+
+    /// Creates a successful response whose body is not valid JSON.
+    pub fn malformed_json() -> Self {
+        Self::new(200, "application/json", "{\"choices\": [")
+    }
+
+    /// Creates a chat completion response whose only choice has the given content.
+    pub fn chat_completion(content: &str) -> Self {
+        Self::json(200, &chat_completion(content))
+    }
+
+    /// Creates a streamed chat completion response, sending one event per piece of content.
+    pub fn chat_completion_stream(pieces: &[&str]) -> Self {
+        let chunk = |delta: Value, finish_reason: Value, x_groq: Value| {
+            let chunk = json!({
+                "id": "chatcmpl-mock",
+                "object": "chat.completion.chunk",
+                "created": 0,
+                "model": models::LLAMA_3_3_70B_VERSATILE,
+                "system_fingerprint": "fp_mock",
+                "choices": [{
+                    "index": 0,
+                    "delta": delta,
+                    "logprobs": null,
+                    "finish_reason": finish_reason
+                }],
+                "x_groq": x_groq
+            });
+            format!("data: {}\n\n", chunk).into_bytes()
+        };
+        let mut body = vec![chunk(
+            json!({ "role": "assistant", "content": "" }),
+            Value::Null,
+            json!({ "id": "req_mock" }),
+        )];
+        for piece in pieces {
+            body.push(chunk(
+                json!({ "content": piece }),
+                Value::Null,
+                json!({ "id": "req_mock" }),
+            ));
+        }
+        body.push(chunk(
+            json!({}),
+            json!("stop"),
+            json!({ "id": "req_mock", "usage": usage() }),
+        ));
+        body.push(b"data: [DONE]\n\n".to_vec());
+        Self {
+            body,
+            ..Self::new(200, "text/event-stream", Vec::new())
+        }
+    }
+
+    /// Creates a transcription or translation response with the given text.
+    pub fn transcription(text: &str) -> Self {
+        Self::json(
+            200,
+            &json!({ "text": text, "x_groq": { "id": "req_mock" } }),
+        )
+    }
+
+    /// Creates a `verbose_json` speech-to-text response in a single segment, each word lasting half a second.
+    pub fn verbose_transcription(text: &str) -> Self {
+        let words: Vec<Value> = text
+            .split_whitespace()
+            .enumerate()
+            .map(|(i, word)| json!({ "word": word, "start": i as f64 * 0.5, "end": (i + 1) as f64 * 0.5 }))
+            .collect();
+        let duration = words.len() as f64 * 0.5;
+        Self::json(
+            200,
+            &json!({
+                "task": "transcribe",
+                "language": "English",
+                "duration": duration,
+                "text": text,
+                "segments": [{
+                    "id": 0,
+                    "seek": 0,
+                    "start": 0.0,
+                    "end": duration,
+                    "text": text,
+                    "tokens": [50364, 2425, 50414],
+                    "temperature": 0.0,
+                    "avg_logprob": -0.2,
+                    "compression_ratio": 1.1,
+                    "no_speech_prob": 0.01
+                }],
+                "words": words,
+                "x_groq": { "id": "req_mock" }
+            }),
+        )
+    }
+
+    /// Creates a text-to-speech response containing a short silent WAV file.
+    pub fn speech() -> Self {
+        Self::new(200, "audio/wav", silent_wav(Duration::from_millis(100)))
+    }
+
+    /// Creates a model listing response with the given model ids.
+    pub fn models(ids: &[&str]) -> Self {
+        let data: Vec<Value> = ids.iter().map(|id| model(id)).collect();
+        Self::json(200, &json!({ "object": "list", "data": data }))
+    }
+
+    /// Adds a header to the response.
+    pub fn header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    /// Delays the response, to trigger client timeouts.
+    pub fn delay(mut self, delay: Duration) -> Self {
+        self.delay = Some(delay);
+        self
+    }
+}
+
+/// A chat completion whose only choice has the given content.
+fn chat_completion(content: &str) -> Value {
+    json!({
+        "id": "chatcmpl-mock",
+        "object": "chat.completion",
+        "created": 0,
+        "model": models::LLAMA_3_3_70B_VERSATILE,
+        "choices": [{
+            "index": 0,
+            "message": { "role": "assistant", "content": content },
+            "logprobs": null,
+            "finish_reason": "stop"
+        }],
+        "usage": usage(),
+        "system_fingerprint": "fp_mock",
+        "x_groq": { "id": "req_mock" }
+    })
+}
+
+/// The `usage` object of the fixtures.
+fn usage() -> Value {
+    json!({
+        "queue_time": 0.0,
+        "prompt_tokens": 10,
+        "prompt_time": 0.001,
+        "completion_tokens": 5,
+        "completion_time": 0.002,
+        "total_tokens": 15,
+        "total_time": 0.003
+    })
+}
+
+/// A model record as returned by the `/models` endpoint.
+fn model(id: &str) -> Value {
+    json!({
+        "id": id,
+        "object": "model",
+        "created": 0,
+        "owned_by": "Mock",
+        "active": true,
+        "context_window": 131072,
+        "public_apps": null
+    })
+}
+
+/// Encodes a silent 16 kHz mono WAV file of the given duration.
+pub fn silent_wav(duration: Duration) -> Vec<u8> {
+    let spec = hound::WavSpec {
+        channels: 1,
+        sample_rate: 16000,
+        bits_per_sample: 16,
+        sample_format: hound::SampleFormat::Int,
+    };
+    let mut wav = Cursor::new(Vec::new());
+    let mut writer = hound::WavWriter::new(&mut wav, spec).expect("writing to memory");
+    for _ in 0..(duration.as_secs_f64() * 16000.0) as usize {
+        writer.write_sample(0i16).expect("writing to memory");
+    }
+    writer.finalize().expect("writing to memory");
+    wav.into_inner()
+}
+
+/// A request received by `MockGroqServer`.
+///
+/// - `method`: The HTTP method, such as `POST`.
+/// - `path`: The path of the request, relative to the server's endpoint and without query.
+/// - `headers`: The headers of the request, with lowercase names, sorted by name.
+/// - `body`: The raw body of the request.
+#[derive(Debug, Clone, PartialEq)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub path: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl RecordedRequest {
+    /// Returns the value of a header.
+    pub fn header(&self, name: &str) -> Option<&str> {
+        let name = name.to_lowercase();
+        self.headers
+            .iter()
+            .find(|(n, _)| *n == name)
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// Parses the body as JSON.
+    pub fn json(&self) -> Option<Value> {
+        serde_json::from_slice(&self.body).ok()
+    }
+
+    /// Returns the value of a text field of a `multipart/form-data` body.
+    pub fn form_text(&self, name: &str) -> Option<String> {
+        let body = String::from_utf8_lossy(&self.body);
+        let marker = format!("name=\"{}\"\r\n\r\n", name);
+        let start = body.find(&marker)? + marker.len();
+        let end = body[start..].find("\r\n--")?;
+        Some(body[start..start + end].to_string())
+    }
+
+    /// Returns the file name and content of a file field of a `multipart/form-data` body.
+    pub fn form_file(&self, name: &str) -> Option<(String, Vec<u8>)> {
+        let find = |haystack: &[u8], needle: &[u8]| {
+            haystack
+                .windows(needle.len())
+                .position(|window| window == needle)
+        };
+        let marker = format!("name=\"{}\"; filename=\"", name);
+        let name_start = find(&self.body, marker.as_bytes())? + marker.len();
+        let name_end = name_start + find(&self.body[name_start..], b"\"")?;
+        let start = name_end + find(&self.body[name_end..], b"\r\n\r\n")? + 4;
+        let end = start + find(&self.body[start..], b"\r\n--")?;
+        Some((
+            String::from_utf8_lossy(&self.body[name_start..name_end]).to_string(),
+            self.body[start..end].to_vec(),
+        ))
+    }
+}
+
+/// The responses queued by tests, the requests received so far, and the files and batches created.
+#[derive(Debug, Default)]
+struct MockState {
+    queued: HashMap<String, VecDeque<MockResponse>>,
+    requests: Vec<RecordedRequest>,
+    files: Vec<(Value, Vec<u8>)>,
+    batches: Vec<Value>,
+}
+
+/// A local HTTP server standing in for the Groq API.
+///
+/// Requests are answered with the responses queued for their path, in order, then with fixtures:
+///
+/// - `POST /chat/completions`: `MockResponse::chat_completion`, or `chat_completion_stream` when `stream` is set.
+/// - `POST /audio/transcriptions` and `/audio/translations`: `MockResponse::transcription`.
+/// - `POST /audio/speech`: `MockResponse::speech`.
+/// - `GET /models` and `/models/{id}`: the models of `groq_api_rust::models`, or `404` for unknown ids.
+/// - `/files` and `/batches`: files are stored in memory, and batches of chat completions are
+///   created as `validating` and completed by the time they are retrieved. Their output file
+///   holds a `MockResponse::chat_completion` for every request, in reverse order.
+///
+/// Any other path is answered with `404`. The server stops when dropped.
+pub struct MockGroqServer {
+    addr: SocketAddr,
+    state: Arc<Mutex<MockState>>,
+    shutdown: Arc<AtomicBool>,
+}
+
+impl MockGroqServer {
+    /// Starts a server on a random local port.
+    pub fn start() -> Self {
+        let listener = TcpListener::bind("127.0.0.1:0").expect("binding a local port");
+        let addr = listener.local_addr().expect("reading the local address");
+        let state = Arc::new(Mutex::new(MockState::default()));
+        let shutdown = Arc::new(AtomicBool::new(false));
+
+        let accept_state = state.clone();
+        let accept_shutdown = shutdown.clone();
+        std::thread::spawn(move || {
+            for stream in listener.incoming() {
+                if accept_shutdown.load(Ordering::SeqCst) {
+                    break;
+                }
+                let Ok(stream) = stream else { continue };
+                let state = accept_state.clone();
+                std::thread::spawn(move || handle_connection(stream, &state));
+            }
+        });
+        Self {
+            addr,
+            state,
+            shutdown,
+        }
+    }
+
+    /// Returns the base URL of the server, to use as the endpoint of a client.
+    pub fn endpoint(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// Queues a response for the next request to `path`, after those already queued for it.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the endpoint, such as `/chat/completions`.
+    /// * `response` - The response to serve.
+    pub fn enqueue(&self, path: &str, response: MockResponse) {
+        self.lock()
+            .queued
+            .entry(path.to_string())
+            .or_default()
+            .push_back(response);
+    }
+
+    /// Returns every request received so far, in order.
+    pub fn requests(&self) -> Vec<RecordedRequest> {
+        self.lock().requests.clone()
+    }
+
+    fn lock(&self) -> std::sync::MutexGuard<'_, MockState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+impl Drop for MockGroqServer {
+    fn drop(&mut self) {
+        self.shutdown.store(true, Ordering::SeqCst);
+        // Wake the accept loop up so it sees the flag.
+        let _ = TcpStream::connect(self.addr);
+    }
+}
+
+/// Reads a single request from `stream`, records it and writes its response.
+fn handle_connection(mut stream: TcpStream, state: &Mutex<MockState>) {
+    let Some(request) = read_request(&mut stream) else {
+        return;
+    };
+    let response = {
+        let mut state = state.lock().unwrap_or_else(|e| e.into_inner());
+        let queued = state
+            .queued
+            .get_mut(&request.path)
+            .and_then(VecDeque::pop_front);
+        let response = queued.unwrap_or_else(|| default_response(&request, &mut state));
+        // Record the request before answering, so it is visible once the client returns.
+        state.requests.push(request);
+        response
+    };
+    if let Some(delay) = response.delay {
+        std::thread::sleep(delay);
+    }
+    // The client may have given up already, so write errors are ignored.
+    let _ = write_response(&mut stream, &response);
+}
+
+/// Reads the head and body of a request, or `None` if the connection closed first.
+fn read_request(stream: &mut TcpStream) -> Option<RecordedRequest> {
+    let mut raw = Vec::new();
+    let mut buf = [0; 8192];
+    let head_end = loop {
+        let n = stream.read(&mut buf).ok().filter(|&n| n > 0)?;
+        raw.extend_from_slice(&buf[..n]);
+        if let Some(pos) = raw.windows(4).position(|w| w == b"\r\n\r\n") {
+            break pos + 4;
+        }
+    };
+    let head = String::from_utf8_lossy(&raw[..head_end]).to_string();
+    let mut lines = head.split("\r\n");
+    let mut request_line = lines.next()?.split(' ');
+    let method = request_line.next()?.to_string();
+    let target = request_line.next()?;
+    let path = target.split('?').next().unwrap_or(target).to_string();
+
+    let mut headers: Vec<(String, String)> = lines
+        .filter_map(|line| line.split_once(':'))
+        .map(|(name, value)| (name.trim().to_lowercase(), value.trim().to_string()))
+        .collect();
+    headers.sort();
+    let length: usize = headers
+        .iter()
+        .find(|(name, _)| name == "content-length")
+        .and_then(|(_, value)| value.parse().ok())
+        .unwrap_or(0);
+    let chunked = headers
+        .iter()
+        .any(|(name, value)| name == "transfer-encoding" && value.contains("chunked"));
+    let body = loop {
+        if chunked {
+            if let Some(body) = decode_chunked(&raw[head_end..]) {
+                break body;
+            }
+        } else if raw.len() >= head_end + length {
+            break raw[head_end..].to_vec();
+        }
+        let n = stream.read(&mut buf).ok().filter(|&n| n > 0)?;
+        raw.extend_from_slice(&buf[..n]);
+    };
+    Some(RecordedRequest {
+        method,
+        path,
+        headers,
+        body,
+    })
+}
+
+/// Decodes a body sent with `Transfer-Encoding: chunked`, as requests streamed without a known
+/// length are, or returns `None` if its last chunk has not been read yet.
+fn decode_chunked(mut raw: &[u8]) -> Option<Vec<u8>> {
+    let mut body = Vec::new();
+    loop {
+        let line_end = raw.windows(2).position(|w| w == b"\r\n")?;
+        let size = std::str::from_utf8(&raw[..line_end]).ok()?;
+        let size = usize::from_str_radix(size.split(';').next()?.trim(), 16).ok()?;
+        let start = line_end + 2;
+        if size == 0 {
+            return Some(body);
+        }
+        body.extend_from_slice(raw.get(start..start + size)?);
+        raw = raw.get(start + size + 2..)?;
+    }
+}
+
+fn write_response(stream: &mut TcpStream, response: &MockResponse) -> std::io::Result<()> {
+    let reason = reqwest::StatusCode::from_u16(response.status)
+        .ok()
+        .and_then(|s| s.canonical_reason())
+        .unwrap_or("Mock");
+    let mut head = format!("HTTP/1.1 {} {}\r\n", response.status, reason);
+    for (name, value) in &response.headers {
+        head.push_str(&format!("{}: {}\r\n", name, value));
+    }
+    // Single-piece bodies get a length; streamed ones are delimited by closing the connection.
+    if response.body.len() == 1 {
+        head.push_str(&format!("Content-Length: {}\r\n", response.body[0].len()));
+    }
+    head.push_str("Connection: close\r\n\r\n");
+    stream.write_all(head.as_bytes())?;
+    for piece in &response.body {
+        stream.write_all(piece)?;
+        stream.flush()?;
+    }
+    Ok(())
+}
+
+/// Returns the fixture answering a request nothing was queued for.
+fn default_response(request: &RecordedRequest, state: &mut MockState) -> MockResponse {
+    let known_models = [
+        models::LLAMA_3_3_70B_VERSATILE,
+        models::LLAMA_3_1_8B_INSTANT,
+        models::LLAMA_4_SCOUT,
+        models::LLAMA_4_MAVERICK,
+        models::GEMMA2_9B_IT,
+        models::WHISPER_LARGE_V3,
+        models::WHISPER_LARGE_V3_TURBO,
+        models::PLAYAI_TTS,
+        models::PLAYAI_TTS_ARABIC,
+    ];
+    match (request.method.as_str(), request.path.as_str()) {
+        ("POST", "/chat/completions") => {
+            let stream = request
+                .json()
+                .and_then(|body| body["stream"].as_bool())
+                .unwrap_or(false);
+            if stream {
+                MockResponse::chat_completion_stream(&["Hello", " from the", " mock server!"])
+            } else {
+                MockResponse::chat_completion(MOCK_TEXT)
+            }
+        }
+        ("POST", "/audio/transcriptions" | "/audio/translations") => {
+            MockResponse::transcription(MOCK_TEXT)
+        }
+        ("POST", "/audio/speech") => MockResponse::speech(),
+        (_, path) if path.starts_with("/files") || path.starts_with("/batches") => {
+            files_and_batches(request, state)
+        }
+        ("GET", "/models") => MockResponse::models(&known_models),
+        ("GET", path) if path.starts_with("/models/") => {
+            let id = &path["/models/".len()..];
+            if known_models.contains(&id) {
+                MockResponse::json(200, &model(id))
+            } else {
+                MockResponse::error(
+                    404,
+                    "invalid_request_error",
+                    "model_not_found",
+                    &format!("The model `{}` does not exist", id),
+                )
+            }
+        }
+        _ => MockResponse::error(
+            404,
+            "invalid_request_error",
+            "unknown_url",
+            &format!("Unknown request URL: {} {}", request.method, request.path),
+        ),
+    }
+}
+
+/// Answers requests to the Files and Batch APIs from the files and batches in `state`.
+fn files_and_batches(request: &RecordedRequest, state: &mut MockState) -> MockResponse {
+    let not_found = || {
+        MockResponse::error(
+            404,
+            "invalid_request_error",
+            "not_found",
+            &format!("No such object: {}", request.path),
+        )
+    };
+    let segments: Vec<&str> = request.path.trim_matches('/').split('/').collect();
+    match (request.method.as_str(), segments.as_slice()) {
+        ("POST", ["files"]) => {
+            let Some((filename, content)) = request.form_file("file") else {
+                return MockResponse::error(400, "invalid_request_error", "", "Missing file");
+            };
+            let file = json!({
+                "id": format!("file_mock_{}", state.files.len()),
+                "object": "file",
+                "bytes": content.len(),
+                "created_at": 0,
+                "filename": filename,
+                "purpose": request.form_text("purpose").unwrap_or_default(),
+            });
+            state.files.push((file.clone(), content));
+            MockResponse::json(200, &file)
+        }
+        ("GET", ["files"]) => {
+            let data: Vec<&Value> = state.files.iter().map(|(file, _)| file).collect();
+            MockResponse::json(200, &json!({ "object": "list", "data": data }))
+        }
+        (method, ["files", id, rest @ ..]) => {
+            let Some(index) = state.files.iter().position(|(file, _)| file["id"] == *id) else {
+                return not_found();
+            };
+            match (method, rest) {
+                ("GET", []) => MockResponse::json(200, &state.files[index].0),
+                ("GET", ["content"]) => MockResponse::new(
+                    200,
+                    "application/octet-stream",
+                    state.files[index].1.clone(),
+                ),
+                ("DELETE", []) => {
+                    state.files.remove(index);
+                    MockResponse::json(200, &json!({ "id": id, "object": "file", "deleted": true }))
+                }
+                _ => not_found(),
+            }
+        }
+        ("POST", ["batches"]) => {
+            let body = request.json().unwrap_or_default();
+            let Some((_, input)) = state
+                .files
+                .iter()
+                .find(|(file, _)| file["id"] == body["input_file_id"])
+            else {
+                return not_found();
+            };
+            let lines: Vec<Value> = input
+                .split(|&b| b == b'\n')
+                .filter_map(|line| serde_json::from_slice(line).ok())
+                .collect();
+            let mut output = Vec::new();
+            for (index, line) in lines.iter().enumerate().rev() {
+                let result = json!({
+                    "id": format!("batch_req_mock_{}", index),
+                    "custom_id": line["custom_id"],
+                    "response": {
+                        "status_code": 200,
+                        "request_id": "req_mock",
+                        "body": chat_completion(MOCK_TEXT),
+                    },
+                    "error": null,
+                });
+                output.extend(result.to_string().into_bytes());
+                output.push(b'\n');
+            }
+            let output_file_id = format!("file_mock_{}", state.files.len());
+            let output_file = json!({
+                "id": output_file_id,
+                "object": "file",
+                "bytes": output.len(),
+                "created_at": 0,
+                "filename": "batch_output.jsonl",
+                "purpose": "batch_output",
+            });
+            state.files.push((output_file, output));
+
+            let batch = json!({
+                "id": format!("batch_mock_{}", state.batches.len()),
+                "object": "batch",
+                "endpoint": body["endpoint"],
+                "errors": null,
+                "input_file_id": body["input_file_id"],
+                "completion_window": body["completion_window"],
+                "status": "validating",
+                "output_file_id": null,
+                "error_file_id": null,
+                "created_at": 0,
+                "request_counts": { "total": lines.len(), "completed": 0, "failed": 0 },
+                "metadata": body["metadata"],
+            });
+            let mut completed = batch.clone();
+            completed["status"] = json!("completed");
+            completed["output_file_id"] = json!(output_file_id);
+            completed["completed_at"] = json!(0);
+            completed["request_counts"]["completed"] = json!(lines.len());
+            state.batches.push(completed);
+            MockResponse::json(200, &batch)
+        }
+        ("GET", ["batches"]) => {
+            MockResponse::json(200, &json!({ "object": "list", "data": state.batches }))
+        }
+        (method, ["batches", id, rest @ ..]) => {
+            let Some(batch) = state.batches.iter_mut().find(|batch| batch["id"] == *id) else {
+                return not_found();
+            };
+            match (method, rest) {
+                ("GET", []) => MockResponse::json(200, batch),
+                ("POST", ["cancel"]) => {
+                    batch["status"] = json!("cancelled");
+                    batch["cancelled_at"] = json!(0);
+                    MockResponse::json(200, batch)
+                }
+                _ => not_found(),
+            }
+        }
+        _ => not_found(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        AsyncGroqClient, ChatCompletionMessage, ChatCompletionRequest, ChatCompletionRoles,
+        GroqClient, GroqClientBuilder, GroqError, RetryPolicy,
+    };
+    use futures_util::StreamExt;
+
+    fn request() -> ChatCompletionRequest {
+        ChatCompletionRequest::new(
+            models::LLAMA_3_3_70B_VERSATILE,
+            vec![ChatCompletionMessage::new(
+                ChatCompletionRoles::User,
+                "Hello",
+            )],
+        )
+    }
+
+    #[test]
+    fn test_default_routes() {
+        let server = MockGroqServer::start();
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()));
+
+        let text: String = client
+            .chat_completion_stream(request())
+            .unwrap()
+            .filter_map(|chunk| chunk.unwrap().content().map(str::to_string))
+            .collect();
+        assert_eq!(text, MOCK_TEXT);
+        assert_eq!(client.list_models().unwrap().len(), 9);
+        assert!(matches!(
+            client.retrieve_model("llama3-70b-8192"),
+            Err(GroqError::InvalidRequest(_))
+        ));
+
+        let requests = server.requests();
+        assert_eq!(requests[0].path, "/chat/completions");
+        assert_eq!(requests[0].json().unwrap()["stream"], true);
+        assert_eq!(requests[0].header("Authorization"), Some("Bearer test_key"));
+        assert_eq!(requests[1].method, "GET");
+    }
+
+    #[test]
+    fn test_error_injection() {
+        let server = MockGroqServer::start();
+        let client = GroqClient::new("test_key".to_string(), Some(server.endpoint()))
+            .with_retry_policy(RetryPolicy::none());
+
+        server.enqueue("/chat/completions", MockResponse::rate_limited(1));
+        server.enqueue("/chat/completions", MockResponse::server_error());
+        server.enqueue("/chat/completions", MockResponse::malformed_json());
+        let error = client.chat_completion(request()).unwrap_err();
+        assert_eq!(error.retry_after(), Some(Duration::from_secs(1)));
+        assert!(matches!(
+            client.chat_completion(request()),
+            Err(GroqError::ServerError(_))
+        ));
+        assert!(matches!(
+            client.chat_completion(request()),
+            Err(GroqError::JsonParseError(_))
+        ));
+        // The queue is drained, so fixtures are served again.
+        assert!(client.chat_completion(request()).is_ok());
+
+        server.enqueue(
+            "/chat/completions",
+            MockResponse::chat_completion("late").delay(Duration::from_millis(500)),
+        );
+        let client = GroqClientBuilder::new()
+            .api_key("test_key")
+            .endpoint(&server.endpoint())
+            .timeout(Duration::from_millis(100))
+            .retry_policy(RetryPolicy::none())
+            .build()
+            .unwrap();
+        assert!(matches!(
+            client.chat_completion(request()),
+            Err(GroqError::Timeout(_))
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_async_stream() {
+        let server = MockGroqServer::start();
+        server.enqueue(
+            "/chat/completions",
+            MockResponse::chat_completion_stream(&["a", "b"]),
+        );
+        let client = AsyncGroqClient::new("test_key".to_string(), Some(server.endpoint()));
+        let chunks: Vec<_> = client
+            .chat_completion_stream(request())
+            .await
+            .unwrap()
+            .collect()
+            .await;
+        assert_eq!(chunks.len(), 4);
+        let text: String = chunks
+            .iter()
+            .filter_map(|c| c.as_ref().unwrap().content())
+            .collect();
+        assert_eq!(text, "ab");
+    }
+}
@@ -0,0 +1,256 @@
+use crate::{ChatCompletionMessage, ChatCompletionRequest};
+
+/// The number of tokens counted for each image, a conservative estimate since the cost of an
+/// image depends on its resolution.
+pub const IMAGE_TOKENS: u32 = 1_600;
+
+/// Represents the families of models served by Groq, which share a tokenizer and chat template.
+///
+/// - `Llama`: Meta Llama 3 and 4 models, with a 128K-token vocabulary.
+/// - `Mixtral`: Mistral models, with a 32K-token vocabulary that splits numbers into digits.
+/// - `Gemma`: Google Gemma models, with a 256K-token vocabulary that splits numbers into digits.
+/// - `Other`: Any other model, estimated at four characters per token.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ModelFamily {
+    Llama,
+    Mixtral,
+    Gemma,
+    Other,
+}
+
+impl ModelFamily {
+    /// Guesses the family of a model from its id.
+    pub fn from_model(model: &str) -> Self {
+        let model = model.to_ascii_lowercase();
+        if model.contains("llama") {
+            ModelFamily::Llama
+        } else if model.contains("mixtral") || model.contains("mistral") {
+            ModelFamily::Mixtral
+        } else if model.contains("gemma") {
+            ModelFamily::Gemma
+        } else {
+            ModelFamily::Other
+        }
+    }
+
+    /// Returns the average number of letters per token in a word.
+    fn letters_per_token(self) -> f64 {
+        match self {
+            ModelFamily::Llama => 6.0,
+            ModelFamily::Mixtral => 4.5,
+            ModelFamily::Gemma => 6.0,
+            ModelFamily::Other => 4.0,
+        }
+    }
+
+    /// Returns the number of digits merged into a single token.
+    fn digits_per_token(self) -> usize {
+        match self {
+            ModelFamily::Llama | ModelFamily::Other => 3,
+            ModelFamily::Mixtral | ModelFamily::Gemma => 1,
+        }
+    }
+
+    /// Returns the average number of tokens for a character outside of ASCII.
+    fn tokens_per_non_ascii_char(self) -> f64 {
+        match self {
+            ModelFamily::Llama => 1.0,
+            ModelFamily::Mixtral => 1.5,
+            ModelFamily::Gemma => 0.7,
+            ModelFamily::Other => 1.0,
+        }
+    }
+
+    /// Returns the number of tokens the chat template adds around each message.
+    fn message_overhead(self) -> u32 {
+        match self {
+            ModelFamily::Mixtral => 6,
+            _ => 5,
+        }
+    }
+}
+
+/// Represents how much of the context window of a model a chat completion request takes.
+///
+/// - `prompt_tokens`: The estimated number of prompt tokens.
+/// - `max_tokens`: The number of tokens reserved for the completion, if set.
+/// - `context_window`: The maximum number of prompt and completion tokens of the model.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ContextUsage {
+    pub prompt_tokens: u32,
+    pub max_tokens: Option<u32>,
+    pub context_window: u32,
+}
+
+impl ContextUsage {
+    /// Returns the number of prompt and reserved completion tokens.
+    pub fn total(&self) -> u32 {
+        self.prompt_tokens + self.max_tokens.unwrap_or(0)
+    }
+
+    /// Returns the number of tokens left in the context window.
+    pub fn remaining(&self) -> u32 {
+        self.context_window.saturating_sub(self.total())
+    }
+
+    /// Returns the share of the context window taken by the prompt, between 0.0 and 1.0, for a context meter.
+    pub fn fraction(&self) -> f64 {
+        (self.prompt_tokens as f64 / self.context_window.max(1) as f64).min(1.0)
+    }
+
+    /// Returns whether the prompt and the reserved completion fit in the context window.
+    pub fn fits(&self) -> bool {
+        self.total() <= self.context_window
+    }
+}
+
+/// Estimates the number of tokens of chat completion requests without a tokenizer.
+///
+/// Text is split into words, numbers, punctuation and other characters, which are counted with
+/// the ratios of the tokenizer of the model family, and the tokens of the chat template are added
+/// for each message. Estimates are approximate, so leave some margin below the context window.
+///
+/// # Example
+///
+///```
+/// use groq_api_rust::{models, ChatCompletionMessage, ChatCompletionRequest, ChatCompletionRoles, TokenEstimator};
+///
+/// let request = ChatCompletionRequest::new(
+///     models::LLAMA_3_3_70B_VERSATILE,
+///     vec![ChatCompletionMessage::new(ChatCompletionRoles::User, "How far is the moon?")],
+/// );
+/// let estimator = TokenEstimator::for_model(&request.model);
+/// assert!(estimator.count_request(&request) > 5);
+///```
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenEstimator {
+    family: ModelFamily,
+}
+
+impl TokenEstimator {
+    /// Creates an estimator for the given model family.
+    pub fn new(family: ModelFamily) -> Self {
+        Self { family }
+    }
+
+    /// Creates an estimator for the family of the given model.
+    pub fn for_model(model: &str) -> Self {
+        Self::new(ModelFamily::from_model(model))
+    }
+
+    /// Returns the family the estimator counts tokens for.
+    pub fn family(&self) -> ModelFamily {
+        self.family
+    }
+
+    /// Estimates the number of tokens of a text.
+    pub fn count_text(&self, text: &str) -> u32 {
+        let family = self.family;
+        let mut tokens = 0.0;
+        let mut chars = text.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c.is_ascii_alphabetic() {
+                let mut letters = 1;
+                while chars.next_if(|c| c.is_ascii_alphabetic()).is_some() {
+                    letters += 1;
+                }
+                tokens += (letters as f64 / family.letters_per_token()).ceil();
+            } else if c.is_ascii_digit() {
+                let mut digits: usize = 1;
+                while chars.next_if(|c| c.is_ascii_digit()).is_some() {
+                    digits += 1;
+                }
+                tokens += digits.div_ceil(family.digits_per_token()) as f64;
+            } else if c == ' ' {
+                // A single space is merged into the following word, longer runs are a token.
+                let mut spaces = 1;
+                while chars.next_if_eq(&' ').is_some() {
+                    spaces += 1;
+                }
+                if spaces > 1 {
+                    tokens += 1.0;
+                }
+            } else if c.is_ascii_whitespace() {
+                while chars.next_if(|c| c.is_ascii_whitespace()).is_some() {}
+                tokens += 1.0;
+            } else if c.is_ascii() {
+                tokens += 1.0;
+            } else {
+                tokens += family.tokens_per_non_ascii_char();
+            }
+        }
+        tokens.ceil() as u32
+    }
+
+    /// Estimates the number of tokens of a message, including the tokens of the chat template.
+    pub fn count_message(&self, message: &ChatCompletionMessage) -> u32 {
+        let mut tokens = self.family.message_overhead()
+            + self.count_text(&message.content.text())
+            + IMAGE_TOKENS * message.content.images().len() as u32;
+        if let Some(name) = &message.name {
+            tokens += self.count_text(name);
+        }
+        for tool_call in message.tool_calls.iter().flatten() {
+            tokens += self.count_text(&tool_call.function.name)
+                + self.count_text(&tool_call.function.arguments);
+        }
+        tokens
+    }
+
+    /// Estimates the number of prompt tokens of a request, including its tools.
+    pub fn count_request(&self, request: &ChatCompletionRequest) -> u32 {
+        let messages: u32 = request.messages.iter().map(|m| self.count_message(m)).sum();
+        let tools = request
+            .tools
+            .as_ref()
+            .and_then(|tools| serde_json::to_string(tools).ok())
+            .map_or(0, |tools| self.count_text(&tools));
+        // The beginning of the prompt and the header of the reply.
+        messages + tools + self.family.message_overhead()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatCompletionRoles, ContentPart, ImageUrl};
+
+    #[test]
+    fn test_count_text() {
+        let llama = TokenEstimator::new(ModelFamily::Llama);
+        let mixtral = TokenEstimator::new(ModelFamily::Mixtral);
+        assert_eq!(llama.count_text(""), 0);
+        assert_eq!(llama.count_text("Hello, world!"), 4);
+        assert_eq!(llama.count_text("internationalization"), 4);
+        // Llama merges up to three digits, Mixtral splits every digit.
+        assert_eq!(llama.count_text("2024"), 2);
+        assert_eq!(mixtral.count_text("2024"), 4);
+        assert_eq!(llama.count_text("line\n\nline"), 3);
+        assert_eq!(llama.count_text("日本語"), 3);
+    }
+
+    #[test]
+    fn test_count_request() {
+        let estimator = TokenEstimator::for_model("llama-3.3-70b-versatile");
+        assert_eq!(estimator.family(), ModelFamily::Llama);
+        assert_eq!(
+            TokenEstimator::for_model("gemma2-9b-it").family(),
+            ModelFamily::Gemma
+        );
+        let request = ChatCompletionRequest::new(
+            "llama-3.3-70b-versatile",
+            vec![
+                ChatCompletionMessage::new(ChatCompletionRoles::System, "Be brief."),
+                ChatCompletionMessage::parts(
+                    ChatCompletionRoles::User,
+                    vec![
+                        ContentPart::text("What is this?"),
+                        ContentPart::image(ImageUrl::new("https://example.com/a.png")),
+                    ],
+                ),
+            ],
+        );
+        // Two messages and the reply header, at 5 tokens each, 3 and 4 tokens of text, and an image.
+        assert_eq!(estimator.count_request(&request), 15 + 3 + 4 + IMAGE_TOKENS);
+    }
+}
@@ -0,0 +1,233 @@
+use crate::{SpeechToTextResponse, TranscriptionSegment};
+use serde::{Deserialize, Serialize};
+use std::fmt::Write;
+
+/// Represents the format of a transcription response.
+///
+/// - `Json`: A JSON object holding the text. The default.
+/// - `Text`: The text alone.
+/// - `Srt`: SubRip subtitles.
+/// - `Vtt`: WebVTT subtitles.
+/// - `VerboseJson`: A JSON object also holding the language, the duration and timestamps.
+///
+/// The Groq API only supports `Json`, `Text` and `VerboseJson`; use `Transcript::subtitles` to
+/// build subtitles from the segments of a `VerboseJson` transcription instead.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum TranscriptFormat {
+    #[default]
+    Json,
+    Text,
+    Srt,
+    Vtt,
+    VerboseJson,
+}
+
+impl TranscriptFormat {
+    /// Returns the format as sent to the API.
+    pub fn as_str(&self) -> &str {
+        match self {
+            TranscriptFormat::Json => "json",
+            TranscriptFormat::Text => "text",
+            TranscriptFormat::Srt => "srt",
+            TranscriptFormat::Vtt => "vtt",
+            TranscriptFormat::VerboseJson => "verbose_json",
+        }
+    }
+
+    /// Returns whether responses in this format are JSON objects.
+    pub fn is_json(&self) -> bool {
+        matches!(self, TranscriptFormat::Json | TranscriptFormat::VerboseJson)
+    }
+}
+
+/// Represents a transcription, as returned in the requested `TranscriptFormat`.
+///
+/// - `Json`: The parsed response, for `Json` and `VerboseJson`.
+/// - `Text`: The text, for `Text`.
+/// - `Subtitles`: The subtitles as sent by the server, for `Srt` and `Vtt`.
+#[derive(Debug, Clone)]
+pub enum Transcript {
+    Json(SpeechToTextResponse),
+    Text(String),
+    Subtitles {
+        format: TranscriptFormat,
+        content: String,
+    },
+}
+
+impl Transcript {
+    /// Returns the transcribed text, without the timings of subtitles.
+    pub fn text(&self) -> String {
+        match self {
+            Transcript::Json(response) => response.text.clone(),
+            Transcript::Text(text) => text.trim().to_string(),
+            Transcript::Subtitles { content, .. } => subtitle_text(content),
+        }
+    }
+
+    /// Returns subtitles in `format`, as sent by the server or built from the segments of a
+    /// `VerboseJson` transcription.
+    ///
+    /// Returns `None` if `format` is not `Srt` or `Vtt`, or if the transcription holds neither
+    /// subtitles in that format nor segments.
+    pub fn subtitles(&self, format: TranscriptFormat) -> Option<String> {
+        match self {
+            Transcript::Subtitles {
+                format: sent,
+                content,
+            } if *sent == format => Some(content.clone()),
+            Transcript::Json(response) => response.subtitles(format),
+            _ => None,
+        }
+    }
+
+    /// Converts the transcription into a `SpeechToTextResponse`, whose `text` holds the subtitles
+    /// as sent for `Srt` and `Vtt`.
+    pub fn into_response(self) -> SpeechToTextResponse {
+        let text = match self {
+            Transcript::Json(response) => return response,
+            Transcript::Text(text) => text.trim().to_string(),
+            Transcript::Subtitles { content, .. } => content,
+        };
+        SpeechToTextResponse {
+            text,
+            language: None,
+            duration: None,
+            segments: None,
+            words: None,
+            rate_limit: None,
+        }
+    }
+}
+
+impl SpeechToTextResponse {
+    /// Builds subtitles in `format` from the segments of a `VerboseJson` transcription, one cue per segment.
+    ///
+    /// Returns `None` if `format` is not `Srt` or `Vtt`, or if no segments were returned.
+    pub fn subtitles(&self, format: TranscriptFormat) -> Option<String> {
+        let segments = self.segments.as_deref()?;
+        match format {
+            TranscriptFormat::Srt => Some(srt(segments)),
+            TranscriptFormat::Vtt => Some(vtt(segments)),
+            _ => None,
+        }
+    }
+}
+
+/// Formats segments as SubRip subtitles.
+fn srt(segments: &[TranscriptionSegment]) -> String {
+    let mut srt = String::new();
+    for (index, segment) in segments.iter().enumerate() {
+        let _ = write!(
+            srt,
+            "{}\n{} --> {}\n{}\n\n",
+            index + 1,
+            timestamp(segment.start, ','),
+            timestamp(segment.end, ','),
+            segment.text.trim()
+        );
+    }
+    srt
+}
+
+/// Formats segments as WebVTT subtitles.
+fn vtt(segments: &[TranscriptionSegment]) -> String {
+    let mut vtt = String::from("WEBVTT\n\n");
+    for segment in segments {
+        let _ = write!(
+            vtt,
+            "{} --> {}\n{}\n\n",
+            timestamp(segment.start, '.'),
+            timestamp(segment.end, '.'),
+            segment.text.trim()
+        );
+    }
+    vtt
+}
+
+/// Formats `seconds` as `HH:MM:SS` followed by `separator` and the milliseconds.
+fn timestamp(seconds: f64, separator: char) -> String {
+    let millis = (seconds.max(0.0) * 1000.0).round() as u64;
+    format!(
+        "{:02}:{:02}:{:02}{}{:03}",
+        millis / 3_600_000,
+        millis / 60_000 % 60,
+        millis / 1000 % 60,
+        separator,
+        millis % 1000
+    )
+}
+
+/// Extracts the text of the cues of SRT or VTT subtitles, joined by spaces.
+fn subtitle_text(content: &str) -> String {
+    let mut lines = Vec::new();
+    let mut in_cue = false;
+    for line in content.lines().map(str::trim) {
+        if line.is_empty() {
+            in_cue = false;
+        } else if line.contains("-->") {
+            in_cue = true;
+        } else if in_cue {
+            lines.push(line);
+        }
+    }
+    lines.join(" ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn segment(start: f64, end: f64, text: &str) -> TranscriptionSegment {
+        TranscriptionSegment {
+            id: 0,
+            seek: 0,
+            start,
+            end,
+            text: text.to_string(),
+            tokens: Vec::new(),
+            temperature: 0.0,
+            avg_logprob: -0.2,
+            compression_ratio: 1.0,
+            no_speech_prob: 0.0,
+        }
+    }
+
+    #[test]
+    fn test_subtitles() {
+        let mut response =
+            Transcript::Text(" Hello there. General Kenobi.\n".to_string()).into_response();
+        assert_eq!(response.text, "Hello there. General Kenobi.");
+        assert!(response.subtitles(TranscriptFormat::Srt).is_none());
+
+        response.segments = Some(vec![
+            segment(0.0, 1.25, " Hello there."),
+            segment(3661.5, 3663.0, " General Kenobi."),
+        ]);
+        let srt = response.subtitles(TranscriptFormat::Srt).unwrap();
+        assert_eq!(
+            srt,
+            "1\n00:00:00,000 --> 00:00:01,250\nHello there.\n\n\
+             2\n01:01:01,500 --> 01:01:03,000\nGeneral Kenobi.\n\n"
+        );
+        let vtt = response.subtitles(TranscriptFormat::Vtt).unwrap();
+        assert_eq!(
+            vtt,
+            "WEBVTT\n\n00:00:00.000 --> 00:00:01.250\nHello there.\n\n\
+             01:01:01.500 --> 01:01:03.000\nGeneral Kenobi.\n\n"
+        );
+        assert!(response.subtitles(TranscriptFormat::Text).is_none());
+
+        // Subtitles sent by the server are kept as is, and their text can still be read.
+        let transcript = Transcript::Subtitles {
+            format: TranscriptFormat::Vtt,
+            content: vtt.clone(),
+        };
+        assert_eq!(transcript.text(), "Hello there. General Kenobi.");
+        assert_eq!(transcript.subtitles(TranscriptFormat::Vtt), Some(vtt));
+        assert!(transcript.subtitles(TranscriptFormat::Srt).is_none());
+        let transcript = Transcript::Json(response);
+        assert_eq!(transcript.subtitles(TranscriptFormat::Srt), Some(srt));
+    }
+}
@@ -0,0 +1,428 @@
+use crate::{GroqError, SpeechToTextRequest};
+use bytes::Bytes;
+use futures_util::stream::{self, Stream, StreamExt};
+use std::fmt;
+use std::io::{self, Cursor, Read};
+use std::path::{Path, PathBuf};
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context, Poll};
+use tokio::io::{AsyncRead, ReadBuf};
+
+/// How much of a streamed file is read up front, enough for the header of a WAV file.
+const HEAD_BYTES: u64 = 4096;
+
+/// The size of the pieces a streamed file is read and sent in.
+const CHUNK_BYTES: usize = 64 * 1024;
+
+type BoxedReader = Pin<Box<dyn AsyncRead + Send>>;
+
+/// Represents the progress of a streamed audio upload.
+///
+/// - `sent`: The number of bytes of the file sent so far.
+/// - `total`: The size of the file, if known.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct UploadProgress {
+    pub sent: u64,
+    pub total: Option<u64>,
+}
+
+/// A callback told the `UploadProgress` of a streamed upload.
+#[derive(Clone)]
+pub(crate) struct ProgressCallback(Arc<dyn Fn(UploadProgress) + Send + Sync>);
+
+impl fmt::Debug for ProgressCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("ProgressCallback")
+    }
+}
+
+/// Where a streamed audio file is read from.
+#[derive(Clone)]
+enum SourceKind {
+    /// A local file, opened again for every attempt.
+    Path(PathBuf),
+    /// An async reader, which can only be read once.
+    Reader(Arc<Mutex<Option<BoxedReader>>>),
+}
+
+/// An audio file read while it is uploaded, rather than held in memory.
+///
+/// - `head`: The first bytes of the file, to sniff its format and read the header of WAV files.
+/// - `len`: The size of the file, if known.
+#[derive(Clone)]
+pub(crate) struct AudioSource {
+    kind: SourceKind,
+    pub head: Vec<u8>,
+    pub len: Option<u64>,
+}
+
+impl fmt::Debug for AudioSource {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut debug = f.debug_struct("AudioSource");
+        match &self.kind {
+            SourceKind::Path(path) => debug.field("path", path),
+            SourceKind::Reader(_) => debug.field("reader", &".."),
+        };
+        debug.field("len", &self.len).finish()
+    }
+}
+
+impl AudioSource {
+    /// Opens the file for an upload.
+    async fn open(&self) -> Result<BoxedReader, GroqError> {
+        match &self.kind {
+            SourceKind::Path(path) => {
+                let file = tokio::fs::File::open(path)
+                    .await
+                    .map_err(|e| read_error(path, e))?;
+                Ok(Box::pin(file))
+            }
+            SourceKind::Reader(reader) => take_reader(reader),
+        }
+    }
+
+    /// Reads the whole file into memory.
+    async fn read_all(&self) -> Result<Vec<u8>, GroqError> {
+        use tokio::io::AsyncReadExt;
+
+        let mut file = Vec::new();
+        self.open()
+            .await?
+            .read_to_end(&mut file)
+            .await
+            .map_err(|e| GroqError::InvalidInput(format!("failed to read the audio: {}", e)))?;
+        Ok(file)
+    }
+}
+
+/// Takes the reader out of a source, which may only happen once.
+fn take_reader(reader: &Mutex<Option<BoxedReader>>) -> Result<BoxedReader, GroqError> {
+    reader
+        .lock()
+        .unwrap_or_else(|e| e.into_inner())
+        .take()
+        .ok_or_else(|| GroqError::InvalidInput("the audio reader was already read".to_string()))
+}
+
+fn read_error(path: &Path, e: io::Error) -> GroqError {
+    GroqError::InvalidInput(format!("failed to read {}: {}", path.display(), e))
+}
+
+/// The audio file of a speech-to-text request created with `SpeechToTextRequest::from_path` or
+/// `from_reader`, streamed into the multipart body instead of being held in `ApiRequest::body`.
+///
+/// The body sent is `ApiRequest::body`, then the file, then the rest of the form.
+#[derive(Debug, Clone)]
+pub struct FileUpload {
+    source: AudioSource,
+    suffix: Vec<u8>,
+    progress: Option<ProgressCallback>,
+}
+
+impl FileUpload {
+    pub(crate) fn new(
+        source: AudioSource,
+        suffix: Vec<u8>,
+        progress: Option<ProgressCallback>,
+    ) -> Self {
+        Self {
+            source,
+            suffix,
+            progress,
+        }
+    }
+
+    /// Returns the size of the file, if known.
+    pub fn file_size(&self) -> Option<u64> {
+        self.source.len
+    }
+
+    /// Returns the form fields sent after the file.
+    pub fn suffix(&self) -> &[u8] {
+        &self.suffix
+    }
+
+    /// Returns whether the upload can be sent again, as files read from a path can but async
+    /// readers cannot. Uploads that cannot are never retried.
+    pub fn is_replayable(&self) -> bool {
+        matches!(self.source.kind, SourceKind::Path(_))
+    }
+
+    /// Builds the body of an attempt, starting with `prefix`.
+    pub(crate) async fn body(&self, prefix: &[u8]) -> Result<reqwest::Body, GroqError> {
+        let reader = self.source.open().await?;
+        let file = ReaderStream {
+            reader,
+            buf: vec![0; CHUNK_BYTES],
+            progress: Progress::new(self.progress.clone(), self.source.len),
+        };
+        let body = stream::iter([Ok(Bytes::copy_from_slice(prefix))])
+            .chain(file)
+            .chain(stream::iter([Ok(Bytes::from(self.suffix.clone()))]));
+        Ok(reqwest::Body::wrap_stream(body))
+    }
+
+    /// Builds the body of an attempt of the blocking client, starting with `prefix`.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidInput` for async readers, which only `AsyncGroqClient` can upload.
+    pub(crate) fn blocking_body(
+        &self,
+        prefix: &[u8],
+    ) -> Result<reqwest::blocking::Body, GroqError> {
+        let SourceKind::Path(path) = &self.source.kind else {
+            return Err(GroqError::InvalidInput(
+                "audio read from an async reader can only be sent with AsyncGroqClient".to_string(),
+            ));
+        };
+        let file = std::fs::File::open(path).map_err(|e| read_error(path, e))?;
+        let file = ProgressReader {
+            reader: file,
+            progress: Progress::new(self.progress.clone(), self.source.len),
+        };
+        let body = Cursor::new(prefix.to_vec())
+            .chain(file)
+            .chain(Cursor::new(self.suffix.clone()));
+        Ok(match self.source.len {
+            Some(len) => reqwest::blocking::Body::sized(
+                body,
+                prefix.len() as u64 + len + self.suffix.len() as u64,
+            ),
+            None => reqwest::blocking::Body::new(body),
+        })
+    }
+}
+
+/// Counts the bytes of a file sent, telling the callback, if any.
+struct Progress {
+    callback: Option<ProgressCallback>,
+    sent: u64,
+    total: Option<u64>,
+}
+
+impl Progress {
+    fn new(callback: Option<ProgressCallback>, total: Option<u64>) -> Self {
+        Self {
+            callback,
+            sent: 0,
+            total,
+        }
+    }
+
+    fn advance(&mut self, n: usize) {
+        self.sent += n as u64;
+        if let Some(callback) = &self.callback {
+            (callback.0)(UploadProgress {
+                sent: self.sent,
+                total: self.total,
+            });
+        }
+    }
+}
+
+/// A stream of the pieces of an async reader.
+struct ReaderStream {
+    reader: BoxedReader,
+    buf: Vec<u8>,
+    progress: Progress,
+}
+
+impl Stream for ReaderStream {
+    type Item = io::Result<Bytes>;
+
+    fn poll_next(self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Option<Self::Item>> {
+        let this = self.get_mut();
+        let mut buf = ReadBuf::new(&mut this.buf);
+        match this.reader.as_mut().poll_read(cx, &mut buf) {
+            Poll::Pending => Poll::Pending,
+            Poll::Ready(Err(e)) => Poll::Ready(Some(Err(e))),
+            Poll::Ready(Ok(())) if buf.filled().is_empty() => Poll::Ready(None),
+            Poll::Ready(Ok(())) => {
+                let piece = Bytes::copy_from_slice(buf.filled());
+                this.progress.advance(piece.len());
+                Poll::Ready(Some(Ok(piece)))
+            }
+        }
+    }
+}
+
+/// A blocking reader reporting its progress.
+struct ProgressReader<R> {
+    reader: R,
+    progress: Progress,
+}
+
+impl<R: Read> Read for ProgressReader<R> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let n = self.reader.read(buf)?;
+        if n > 0 {
+            self.progress.advance(n);
+        }
+        Ok(n)
+    }
+}
+
+impl SpeechToTextRequest {
+    /// Constructs a request streaming the audio file at `path`, so it is never held in memory.
+    ///
+    /// The file is opened again for every attempt, so the request can be retried. Its name is
+    /// used as `file_name`.
+    ///
+    /// # Arguments
+    /// * `path` - The path of the audio file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidInput` if the file cannot be read.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, GroqError> {
+        let path = path.as_ref();
+        let mut file = std::fs::File::open(path).map_err(|e| read_error(path, e))?;
+        let len = file.metadata().map_err(|e| read_error(path, e))?.len();
+        let mut head = Vec::new();
+        (&mut file)
+            .take(HEAD_BYTES)
+            .read_to_end(&mut head)
+            .map_err(|e| read_error(path, e))?;
+        let mut request = Self::new(Vec::new());
+        request.file_name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string());
+        request.source = Some(AudioSource {
+            kind: SourceKind::Path(path.to_path_buf()),
+            head,
+            len: Some(len),
+        });
+        Ok(request)
+    }
+
+    /// Constructs a request streaming the audio file read from `reader`, such as a download or
+    /// a file opened with `tokio::fs`.
+    ///
+    /// The reader can only be read once, so the request is not retried, and only
+    /// `AsyncGroqClient` can send it. Its format is taken from the extension of `file_name`.
+    ///
+    /// # Arguments
+    /// * `reader` - The reader of the audio file.
+    /// * `file_name` - The name the file is uploaded with, such as `memo.m4a`.
+    /// * `len` - The size of the file, if known. Without it the body is sent chunked.
+    pub fn from_reader(
+        reader: impl AsyncRead + Send + 'static,
+        file_name: &str,
+        len: Option<u64>,
+    ) -> Self {
+        let mut request = Self::new(Vec::new()).file_name(file_name);
+        request.source = Some(AudioSource {
+            kind: SourceKind::Reader(Arc::new(Mutex::new(Some(Box::pin(reader))))),
+            head: Vec::new(),
+            len,
+        });
+        request
+    }
+
+    /// Constructs a request for the audio at `url`, which the API downloads itself.
+    ///
+    /// # Arguments
+    /// * `url` - The URL of the audio file.
+    pub fn from_url(url: &str) -> Self {
+        let mut request = Self::new(Vec::new());
+        request.url = Some(url.to_string());
+        request
+    }
+
+    /// Sets a callback told the progress of the upload of a file streamed from a path or a
+    /// reader, after every piece sent. It starts over when a failed request is retried.
+    ///
+    /// # Arguments
+    /// * `callback` - The function called with the `UploadProgress`.
+    ///
+    /// # Returns
+    /// The modified `SpeechToTextRequest` instance with the progress callback.
+    pub fn on_progress(
+        mut self,
+        callback: impl Fn(UploadProgress) + Send + Sync + 'static,
+    ) -> Self {
+        self.progress = Some(ProgressCallback(Arc::new(callback)));
+        self
+    }
+
+    /// Returns the first bytes of the audio file, whole if it is held in memory.
+    pub(crate) fn file_head(&self) -> &[u8] {
+        match &self.source {
+            Some(source) => &source.head,
+            None => &self.file,
+        }
+    }
+
+    /// Returns the size of the audio file, if known.
+    pub(crate) fn file_len(&self) -> Option<u64> {
+        match &self.source {
+            Some(source) => source.len,
+            None => Some(self.file.len() as u64),
+        }
+    }
+
+    /// Reads a streamed audio file into `file`.
+    pub(crate) async fn into_memory(mut self) -> Result<Self, GroqError> {
+        if let Some(source) = self.source.take() {
+            self.file = source.read_all().await?;
+        }
+        Ok(self)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::testing::silent_wav;
+    use futures_util::TryStreamExt;
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_audio_source() {
+        let wav = silent_wav(Duration::from_secs(1));
+        let path = std::env::temp_dir().join(format!("groq-rs-source-{}.wav", std::process::id()));
+        std::fs::write(&path, &wav).unwrap();
+
+        let request = SpeechToTextRequest::from_path(&path).unwrap();
+        assert_eq!(request.file_len(), Some(wav.len() as u64));
+        assert_eq!(request.file_head(), &wav[..HEAD_BYTES as usize]);
+        assert!(request.file_name.as_deref().unwrap().ends_with(".wav"));
+        let loaded = request.clone().into_memory().await.unwrap();
+        assert_eq!(loaded.file, wav);
+        assert!(loaded.source.is_none());
+        // Paths are opened again for every attempt.
+        let upload = FileUpload::new(request.source.clone().unwrap(), b"--end".to_vec(), None);
+        assert!(upload.is_replayable());
+        for _ in 0..2 {
+            let file = upload.source.read_all().await.unwrap();
+            assert_eq!(file, wav);
+        }
+        std::fs::remove_file(&path).unwrap();
+        assert!(SpeechToTextRequest::from_path(&path).is_err());
+
+        // Readers are read once, in pieces, reporting their progress.
+        let sent = Arc::new(Mutex::new(Vec::new()));
+        let seen = sent.clone();
+        let request = SpeechToTextRequest::from_reader(Cursor::new(wav.clone()), "memo.wav", None)
+            .on_progress(move |progress| seen.lock().unwrap().push(progress));
+        let source = request.source.clone().unwrap();
+        let upload = FileUpload::new(source.clone(), Vec::new(), request.progress.clone());
+        assert!(!upload.is_replayable());
+        let stream = ReaderStream {
+            reader: source.open().await.unwrap(),
+            buf: vec![0; CHUNK_BYTES],
+            progress: Progress::new(request.progress.clone(), None),
+        };
+        let pieces: Vec<Bytes> = stream.try_collect().await.unwrap();
+        assert_eq!(pieces.concat(), wav);
+        assert!(matches!(
+            source.read_all().await,
+            Err(GroqError::InvalidInput(_))
+        ));
+        let sent = sent.lock().unwrap();
+        assert_eq!(sent.len(), pieces.len());
+        assert_eq!(sent.last().unwrap().sent, wav.len() as u64);
+    }
+}
@@ -0,0 +1,145 @@
+use crate::{GroqError, ImageUrl};
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::path::Path;
+
+/// The maximum size of an image sent as a base64 data URL, including the `data:` prefix.
+pub const MAX_BASE64_IMAGE_BYTES: usize = 4 * 1024 * 1024;
+
+/// The maximum number of images in a single chat completion request.
+pub const MAX_IMAGES_PER_REQUEST: usize = 5;
+
+/// Represents the image formats accepted by vision models.
+///
+/// - `Png`: A PNG image.
+/// - `Jpeg`: A JPEG image.
+/// - `Webp`: A WebP image.
+/// - `Gif`: A GIF image.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Png,
+    Jpeg,
+    Webp,
+    Gif,
+}
+
+impl ImageFormat {
+    /// Detects the format of an image from its leading bytes, or `None` if it is not supported.
+    pub fn sniff(bytes: &[u8]) -> Option<Self> {
+        if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+            Some(ImageFormat::Png)
+        } else if bytes.starts_with(&[0xFF, 0xD8, 0xFF]) {
+            Some(ImageFormat::Jpeg)
+        } else if bytes.len() >= 12 && &bytes[..4] == b"RIFF" && &bytes[8..12] == b"WEBP" {
+            Some(ImageFormat::Webp)
+        } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+            Some(ImageFormat::Gif)
+        } else {
+            None
+        }
+    }
+
+    /// Returns the MIME type of the format.
+    pub fn mime_type(self) -> &'static str {
+        match self {
+            ImageFormat::Png => "image/png",
+            ImageFormat::Jpeg => "image/jpeg",
+            ImageFormat::Webp => "image/webp",
+            ImageFormat::Gif => "image/gif",
+        }
+    }
+}
+
+impl ImageUrl {
+    /// Creates an image holding `bytes` as a base64 data URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `bytes` - The encoded image, in one of the `ImageFormat`s.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidInput` if the format is not supported, or if the data URL would
+    /// be larger than `MAX_BASE64_IMAGE_BYTES`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, GroqError> {
+        let format = ImageFormat::sniff(bytes).ok_or_else(|| {
+            GroqError::InvalidInput(
+                "unsupported image format, expected PNG, JPEG, WebP or GIF".to_string(),
+            )
+        })?;
+        let prefix = format!("data:{};base64,", format.mime_type());
+        let encoded_len = prefix.len() + bytes.len().div_ceil(3) * 4;
+        if encoded_len > MAX_BASE64_IMAGE_BYTES {
+            return Err(GroqError::InvalidInput(format!(
+                "image is {} bytes once encoded, above the limit of {} bytes",
+                encoded_len, MAX_BASE64_IMAGE_BYTES
+            )));
+        }
+        Ok(Self::new(&format!("{}{}", prefix, STANDARD.encode(bytes))))
+    }
+
+    /// Reads a local image file into a base64 data URL.
+    ///
+    /// # Arguments
+    ///
+    /// * `path` - The path of the image file.
+    ///
+    /// # Errors
+    ///
+    /// Returns `GroqError::InvalidInput` if the file cannot be read, or for the reasons of `from_bytes`.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, GroqError> {
+        let path = path.as_ref();
+        let bytes = std::fs::read(path).map_err(|e| {
+            GroqError::InvalidInput(format!("failed to read {}: {}", path.display(), e))
+        })?;
+        Self::from_bytes(&bytes)
+    }
+
+    /// Returns whether the image is embedded as a data URL rather than fetched by the API.
+    pub fn is_data_url(&self) -> bool {
+        self.url.starts_with("data:")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{ChatCompletionMessage, ChatCompletionRoles, ContentPart};
+
+    const PNG: &[u8] = b"\x89PNG\r\n\x1a\n\0\0\0\rIHDR";
+
+    #[test]
+    fn test_from_bytes() {
+        let image = ImageUrl::from_bytes(PNG).unwrap();
+        assert_eq!(image.url, "data:image/png;base64,iVBORw0KGgoAAAANSUhEUg==");
+        assert!(image.is_data_url());
+
+        assert!(matches!(
+            ImageUrl::from_bytes(b"not an image"),
+            Err(GroqError::InvalidInput(_))
+        ));
+        let mut large = PNG.to_vec();
+        large.resize(MAX_BASE64_IMAGE_BYTES, 0);
+        assert!(ImageUrl::from_bytes(&large).is_err());
+    }
+
+    #[test]
+    fn test_serialize_parts() {
+        let message = ChatCompletionMessage::parts(
+            ChatCompletionRoles::User,
+            vec![
+                ContentPart::text("What is this?"),
+                ContentPart::image(ImageUrl::new("https://example.com/a.png")),
+            ],
+        );
+        assert_eq!(
+            serde_json::to_value(&message.content).unwrap(),
+            serde_json::json!([
+                { "type": "text", "text": "What is this?" },
+                { "type": "image_url", "image_url": { "url": "https://example.com/a.png" } }
+            ])
+        );
+        assert_eq!(message.content.text(), "What is this?");
+        assert_eq!(message.content.images().len(), 1);
+    }
+}
@@ -1,22 +1,98 @@
 use hound::{SampleFormat};
 use std::io::Cursor;
 use groq_api_rust::{
-  AsyncGroqClient, 
-  SpeechToTextRequest
+  models,
+  ApiRequest, ApiResponse, AsyncGroqClient, AuthScheme, CancellationToken, ChunkingOptions, GroqError, Hook,
+  OpenAiCompatibleClient, RequestInfo, RequestOptions, ResponseCache, SpeechToTextProvider,
+  SpeechToTextRequest, UploadProgress
 };
-use std::env;
-use tokio::sync::OnceCell;
-
-static GROQ_CLIENT: OnceCell<AsyncGroqClient> = OnceCell::const_new();
-async fn get_client() -> &'static AsyncGroqClient {
-  GROQ_CLIENT
-      .get_or_init(|| async {
-          let api_key = env::var("GROQ_API_KEY").expect("GROQ_API_KEY env variable not set");
-          AsyncGroqClient::new(api_key.to_string(), None).await
-      })
-      .await
+use tauri::{AppHandle, Emitter};
+use once_cell::sync::OnceCell;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Logs the outcome of every Groq API request through the `log` crate.
+struct LogHook;
+
+impl Hook for LogHook {
+  fn on_response(&self, request: &ApiRequest, response: &mut ApiResponse, info: &RequestInfo) {
+      log::info!(
+          "{} {} -> {} in {:?} ({} attempt(s))",
+          request.method, request.path, response.status, info.elapsed, info.attempts
+      );
+  }
+
+  fn on_error(&self, request: &ApiRequest, error: &GroqError, info: &RequestInfo) {
+      log::error!(
+          "{} {} failed after {:?} ({} attempt(s)): {}",
+          request.method, request.path, info.elapsed, info.attempts, error
+      );
+  }
+}
+
+/// The environment variable holding the directory of the on-disk response cache, off when unset.
+const CACHE_DIR_ENV: &str = "GROQ_CACHE_DIR";
+
+/// The environment variable holding the URL of an OpenAI-compatible speech-to-text server, such
+/// as a local whisper.cpp server, used instead of Groq when set.
+const STT_BASE_URL_ENV: &str = "STT_BASE_URL";
+
+/// The environment variable holding the API key of that server, if it needs one.
+const STT_API_KEY_ENV: &str = "STT_API_KEY";
+
+/// The environment variable set to `api-key` when that server expects the key in an `api-key`
+/// header rather than as a bearer token.
+const STT_AUTH_ENV: &str = "STT_AUTH";
+
+/// The environment variable holding the name that server gives to the Whisper model.
+const STT_MODEL_ENV: &str = "STT_MODEL";
+
+static STT_PROVIDER: OnceCell<Arc<dyn SpeechToTextProvider>> = OnceCell::new();
+fn get_provider() -> Result<&'static dyn SpeechToTextProvider, String> {
+  // Reads GROQ_API_KEY and GROQ_BASE_URL, or the STT_* variables, from the environment (loaded from .env in main).
+  STT_PROVIDER.get_or_try_init(|| {
+      let mut builder = AsyncGroqClient::builder()
+          .connect_timeout(Duration::from_secs(10))
+          .timeout(Duration::from_secs(60))
+          .hook(Arc::new(LogHook));
+      // Replaying the same recordings during development is served from disk when this is set.
+      if let Ok(dir) = std::env::var(CACHE_DIR_ENV) {
+          builder = builder.response_cache(Arc::new(ResponseCache::new(dir)));
+      }
+      let Ok(base_url) = std::env::var(STT_BASE_URL_ENV) else {
+          let client = builder.build_async().map_err(|e| e.to_string())?;
+          return Ok(Arc::new(client) as Arc<dyn SpeechToTextProvider>);
+      };
+      log::info!("Transcribing with the server at {}", base_url);
+      let auth = match std::env::var(STT_API_KEY_ENV) {
+          Ok(key) if std::env::var(STT_AUTH_ENV).as_deref() == Ok("api-key") => AuthScheme::ApiKey(key),
+          Ok(key) => AuthScheme::Bearer(key),
+          Err(_) => AuthScheme::None,
+      };
+      builder = builder.endpoint(&base_url).auth(auth);
+      if let Ok(model) = std::env::var(STT_MODEL_ENV) {
+          builder = builder.model_alias(models::WHISPER_LARGE_V3, &model);
+      }
+      let client = builder.build_async().map_err(|e| e.to_string())?;
+      Ok(Arc::new(OpenAiCompatibleClient::from_client(client)))
+  }).map(|provider| provider.as_ref())
 }
 
+/// The id and token of the transcription in flight, cancelled when it is superseded.
+static CURRENT_TRANSCRIPTION: Mutex<Option<(u64, CancellationToken)>> = Mutex::new(None);
+
+/// The id of the next transcription, telling apart the one in flight from those it superseded.
+static NEXT_TRANSCRIPTION_ID: AtomicU64 = AtomicU64::new(0);
+
+/// Abandons the transcription in flight, if any, when a new recording starts or the window closes.
+#[tauri::command]
+pub fn cancel_transcription() {
+  if let Some((_, token)) = CURRENT_TRANSCRIPTION.lock().unwrap().take() {
+      log::info!("Cancelling the transcription in flight");
+      token.cancel();
+  }
+}
 
 #[tauri::command]
 pub fn process_audio(audio: Vec<f32>) -> Result<Vec<u8>, String> {
@@ -46,38 +122,97 @@ pub fn process_audio(audio: Vec<f32>) -> Result<Vec<u8>, String> {
 pub async fn transcribe(audio: Vec<f32>) -> Result<String, String> {
   // No need for a separate runtime or block_on
 
-  println!("Processing audio..."); // Log progress
-                                   // Process the audio to create WAV data in memory
+  // Process the audio to create WAV data in memory
   let wav_data = process_audio(audio)?;
-  println!("Audio processed, WAV size: {} bytes", wav_data.len()); // Log size
+  log::debug!("Audio processed, WAV size: {} bytes", wav_data.len());
 
   // Create the speech-to-text request
   let request = SpeechToTextRequest::new(wav_data)
-      .temperature(0.7) // Optional: configure as needed
       .language("en") // Optional: configure as needed
-      .model("whisper-large-v3"); // Ensure this model is supported by Groq STT
+      .model(models::WHISPER_LARGE_V3); // Ensure this model is supported by Groq STT
+
+  run_transcription(request).await
+}
+
+/// The event carrying the `UploadProgress` of a file being transcribed.
+const UPLOAD_PROGRESS_EVENT: &str = "transcription-upload-progress";
+
+/// Transcribes an imported audio file, streamed from disk rather than loaded into memory,
+/// emitting its upload progress to the UI.
+#[tauri::command]
+pub async fn transcribe_file(app: AppHandle, path: String) -> Result<String, String> {
+  let request = SpeechToTextRequest::from_path(&path)
+      .map_err(|e| e.to_string())?
+      .model(models::WHISPER_LARGE_V3)
+      .on_progress(move |progress: UploadProgress| {
+          let _ = app.emit(UPLOAD_PROGRESS_EVENT, (progress.sent, progress.total));
+      });
+  log::debug!("Transcribing the file {}", path);
+
+  run_transcription(request).await
+}
+
+/// Sends `request` to the shared provider, superseding any transcription in flight.
+async fn run_transcription(request: SpeechToTextRequest) -> Result<String, String> {
+  // Get the shared provider, Groq or a local server, which logs every request through `LogHook`
+  let provider = get_provider()?;
 
-  println!("Getting Groq client..."); // Log progress
-                                      // Get the shared Groq client instance
-  let client = get_client().await;
+  // Only the latest transcription matters, so abandon any previous one still in flight
+  let token = CancellationToken::new();
+  let id = NEXT_TRANSCRIPTION_ID.fetch_add(1, Ordering::Relaxed);
+  if let Some((_, previous)) = CURRENT_TRANSCRIPTION.lock().unwrap().replace((id, token.clone())) {
+      previous.cancel();
+  }
+  let options = RequestOptions::new().cancellation(token);
+  // Long recordings are split at pauses and transcribed a few chunks at a time
+  let result = provider
+      .transcribe_chunked(request, &ChunkingOptions::default(), &options)
+      .await;
 
-  println!("Sending request to Groq API..."); // Log progress
-                                              // Execute the SYNCHRONOUS API request
-                                              // This will block the current Tauri command thread, which is acceptable.
-  let result = client.speech_to_text(request).await;
+  // Nothing is in flight anymore, unless a newer transcription has taken the slot
+  let mut current = CURRENT_TRANSCRIPTION.lock().unwrap();
+  if matches!(*current, Some((current_id, _)) if current_id == id) {
+      *current = None;
+  }
+  drop(current);
 
   // Handle the Result
   match result {
       Ok(response) => {
-          println!("Groq API Success. Transcription: {}", response.text); // Log success and result
+          log::debug!("Transcription: {}", response.text);
           Ok(response.text)
       }
+      Err(GroqError::Cancelled) => Err("Transcription cancelled".to_string()),
       Err(e) => {
-          eprintln!("Groq API Error: {:?}", e); // Log the full error
-                                                // Try to provide a more specific error message if possible
-                                                // For example, if e has a method to get status code or message:
-                                                // Err(format!("Failed to get response from Groq: {} - {}", e.status(), e.message()))
-          Err(format!("Failed to get response from Groq: {}", e))
+          log::error!("Speech-to-text API Error: {:?}", e);
+          Err(format!("Failed to get a transcription: {}", e))
       }
   }
 }
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use groq_api_rust::testing::{MockGroqServer, MOCK_TEXT};
+
+  #[test]
+  fn test_transcribe_against_mock_server() {
+      let server = MockGroqServer::start();
+      // The shared client is built on first use, from the environment, so settings of the
+      // developer's machine that would send it to another server or a stale cache are removed.
+      for name in [CACHE_DIR_ENV, STT_BASE_URL_ENV, STT_API_KEY_ENV, STT_AUTH_ENV, STT_MODEL_ENV] {
+          std::env::remove_var(name);
+      }
+      std::env::set_var(groq_api_rust::API_KEY_ENV, "test_key");
+      std::env::set_var(groq_api_rust::BASE_URL_ENV, server.endpoint());
+
+      let text = tauri::async_runtime::block_on(transcribe(vec![0.0; 16000])).unwrap();
+      assert_eq!(text, MOCK_TEXT);
+      // A finished transcription is no longer cancelled by the next recording.
+      assert!(CURRENT_TRANSCRIPTION.lock().unwrap().is_none());
+
+      let requests = server.requests();
+      assert_eq!(requests[0].path, "/audio/transcriptions");
+      assert_eq!(requests[0].form_text("model").as_deref(), Some(models::WHISPER_LARGE_V3));
+  }
+}
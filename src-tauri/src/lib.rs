@@ -1,6 +1,6 @@
 mod audio;
 
-use audio::transcribe;
+use audio::{cancel_transcription, transcribe, transcribe_file};
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
 pub fn run() {
@@ -15,7 +15,13 @@ pub fn run() {
       }
       Ok(())
     })
-    .invoke_handler(tauri::generate_handler![transcribe])
+    .on_window_event(|_window, event| {
+      // Do not keep a transcription running for a window that is going away.
+      if let tauri::WindowEvent::CloseRequested { .. } = event {
+        cancel_transcription();
+      }
+    })
+    .invoke_handler(tauri::generate_handler![transcribe, transcribe_file, cancel_transcription])
     .run(tauri::generate_context!())
     .expect("error while running tauri application");
 }
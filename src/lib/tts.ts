@@ -20,6 +20,8 @@ export function useVoiceRecorder(): UseVoiceRecorderResult {
       return;
     }
     setError(null);
+    // A new recording supersedes any transcription still in flight.
+    invoke("cancel_transcription").catch(() => {});
     try {
       const stream = await navigator.mediaDevices.getUserMedia({ audio: true });
       const mediaRecorder = new MediaRecorder(stream, {